@@ -0,0 +1,32 @@
+//! Fits the table for uplifting RGB colors to spectra, which is included in the binary by
+//! `RgbToSpectrumTable`. Fitting takes a while, so the build script is optimized, see
+//! `Cargo.toml`.
+
+use std::{env, fs, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/flux/color.rs"]
+mod color;
+#[path = "build/rgb_to_spectrum.rs"]
+mod rgb_to_spectrum;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build/rgb_to_spectrum.rs");
+    println!("cargo:rerun-if-changed=src/flux/color.rs");
+
+    // the resolution, the nodes of the largest channel and the coefficients, in little endian
+    let (z_nodes, coefficients) = rgb_to_spectrum::fit();
+    let mut bytes = (rgb_to_spectrum::TABLE_RESOLUTION as u32)
+        .to_le_bytes()
+        .to_vec();
+    for value in z_nodes
+        .into_iter()
+        .chain(coefficients.into_iter().flat_map(|c| c.to_array()))
+    {
+        bytes.extend(value.to_le_bytes());
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("rgb_to_spectrum.bin"), bytes).unwrap();
+}
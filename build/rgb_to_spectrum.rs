@@ -0,0 +1,190 @@
+//! Fits the table of `RgbToSpectrumTable` when building, see `build.rs`.
+
+use glam::{DMat3, DVec3, Vec3};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use crate::color::{cie_xyz, xyz_to_srgb, VISIBLE_WAVELENGTHS};
+
+/// The resolution of the table along each of its axes.
+pub const TABLE_RESOLUTION: usize = 48;
+/// The spacing in nm of the wavelengths at which spectra are integrated while fitting.
+const FIT_WAVELENGTH_STEP: f32 = 10.0;
+const FIT_ITERATIONS: usize = 15;
+
+/// Fits the polynomials of all nodes, starting from a medium brightness and reusing the
+/// solution of the previous node as the initial guess while moving towards black and white.
+/// Returns the nodes of the largest channel and the coefficients, indexed like `table_index`.
+pub fn fit() -> (Vec<f32>, Vec<Vec3>) {
+    let n = TABLE_RESOLUTION;
+    let smoothstep = |x: f32| x * x * (3.0 - 2.0 * x);
+    let z_nodes: Vec<f32> = (0..n)
+        .map(|k| smoothstep(smoothstep(k as f32 / (n - 1) as f32)))
+        .collect();
+
+    let fitter = Fitter::new();
+    let start = n / 5;
+    // every row of colors with the same largest channel and ratio y is fitted on its own
+    let rows: Vec<Vec<Vec3>> = (0..3 * n)
+        .into_par_iter()
+        .map(|row| {
+            let (max_channel, yi) = (row / n, row % n);
+            let y = yi as f32 / (n - 1) as f32;
+            let mut coefficients = vec![Vec3::ZERO; n * n];
+            for xi in 0..n {
+                let x = xi as f32 / (n - 1) as f32;
+                let mut fit = |zi: usize, c: Vec3| {
+                    let z = z_nodes[zi];
+                    let mut rgb = Vec3::ZERO;
+                    rgb[max_channel] = z;
+                    rgb[(max_channel + 1) % 3] = x * z;
+                    rgb[(max_channel + 2) % 3] = y * z;
+                    let c = fitter.gauss_newton(rgb, c);
+                    coefficients[zi * n + xi] = c;
+                    c
+                };
+
+                (start..n).fold(Vec3::ZERO, |c, zi| fit(zi, c));
+                (0..start).rev().fold(Vec3::ZERO, |c, zi| fit(zi, c));
+            }
+            coefficients
+        })
+        .collect();
+
+    let mut coefficients = vec![Vec3::ZERO; 3 * n * n * n];
+    for (row, row_coefficients) in rows.iter().enumerate() {
+        let (max_channel, yi) = (row / n, row % n);
+        for zi in 0..n {
+            for xi in 0..n {
+                coefficients[table_index(max_channel, zi, yi, xi)] = row_coefficients[zi * n + xi];
+            }
+        }
+    }
+
+    (z_nodes, coefficients)
+}
+
+/// Keeps the sigmoid from saturating, where its derivatives vanish.
+fn clamp_coefficients(c: DVec3) -> DVec3 {
+    let max = c.abs().max_element();
+    if max > 200.0 {
+        c * (200.0 / max)
+    } else {
+        c
+    }
+}
+
+fn table_index(max_channel: usize, zi: usize, yi: usize, xi: usize) -> usize {
+    let n = TABLE_RESOLUTION;
+    ((max_channel * n + zi) * n + yi) * n + xi
+}
+
+/// Compares the colors of candidate spectra with the targets in CIELAB, relative to the equal
+/// energy white that a constant spectrum of 1 has. Fitting needs double precision for the
+/// saturated colors, whose sigmoids are steep.
+struct Fitter {
+    /// The normalized wavelengths and color matching functions to integrate over.
+    samples: Vec<(f64, DVec3)>,
+    y_integral: f64,
+    white: DVec3,
+    srgb_to_xyz: DMat3,
+}
+
+impl Fitter {
+    fn new() -> Self {
+        let (start, end) = VISIBLE_WAVELENGTHS;
+        let steps = ((end - start) / FIT_WAVELENGTH_STEP) as usize;
+        let samples: Vec<(f64, DVec3)> = (0..=steps)
+            .map(|i| {
+                let lambda = start + i as f32 * FIT_WAVELENGTH_STEP;
+                let t = (lambda - start) / (end - start);
+                (t as f64, cie_xyz(lambda).as_dvec3())
+            })
+            .collect();
+        let y_integral = samples.iter().map(|(_, cmf)| cmf.y).sum();
+        let white = samples.iter().map(|(_, cmf)| *cmf).sum::<DVec3>() / y_integral;
+
+        let xyz_to_srgb_matrix = DMat3::from_cols(
+            xyz_to_srgb(Vec3::X).as_dvec3(),
+            xyz_to_srgb(Vec3::Y).as_dvec3(),
+            xyz_to_srgb(Vec3::Z).as_dvec3(),
+        );
+
+        Self {
+            samples,
+            y_integral,
+            white,
+            srgb_to_xyz: xyz_to_srgb_matrix.inverse(),
+        }
+    }
+
+    fn xyz(&self, c: DVec3) -> DVec3 {
+        self.samples
+            .iter()
+            .map(|&(t, cmf)| {
+                let x = (c.x * t + c.y) * t + c.z;
+                (0.5 + x / (2.0 * (1.0 + x * x).sqrt())) * cmf
+            })
+            .sum::<DVec3>()
+            / self.y_integral
+    }
+
+    fn lab(&self, xyz: DVec3) -> DVec3 {
+        let f = |t: f64| {
+            const DELTA: f64 = 6.0 / 29.0;
+            if t > DELTA.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        };
+        let [fx, fy, fz] = (xyz / self.white).to_array().map(f);
+        DVec3::new(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+
+    fn residual(&self, c: DVec3, target_lab: DVec3) -> DVec3 {
+        target_lab - self.lab(self.xyz(c))
+    }
+
+    /// Refines the coefficients `c` of the spectrum for the linear sRGB color `rgb`.
+    fn gauss_newton(&self, rgb: Vec3, c: Vec3) -> Vec3 {
+        // the constant spectrum of 1 is white, so the white point scales the color first
+        let white_rgb = self.srgb_to_xyz.inverse() * self.white;
+        let target_lab = self.lab(self.srgb_to_xyz * (rgb.as_dvec3() * white_rgb));
+
+        let mut c = c.as_dvec3();
+        for _ in 0..FIT_ITERATIONS {
+            let r = self.residual(c, target_lab);
+
+            const EPSILON: f64 = 1e-5;
+            let jacobian = DMat3::from_cols_array_2d(&[0, 1, 2].map(|i| {
+                let mut offset = DVec3::ZERO;
+                offset[i] = EPSILON;
+                let derivative = (self.residual(c + offset, target_lab)
+                    - self.residual(c - offset, target_lab))
+                    / (2.0 * EPSILON);
+                derivative.to_array()
+            }));
+            if jacobian.determinant().abs() < 1e-15 {
+                break;
+            }
+
+            // Colors near the edge of the gamut can't be reached exactly and the full steps
+            // overshoot there, so they are shortened until the residual decreases.
+            let step = jacobian.inverse() * r;
+            let error = r.length_squared();
+            let Some(next) = (0..16)
+                .map(|i| clamp_coefficients(c - step * 0.5f64.powi(i)))
+                .find(|&next| self.residual(next, target_lab).length_squared() < error)
+            else {
+                break;
+            };
+            c = next;
+
+            if error < 1e-6 {
+                break;
+            }
+        }
+
+        c.as_vec3()
+    }
+}
@@ -0,0 +1,22 @@
+#!/bin/bash
+# Compares the noise of the light sampling strategies against a high sample count reference.
+set -e
+
+scene=${1:-manyspheresemissive}
+out_dir=./output/bench-light-samplers
+
+cargo build --profile release
+mkdir -p "$out_dir/reference"
+
+echo "Rendering reference for $scene"
+RUST_LOG=info target/release/flux --scene "$scene" --sweeps 16 --spp 16 --aux-spp 1 \
+	--light-sampler tree --out-dir "$out_dir/reference"
+
+for sampler in uniform power tree; do
+	echo "Rendering $scene with the $sampler light sampler"
+	mkdir -p "$out_dir/$sampler"
+	RUST_LOG=info target/release/flux --scene "$scene" --sweeps 1 --spp 4 --aux-spp 1 \
+		--light-sampler "$sampler" --out-dir "$out_dir/$sampler" \
+		--reference "$out_dir/reference/output-raw.exr" 2>&1 |
+		grep -E "render finished|RMSE"
+done
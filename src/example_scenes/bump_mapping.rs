@@ -0,0 +1,71 @@
+use std::{f32::consts::PI, rc::Rc};
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    interaction::Interaction,
+    textures::{CheckerTexture, ConstantTexture, NoiseTexture, Texture},
+    CoatedMaterial, ConductorMaterial, DielectricMaterial, Material, MatteMaterial, Metal,
+    PrincipledMaterial, Scene,
+};
+
+use super::util::{gallery, gallery_sphere};
+
+/// Bump maps of increasing strength on white spheres in front, the same noise on different
+/// materials in the middle, and a tangent space normal map of waves behind.
+pub fn bump_mapping() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+
+    let white: Rc<dyn Material> = Rc::new(MatteMaterial::new(color(Vec3::splat(0.8))));
+    let materials: Vec<Rc<dyn Material>> = vec![
+        Rc::new(PrincipledMaterial::new(color(vec3(0.6, 0.1, 0.1))).with_roughness(constant(0.3))),
+        Rc::new(ConductorMaterial::from_metal(Metal::Gold).with_roughness(constant(0.15))),
+        Rc::new(CoatedMaterial::new(Rc::new(MatteMaterial::new(color(
+            vec3(0.1, 0.2, 0.5),
+        ))))),
+        Rc::new(DielectricMaterial::new(color(Vec3::ONE), 1.5)),
+        Rc::new(ConductorMaterial::from_metal(Metal::Silver)),
+    ];
+
+    let fine_noise: Rc<dyn Texture<f32>> = Rc::new(NoiseTexture::new(0.01));
+    let coarse_noise: Rc<dyn Texture<f32>> = Rc::new(NoiseTexture::new(0.05));
+    let tiles: Rc<dyn Texture<f32>> =
+        Rc::new(CheckerTexture::new(0.1, constant(0.0), constant(1.0)));
+
+    let mut primitives = vec![
+        gallery_sphere(0, 0, white.clone()),
+        gallery_sphere(0, 1, white.clone()).with_bump_map(coarse_noise.clone(), 0.002),
+        gallery_sphere(0, 2, white.clone()).with_bump_map(coarse_noise, 0.01),
+        gallery_sphere(0, 3, white.clone()).with_bump_map(fine_noise.clone(), 0.002),
+        gallery_sphere(0, 4, white.clone()).with_bump_map(tiles, 0.0005),
+    ];
+
+    let waves = Rc::new(WavesTexture {
+        frequency: 24.0,
+        amplitude: 0.5,
+    });
+    for (column, material) in materials.into_iter().enumerate() {
+        primitives.push(
+            gallery_sphere(1, column, material.clone()).with_bump_map(fine_noise.clone(), 0.002),
+        );
+        primitives.push(gallery_sphere(2, column, material).with_normal_map(waves.clone()));
+    }
+
+    gallery(primitives)
+}
+
+/// The tangent space normals of sine waves along u, encoded like a normal map image.
+struct WavesTexture {
+    frequency: f32,
+    amplitude: f32,
+}
+
+impl Texture<Vec3> for WavesTexture {
+    fn evaluate(&self, int: &Interaction) -> Vec3 {
+        let uv = int.primitive.shape.uv(int.p, int.face_idx);
+        let slope = self.amplitude * (2.0 * PI * self.frequency * uv.x).cos();
+        let n = vec3(-slope, 0.0, 1.0).normalize();
+        (n + Vec3::ONE) / 2.0
+    }
+}
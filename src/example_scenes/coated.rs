@@ -0,0 +1,61 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    textures::ConstantTexture, CoatedMaterial, ConductorMaterial, Material, MatteMaterial, Metal,
+    PrincipledMaterial, Scene,
+};
+
+use super::util::material_gallery;
+
+/// Red plastic under coatings of increasing roughness, car paints with metallic bases, and a row
+/// of amber varnish of increasing thickness over a white base.
+pub fn coated() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+
+    let red: Rc<dyn Material> = Rc::new(MatteMaterial::new(color(vec3(0.7, 0.05, 0.05))));
+    let plastic = [0.0, 0.1, 0.2, 0.35, 0.5]
+        .into_iter()
+        .map(|roughness| -> Rc<dyn Material> {
+            Rc::new(CoatedMaterial::new(red.clone()).with_roughness(constant(roughness)))
+        })
+        .collect();
+
+    let metallic_paint = |base_color: Vec3| -> Rc<dyn Material> {
+        let base = PrincipledMaterial::new(color(base_color))
+            .with_metallic(constant(1.0))
+            .with_roughness(constant(0.45));
+        Rc::new(CoatedMaterial::new(Rc::new(base)))
+    };
+    let coated_metal = |metal: Metal, roughness: f32| -> Rc<dyn Material> {
+        let base = ConductorMaterial::from_metal(metal).with_roughness(constant(roughness));
+        Rc::new(CoatedMaterial::new(Rc::new(base)))
+    };
+    let paints = vec![
+        metallic_paint(vec3(0.05, 0.15, 0.6)),
+        metallic_paint(vec3(0.6, 0.6, 0.62)),
+        coated_metal(Metal::Copper, 0.3),
+        coated_metal(Metal::Gold, 0.0),
+        Rc::new(
+            CoatedMaterial::new(Rc::new(MatteMaterial::new(color(vec3(0.1, 0.4, 0.1)))))
+                .with_ior(1.33)
+                .with_roughness(constant(0.25)),
+        ),
+    ];
+
+    let white: Rc<dyn Material> = Rc::new(MatteMaterial::new(color(Vec3::splat(0.8))));
+    let varnish = [0.005, 0.01, 0.02, 0.04, 0.08]
+        .into_iter()
+        .map(|thickness| -> Rc<dyn Material> {
+            Rc::new(
+                CoatedMaterial::new(white.clone())
+                    .with_thickness(thickness)
+                    .with_absorption(vec3(0.9, 0.55, 0.2), 0.02),
+            )
+        })
+        .collect();
+
+    material_gallery(vec![plastic, paints, varnish])
+}
@@ -0,0 +1,41 @@
+use std::rc::Rc;
+
+use crate::flux::{textures::ConstantTexture, ConductorMaterial, Material, Metal, Scene};
+
+use super::util::material_gallery;
+
+/// The conductor presets, polished in front and rough behind, and brushed aluminium with
+/// increasing anisotropy in the back row.
+pub fn conductors() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let metals = [
+        Metal::Gold,
+        Metal::Copper,
+        Metal::Aluminium,
+        Metal::Silver,
+        Metal::Chrome,
+    ];
+
+    let with_roughness = |roughness: f32| -> Vec<Rc<dyn Material>> {
+        metals
+            .iter()
+            .map(|&metal| -> Rc<dyn Material> {
+                Rc::new(ConductorMaterial::from_metal(metal).with_roughness(constant(roughness)))
+            })
+            .collect()
+    };
+
+    let brushed = (0..5)
+        .map(|column| -> Rc<dyn Material> {
+            let stretch = column as f32 / 4.0;
+            Rc::new(
+                ConductorMaterial::from_metal(Metal::Aluminium).with_anisotropic_roughness(
+                    constant(0.3 * (1.0 - 0.8 * stretch)),
+                    constant(0.3 * (1.0 + stretch)),
+                ),
+            )
+        })
+        .collect();
+
+    material_gallery(vec![with_roughness(0.0), with_roughness(0.3), brushed])
+}
@@ -1,11 +1,11 @@
 use std::rc::Rc;
 
-use glam::{vec3, Affine3A, Quat, Vec3};
+use glam::{uvec2, vec3, Affine3A, Quat, Vec3};
 
 use crate::flux::{
     shapes::{QuadBox, Sphere, SubdivisionMesh, Transform},
     textures::ConstantTexture,
-    DielectricMaterial, DiffuseLightMaterial, MetalMaterial, Primitive, Scene,
+    DielectricMaterial, DiffuseLightMaterial, MetalMaterial, OrthographicCamera, Primitive, Scene,
 };
 
 use super::util::{build_matte_constant, cornell_box_camera, empty_cornell_box_prims, load_ply};
@@ -35,6 +35,29 @@ pub fn simple_cornell_box() -> Scene {
     Scene::new(camera, aggregate, lights)
 }
 
+pub fn cornell_box_elevation() -> Scene {
+    let box_size = 100.0;
+    let camera = {
+        let resolution = uvec2(1024, 1024);
+        let look_from = vec3(0.0, 0.0, -1.7 * box_size);
+        Box::new(OrthographicCamera::new(
+            resolution,
+            look_from,
+            Vec3::ZERO,
+            box_size,
+            0.0,
+            look_from.length(),
+        ))
+    };
+
+    let mut aggregate = empty_cornell_box_prims(box_size);
+    aggregate.append(&mut build_box_prims(box_size));
+
+    let lights = vec![];
+
+    Scene::new(camera, aggregate, lights)
+}
+
 fn build_box_prims(box_size: f32) -> Vec<Primitive> {
     let white_mat = build_matte_constant(Vec3::splat(0.73));
 
@@ -0,0 +1,113 @@
+use std::rc::Rc;
+
+use glam::{vec3, Quat, Vec3};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::flux::{
+    shapes::Quad,
+    textures::{
+        AlphaTexture, CheckerTexture, ConstantTexture, ImageTexture, NoiseTexture, Texture,
+    },
+    Material, MatteMaterial, Primitive, PrincipledMaterial, Scene,
+};
+
+use super::util::{gallery, gallery_sphere};
+
+/// Leaves cut out of quads by the alpha channel of an image in front, a perforated screen in the
+/// middle, and spheres with partial opacity behind, which let light through at random.
+pub fn cutout() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+
+    let leaf = Rc::new(ImageTexture::new(leaf_image()));
+    let leaf_material: Rc<dyn Material> = Rc::new(MatteMaterial::new(leaf.clone()));
+    let leaf_opacity: Rc<dyn Texture<f32>> = Rc::new(AlphaTexture::new(leaf));
+
+    let mut primitives: Vec<Primitive> = (0..5)
+        .map(|column| {
+            let center = vec3(2.0 - column as f32, 0.5, -2.0);
+            let rotation =
+                Quat::from_rotation_z(0.3 * (column as f32 - 2.0)) * Quat::from_rotation_x(-0.5);
+            Primitive::new(
+                Box::new(Quad::new(square(center, 0.5, rotation))),
+                leaf_material.clone(),
+            )
+            .with_opacity(leaf_opacity.clone())
+        })
+        .collect();
+
+    let screen = {
+        let material = Rc::new(MatteMaterial::new(color(vec3(0.6, 0.45, 0.3))));
+        let holes = Rc::new(CheckerTexture::new(0.025, constant(0.0), constant(1.0)));
+        Primitive::new(
+            Box::new(Quad::new([
+                vec3(2.5, 0.0, -0.5),
+                vec3(-2.5, 0.0, -0.5),
+                vec3(-2.5, 0.6, -0.5),
+                vec3(2.5, 0.6, -0.5),
+            ])),
+            material,
+        )
+        .with_opacity(holes)
+    };
+    primitives.push(screen);
+
+    let opacities: Vec<Option<Rc<dyn Texture<f32>>>> = vec![
+        None,
+        Some(constant(0.75)),
+        Some(constant(0.25)),
+        Some(Rc::new(NoiseTexture::new(0.1))),
+        Some(Rc::new(CheckerTexture::new(
+            0.1,
+            constant(0.0),
+            constant(1.0),
+        ))),
+    ];
+    let red: Rc<dyn Material> =
+        Rc::new(PrincipledMaterial::new(color(vec3(0.6, 0.1, 0.1))).with_roughness(constant(0.3)));
+    for (column, opacity) in opacities.into_iter().enumerate() {
+        let sphere = gallery_sphere(1, column, red.clone());
+        primitives.push(match opacity {
+            Some(opacity) => sphere.with_opacity(opacity),
+            None => sphere,
+        });
+    }
+
+    gallery(primitives)
+}
+
+/// The corners of a square around `center`, rotated from facing the camera, in the order quads
+/// use for their uv coordinates.
+fn square(center: Vec3, half_size: f32, rotation: Quat) -> [Vec3; 4] {
+    [
+        vec3(half_size, -half_size, 0.0),
+        vec3(-half_size, -half_size, 0.0),
+        vec3(-half_size, half_size, 0.0),
+        vec3(half_size, half_size, 0.0),
+    ]
+    .map(|corner| center + rotation * corner)
+}
+
+/// A leaf with a vein along its middle, transparent around it.
+fn leaf_image() -> DynamicImage {
+    let size = 256;
+    let image = RgbaImage::from_fn(size, size, |x, y| {
+        let s = 2.0 * x as f32 / (size - 1) as f32 - 1.0;
+        let t = 2.0 * y as f32 / (size - 1) as f32 - 1.0;
+
+        let width = 0.55 * (1.0 - t * t);
+        if s.abs() > width {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        let shade = 1.0 - 0.4 * (s.abs() / width);
+        let (r, g, b) = if s.abs() < 0.02 {
+            (150.0, 170.0, 60.0)
+        } else {
+            (40.0 * shade, 120.0 * shade, 30.0 * shade)
+        };
+        Rgba([r as u8, g as u8, b as u8, 255])
+    });
+
+    DynamicImage::ImageRgba8(image)
+}
@@ -0,0 +1,46 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    textures::{CheckerTexture, ConstantTexture},
+    DielectricMaterial, Material, Scene, ThinDielectricMaterial,
+};
+
+use super::util::material_gallery;
+
+/// Clear and tinted glass with increasing roughness, and a row with textured roughness, other
+/// indices of refraction, absorbing glass and a thin soap bubble.
+pub fn dielectrics() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let clear = Rc::new(ConstantTexture::new(Vec3::ONE));
+    let tinted = Rc::new(ConstantTexture::new(vec3(0.4, 0.8, 0.5)));
+
+    let with_roughness = |tint: &Rc<ConstantTexture<Vec3>>| -> Vec<Rc<dyn Material>> {
+        [0.0, 0.1, 0.2, 0.35, 0.5]
+            .into_iter()
+            .map(|roughness| -> Rc<dyn Material> {
+                Rc::new(
+                    DielectricMaterial::new(tint.clone(), 1.5).with_roughness(constant(roughness)),
+                )
+            })
+            .collect()
+    };
+
+    let checker_roughness = Rc::new(CheckerTexture::new(0.1, constant(0.0), constant(0.4)));
+    let others: Vec<Rc<dyn Material>> = vec![
+        Rc::new(DielectricMaterial::new(clear.clone(), 1.5).with_roughness(checker_roughness)),
+        Rc::new(DielectricMaterial::new(clear.clone(), 1.33)),
+        Rc::new(DielectricMaterial::new(clear.clone(), 2.42)),
+        Rc::new(
+            DielectricMaterial::new(clear.clone(), 1.5).with_absorption(vec3(0.9, 0.3, 0.1), 0.5),
+        ),
+        Rc::new(ThinDielectricMaterial::new(clear.clone(), 1.5)),
+    ];
+
+    material_gallery(vec![
+        with_roughness(&clear),
+        with_roughness(&tinted),
+        others,
+    ])
+}
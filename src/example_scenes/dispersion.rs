@@ -0,0 +1,77 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    lights::{Light, SunLight, SunPosition},
+    shapes::TriangleMesh,
+    textures::ConstantTexture,
+    DielectricMaterial, Dispersion, Material, Primitive, Scene,
+};
+
+use super::{
+    default_sky_light,
+    util::{gallery_sphere, gallery_with_lights},
+};
+
+/// Plain glass, BK7, fused silica, diamond and dense flint in front, and a flint prism behind,
+/// lit by a low sun from the back so that their caustics fall towards the camera. The rainbows
+/// only appear when rendering with `--spectral`.
+pub fn dispersion() -> Scene {
+    let glass = |dispersion: Option<Dispersion>| -> Rc<dyn Material> {
+        let material = DielectricMaterial::new(Rc::new(ConstantTexture::new(Vec3::ONE)), 1.5);
+        Rc::new(match dispersion {
+            Some(dispersion) => material.with_dispersion(dispersion),
+            None => material,
+        })
+    };
+    let dense_flint = Dispersion::Cauchy {
+        a: 1.7,
+        b: 0.0135,
+        c: 0.0004,
+    };
+
+    let mut primitives: Vec<Primitive> = [
+        None,
+        Some(Dispersion::BK7),
+        Some(Dispersion::FUSED_SILICA),
+        Some(Dispersion::DIAMOND),
+        Some(dense_flint),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(column, dispersion)| gallery_sphere(0, column, glass(dispersion)))
+    .collect();
+
+    primitives.push(Primitive::new(
+        Box::new(prism(vec3(0.0, 0.0, 1.0), 1.2, 0.8, 0.45)),
+        glass(Some(dense_flint)),
+    ));
+
+    let sun: Box<dyn Light> =
+        Box::new(SunLight::new(SunPosition::new(35.0, 10.0), 3.0).with_intensity(2.0));
+    gallery_with_lights(primitives, vec![default_sky_light(), sun])
+}
+
+/// A prism lying along the x axis on the floor at `center`, with a triangular cross section of
+/// the given height and half depth along z.
+fn prism(center: Vec3, half_width: f32, height: f32, half_depth: f32) -> TriangleMesh {
+    let (w, h, d) = (half_width, height, half_depth);
+    let vertices = [
+        vec3(-w, 0.0, -d),
+        vec3(-w, 0.0, d),
+        vec3(-w, h, 0.0),
+        vec3(w, 0.0, -d),
+        vec3(w, 0.0, d),
+        vec3(w, h, 0.0),
+    ]
+    .map(|v| center + v)
+    .to_vec();
+    let indices = vec![
+        0, 1, 2, 3, 5, 4, // sides
+        0, 3, 4, 0, 4, 1, // bottom
+        0, 2, 5, 0, 5, 3, // front
+        1, 4, 5, 1, 5, 2, // back
+    ];
+    TriangleMesh::new(vertices, indices)
+}
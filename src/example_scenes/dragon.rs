@@ -3,8 +3,9 @@ use std::rc::Rc;
 use glam::{uvec2, vec3, Affine3A, Quat, Vec3};
 
 use crate::{
-    example_scenes::util::{build_matte_constant, hdr_light_dome, load_ply},
+    example_scenes::util::{build_matte_constant, load_ply},
     flux::{
+        lights::{EnvironmentLight, Light},
         shapes::{Floor, Sphere, SubdivisionMesh, Transform},
         textures::ConstantTexture,
         DielectricMaterial, DiffuseLightMaterial, PerspectiveCamera, Primitive, Scene,
@@ -28,7 +29,12 @@ pub fn dragon() -> Scene {
 
     let aggregate = build_aggregate();
 
-    let lights = vec![];
+    let light_dome: Box<dyn Light> = Box::new(
+        EnvironmentLight::load("./assets/lightprobes/ennis.exr")
+            .unwrap_or_else(|err| panic!("Failed to load environment light: {}", err))
+            .with_intensity(2.0),
+    );
+    let lights = vec![light_dome];
 
     Scene::new(camera, aggregate, lights)
 }
@@ -67,7 +73,5 @@ fn build_aggregate() -> Vec<Primitive> {
         Primitive::new(shape, mat)
     };
 
-    let light_dome = hdr_light_dome("./assets/lightprobes/ennis.exr");
-
-    vec![floor, dragon, sphere_light, light_dome]
+    vec![floor, dragon, sphere_light]
 }
@@ -0,0 +1,70 @@
+use glam::{uvec2, vec3, Vec3};
+
+use crate::flux::{
+    lights::{
+        DirectionalLight, InfiniteAreaLight, Light, LightLinking, LightProfile, PointLight,
+        SpotLight,
+    },
+    PerspectiveCamera, Scene,
+};
+
+use super::util::material_demo_aggregate;
+
+/// The material demo, lit by a spot, a point and a directional light instead of the sky.
+pub fn lamps() -> Scene {
+    let camera = {
+        let resolution = uvec2(1024, 1024);
+        let look_from = vec3(0.0, 4.0, -8.0);
+        let look_at = vec3(0.0, 2.0, 0.0);
+
+        Box::new(PerspectiveCamera::new(
+            resolution,
+            look_from,
+            look_at,
+            50.0,
+            0.0,
+            look_at.distance(look_from),
+        ))
+    };
+
+    let aggregate = material_demo_aggregate();
+
+    let spot: Box<dyn Light> = {
+        // brighter ring around a dimmer center, like a reflector lamp
+        let profile = LightProfile::new(
+            vec![0.0, 10.0, 20.0, 25.0, 30.0],
+            vec![0.6, 0.8, 1.0, 0.8, 0.3],
+        );
+        let light = SpotLight::new(
+            vec3(0.0, 6.0, -1.0),
+            vec3(0.0, 1.0, 0.0),
+            Vec3::splat(60.0),
+            30.0,
+            20.0,
+        )
+        .with_profile(profile);
+        Box::new(light)
+    };
+
+    let point: Box<dyn Light> =
+        Box::new(PointLight::new(vec3(-3.5, 2.5, -2.0), vec3(8.0, 5.0, 2.0)));
+
+    let moonlight: Box<dyn Light> = Box::new(DirectionalLight::new(
+        vec3(1.0, -1.0, 1.0),
+        vec3(0.15, 0.2, 0.35),
+    ));
+
+    let ambient: Box<dyn Light> = Box::new(InfiniteAreaLight::new(Vec3::splat(0.02)));
+
+    let lights = vec![spot, point, moonlight, ambient];
+
+    let mut scene = Scene::new(camera, aggregate, lights);
+    // the warm point light is a fill light, so it doesn't cast shadows
+    scene.set_light_linking(1, LightLinking::new().with_shadow(false));
+
+    scene.set_light_group(0, "key");
+    scene.set_light_group(1, "fill");
+    scene.set_light_group(2, "env");
+    scene.set_light_group(3, "env");
+    scene
+}
@@ -8,35 +8,47 @@ use crate::{
     flux::{
         shapes::{Floor, Sphere},
         textures::ConstantTexture,
-        Bounds2, DielectricMaterial, Material, MetalMaterial, PerspectiveCamera, Primitive, Scene,
+        Bounds2, DielectricMaterial, DiffuseLightMaterial, Material, MetalMaterial,
+        PerspectiveCamera, Primitive, Scene,
     },
 };
 
 use super::{default_sky_light, sample_disks};
 
 pub fn many_spheres() -> Scene {
-    let camera = {
-        let resolution = uvec2(800, 450);
-        let look_from = vec3(13.0, 4.0, -3.0);
-        let look_at = vec3(2.5, 0.5, 0.0);
-
-        Box::new(PerspectiveCamera::new(
-            resolution,
-            look_from,
-            Vec3::ZERO,
-            35.0,
-            0.025,
-            look_at.distance(look_from),
-        ))
-    };
-
-    let aggregate = build_aggregate();
+    let camera = build_camera();
+    let aggregate = build_aggregate(0.0);
     let lights = vec![default_sky_light()];
 
     Scene::new(camera, aggregate, lights)
 }
 
-fn build_aggregate() -> Vec<Primitive> {
+/// A night version of the scene, only lit by hundreds of small glowing spheres.
+pub fn many_spheres_emissive() -> Scene {
+    let camera = build_camera();
+    let aggregate = build_aggregate(0.5);
+
+    Scene::new(camera, aggregate, vec![])
+}
+
+fn build_camera() -> Box<PerspectiveCamera> {
+    let resolution = uvec2(800, 450);
+    let look_from = vec3(13.0, 4.0, -3.0);
+    let look_at = vec3(2.5, 0.5, 0.0);
+
+    Box::new(PerspectiveCamera::new(
+        resolution,
+        look_from,
+        Vec3::ZERO,
+        35.0,
+        0.025,
+        look_at.distance(look_from),
+    ))
+}
+
+/// Builds the spheres, turning the small ones into lights with the probability
+/// `emissive_prob`.
+fn build_aggregate(emissive_prob: f32) -> Vec<Primitive> {
     let mut aggregate = {
         let floor = {
             let mat = build_matte_constant(Vec3::splat(0.5));
@@ -87,30 +99,39 @@ fn build_aggregate() -> Vec<Primitive> {
             }
 
             let choose_mat: f32 = rng.gen();
-            let material: Rc<dyn Material> = if choose_mat < 0.6 {
-                // diffuse
-                let albedo = rng.gen::<Vec3>() * rng.gen::<Vec3>();
-                build_matte_constant(albedo)
-            } else if choose_mat < 0.9 {
-                // metal
-                let albedo = vec3(
-                    rng.gen_range(0.5..1.0),
-                    rng.gen_range(0.5..1.0),
-                    rng.gen_range(0.5..1.0),
-                );
-                let tex = Rc::new(ConstantTexture::new(albedo));
-                let fuzz = rng.gen_range(0.0..0.5);
-                Rc::new(MetalMaterial::new(tex, fuzz))
-            } else {
-                // dielectric
-                let albedo = vec3(
-                    rng.gen::<f32>().powf(1.0 / 4.0),
-                    rng.gen::<f32>().powf(1.0 / 4.0),
-                    rng.gen::<f32>().powf(1.0 / 4.0),
-                );
-                let tex = Rc::new(ConstantTexture::new(albedo));
-                Rc::new(DielectricMaterial::new(tex, 1.5))
-            };
+            let material: Rc<dyn Material> =
+                if emissive_prob > 0.0 && rng.gen::<f32>() < emissive_prob {
+                    let color = vec3(
+                        rng.gen_range(0.1..1.0),
+                        rng.gen_range(0.1..1.0),
+                        rng.gen_range(0.1..1.0),
+                    );
+                    let tex = Rc::new(ConstantTexture::new(8.0 * color / color.max_element()));
+                    Rc::new(DiffuseLightMaterial::new(tex))
+                } else if choose_mat < 0.6 {
+                    // diffuse
+                    let albedo = rng.gen::<Vec3>() * rng.gen::<Vec3>();
+                    build_matte_constant(albedo)
+                } else if choose_mat < 0.9 {
+                    // metal
+                    let albedo = vec3(
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                    );
+                    let tex = Rc::new(ConstantTexture::new(albedo));
+                    let fuzz = rng.gen_range(0.0..0.5);
+                    Rc::new(MetalMaterial::new(tex, fuzz))
+                } else {
+                    // dielectric
+                    let albedo = vec3(
+                        rng.gen::<f32>().powf(1.0 / 4.0),
+                        rng.gen::<f32>().powf(1.0 / 4.0),
+                        rng.gen::<f32>().powf(1.0 / 4.0),
+                    );
+                    let tex = Rc::new(ConstantTexture::new(albedo));
+                    Rc::new(DielectricMaterial::new(tex, 1.5))
+                };
 
             let shape = Box::new(Sphere::new(sphere_pos, radius));
             let primitive = Primitive::new(shape, material);
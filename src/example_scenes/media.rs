@@ -0,0 +1,57 @@
+use std::{rc::Rc, sync::Arc};
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    media::{HomogeneousMedium, Medium},
+    shapes::Sphere,
+    textures::ConstantTexture,
+    DielectricMaterial, InterfaceMaterial, Material, Primitive, Scene,
+};
+
+use super::util::{gallery, gallery_sphere};
+
+/// Clouds of smoke without a visible surface and glass filled with juice and milk, in light fog
+/// that surrounds the camera.
+pub fn media() -> Scene {
+    let interface: Rc<dyn Material> = Rc::new(InterfaceMaterial);
+    let glass: Rc<dyn Material> = Rc::new(DielectricMaterial::new(
+        Rc::new(ConstantTexture::new(Vec3::ONE)),
+        1.5,
+    ));
+    let medium = |sigma_a: Vec3, sigma_s: Vec3, g: f32| -> Option<Arc<dyn Medium>> {
+        Some(Arc::new(
+            HomogeneousMedium::new(sigma_a, sigma_s).with_anisotropy(g),
+        ))
+    };
+
+    let mut primitives = vec![
+        gallery_sphere(0, 0, interface.clone())
+            .with_media(medium(Vec3::splat(0.5), Vec3::splat(10.0), 0.0), None),
+        gallery_sphere(0, 1, interface.clone())
+            .with_media(medium(Vec3::splat(3.0), Vec3::splat(2.0), 0.6), None),
+        gallery_sphere(0, 2, interface)
+            .with_media(medium(vec3(0.5, 2.0, 6.0), vec3(4.0, 2.0, 1.0), -0.3), None),
+        gallery_sphere(0, 3, glass.clone())
+            .with_media(medium(vec3(0.2, 1.5, 4.0), vec3(3.0, 2.0, 0.5), 0.0), None),
+        gallery_sphere(0, 4, glass)
+            .with_media(medium(Vec3::splat(0.01), vec3(20.0, 24.0, 28.0), 0.0), None),
+    ];
+
+    // The fog has to end somewhere, otherwise no light would reach the scene from the sky. The
+    // camera is inside of it.
+    let fog: Arc<dyn Medium> = Arc::new(HomogeneousMedium::new(
+        Vec3::splat(0.005),
+        Vec3::splat(0.04),
+    ));
+    let fog_bounds = Primitive::new(
+        Box::new(Sphere::new(Vec3::ZERO, 20.0)),
+        Rc::new(InterfaceMaterial),
+    )
+    .with_media(Some(fog.clone()), None);
+
+    primitives.push(fog_bounds);
+    let mut scene = gallery(primitives);
+    scene.set_camera_medium(fog);
+    scene
+}
@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    textures::{CheckerTexture, ConstantTexture, ImageTexture, NoiseTexture, Texture},
+    AddMaterial, ConductorMaterial, DiffuseLightMaterial, Material, MatteMaterial, Metal,
+    MixMaterial, PrincipledMaterial, Scene,
+};
+
+use super::util::material_gallery;
+
+/// Rust over paint and decals blended with noise, checker and image masks in front, and glowing
+/// patterns layered onto surfaces with emission behind.
+pub fn mix() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+
+    let paint: Rc<dyn Material> = Rc::new(
+        PrincipledMaterial::new(color(vec3(0.1, 0.25, 0.6)))
+            .with_roughness(constant(0.3))
+            .with_clearcoat(constant(1.0)),
+    );
+    let rust: Rc<dyn Material> = Rc::new(MatteMaterial::new(color(vec3(0.35, 0.12, 0.04))));
+    let gold: Rc<dyn Material> =
+        Rc::new(ConductorMaterial::from_metal(Metal::Gold).with_roughness(constant(0.2)));
+    let white: Rc<dyn Material> = Rc::new(MatteMaterial::new(color(Vec3::splat(0.8))));
+
+    let mix = |a: &Rc<dyn Material>, b: &Rc<dyn Material>, mask: Rc<dyn Texture<f32>>| {
+        Rc::new(MixMaterial::new(a.clone(), b.clone(), mask)) as Rc<dyn Material>
+    };
+    let checker = |scale: f32| -> Rc<dyn Texture<f32>> {
+        Rc::new(CheckerTexture::new(scale, constant(0.0), constant(1.0)))
+    };
+    let earth: Rc<dyn Texture<f32>> = Rc::new(ImageTexture::new(
+        image::open("./assets/earthmap.jpg").unwrap(),
+    ));
+
+    let blended = vec![
+        mix(&paint, &rust, Rc::new(NoiseTexture::new(0.1))),
+        mix(&paint, &rust, Rc::new(NoiseTexture::new(0.025))),
+        mix(&paint, &rust, constant(0.5)),
+        mix(&white, &gold, checker(0.1)),
+        mix(&paint, &gold, earth),
+    ];
+
+    let glowing = |material: &Rc<dyn Material>, emit: Rc<dyn Texture<Vec3>>| {
+        let light = Rc::new(DiffuseLightMaterial::new(emit).with_two_sided(false));
+        Rc::new(AddMaterial::new(material.clone(), light)) as Rc<dyn Material>
+    };
+    let black = color(Vec3::ZERO);
+    let stripes = |emit: Vec3, scale: f32| -> Rc<dyn Texture<Vec3>> {
+        Rc::new(CheckerTexture::new(scale, black.clone(), color(emit)))
+    };
+
+    let emissive = vec![
+        glowing(&white, stripes(vec3(4.0, 1.5, 0.3), 0.1)),
+        glowing(&paint, stripes(vec3(0.3, 2.0, 4.0), 0.05)),
+        glowing(&gold, stripes(vec3(2.0, 2.0, 2.0), 0.25)),
+        glowing(&rust, Rc::new(NoiseTexture::new(0.05))),
+        glowing(&white, color(vec3(0.5, 0.5, 0.5))),
+    ];
+
+    material_gallery(vec![blended, emissive])
+}
@@ -1,9 +1,23 @@
+mod bump_mapping;
+mod coated;
+mod conductors;
 mod cornell_box;
+mod cutout;
 mod defocus_blur;
+mod dielectrics;
+mod dispersion;
 mod dragon;
+mod lamps;
 mod many_spheres;
 mod material_demo;
+mod media;
+mod mix;
+mod principled;
+mod smoke;
+mod subsurface;
+mod sun_sky;
 mod suzanne;
+mod turntable;
 mod util;
 
 use glam::{vec2, vec3, Vec2, Vec3};
@@ -18,36 +32,82 @@ use crate::{
     },
 };
 
+use bump_mapping::bump_mapping;
+use coated::coated;
+use conductors::conductors;
+use cutout::cutout;
 use defocus_blur::defocus_blur;
+use dielectrics::dielectrics;
+use dispersion::dispersion;
 use dragon::dragon;
-use many_spheres::many_spheres;
+use lamps::lamps;
+use many_spheres::{many_spheres, many_spheres_emissive};
 use material_demo::material_demo;
+use media::media;
+use mix::mix;
+use principled::principled;
+use smoke::smoke;
+use subsurface::subsurface;
+use sun_sky::sun_sky;
 use suzanne::suzanne;
+use turntable::turntable;
 
-use self::cornell_box::simple_cornell_box;
+use self::cornell_box::{cornell_box_elevation, simple_cornell_box};
 
 #[allow(dead_code)]
 #[derive(Debug, EnumString)]
 #[strum(ascii_case_insensitive)]
 pub enum ExampleScene {
+    BumpMapping,
+    Coated,
+    Conductors,
     CornellBox,
+    CornellBoxElevation,
+    Cutout,
     DefocusBlur,
+    Dielectrics,
+    Dispersion,
     Dragon,
+    Lamps,
     ManySpheres,
+    ManySpheresEmissive,
     MaterialDemo,
+    Media,
+    Mix,
+    Principled,
     SimpleCornellBox,
+    Smoke,
+    Subsurface,
+    SunSky,
     Suzanne,
+    Turntable,
 }
 
 pub fn load_example_scene(scene: ExampleScene) -> Scene {
     match scene {
+        ExampleScene::BumpMapping => bump_mapping(),
+        ExampleScene::Coated => coated(),
+        ExampleScene::Conductors => conductors(),
         ExampleScene::CornellBox => cornell_box(),
+        ExampleScene::CornellBoxElevation => cornell_box_elevation(),
+        ExampleScene::Cutout => cutout(),
         ExampleScene::DefocusBlur => defocus_blur(),
+        ExampleScene::Dielectrics => dielectrics(),
+        ExampleScene::Dispersion => dispersion(),
         ExampleScene::Dragon => dragon(),
+        ExampleScene::Lamps => lamps(),
         ExampleScene::ManySpheres => many_spheres(),
+        ExampleScene::ManySpheresEmissive => many_spheres_emissive(),
         ExampleScene::MaterialDemo => material_demo(),
+        ExampleScene::Media => media(),
+        ExampleScene::Mix => mix(),
+        ExampleScene::Principled => principled(),
         ExampleScene::SimpleCornellBox => simple_cornell_box(),
+        ExampleScene::Smoke => smoke(),
+        ExampleScene::Subsurface => subsurface(),
+        ExampleScene::SunSky => sun_sky(),
         ExampleScene::Suzanne => suzanne(),
+        ExampleScene::Turntable => turntable(),
     }
 }
 
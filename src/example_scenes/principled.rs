@@ -0,0 +1,54 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{textures::ConstantTexture, Material, PrincipledMaterial, Scene};
+
+use super::util::material_gallery;
+
+/// Spheres with the principled material: a dielectric and a metallic row with increasing
+/// roughness, and a row showing transmission, clearcoat, sheen, anisotropy and emission.
+pub fn principled() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+    let base = color(vec3(0.9, 0.45, 0.2));
+
+    let mut dielectrics: Vec<Rc<dyn Material>> = vec![];
+    let mut metals: Vec<Rc<dyn Material>> = vec![];
+    for column in 0..5 {
+        let roughness = column as f32 / 4.0;
+
+        let dielectric = PrincipledMaterial::new(base.clone()).with_roughness(constant(roughness));
+        dielectrics.push(Rc::new(dielectric));
+
+        let metal = PrincipledMaterial::new(base.clone())
+            .with_metallic(constant(1.0))
+            .with_roughness(constant(roughness));
+        metals.push(Rc::new(metal));
+    }
+
+    let glass = PrincipledMaterial::new(color(vec3(0.8, 0.95, 0.9)))
+        .with_transmission(constant(1.0))
+        .with_roughness(constant(0.1));
+    let clearcoat = PrincipledMaterial::new(color(vec3(0.6, 0.05, 0.05)))
+        .with_roughness(constant(0.8))
+        .with_clearcoat(constant(1.0));
+    let cloth = PrincipledMaterial::new(color(vec3(0.2, 0.2, 0.6)))
+        .with_roughness(constant(1.0))
+        .with_sheen(constant(1.0));
+    let brushed = PrincipledMaterial::new(color(Vec3::splat(0.9)))
+        .with_metallic(constant(1.0))
+        .with_roughness(constant(0.4))
+        .with_anisotropic(constant(0.9));
+    let glowing =
+        PrincipledMaterial::new(color(Vec3::splat(0.1))).with_emission(color(vec3(4.0, 2.0, 0.6)));
+    let features: Vec<Rc<dyn Material>> = vec![
+        Rc::new(glass),
+        Rc::new(clearcoat),
+        Rc::new(cloth),
+        Rc::new(brushed),
+        Rc::new(glowing),
+    ];
+
+    material_gallery(vec![dielectrics, metals, features])
+}
@@ -0,0 +1,59 @@
+use std::{rc::Rc, sync::Arc};
+
+use glam::{uvec3, vec3, Affine3A, Quat, Vec3};
+use noise::{Fbm, NoiseFn, Perlin};
+
+use crate::flux::{
+    media::{GridMedium, Medium, VoxelGrid},
+    Bounds3, InterfaceMaterial, Material, Primitive, Scene,
+};
+
+use super::util::gallery;
+
+/// A rotated cloud that scatters the sky light on the left, given by a procedural voxel grid, and
+/// a plume of fire on the right, loaded from a grid file, whose temperature makes it glow like a
+/// black body.
+pub fn smoke() -> Scene {
+    let interface: Rc<dyn Material> = Rc::new(InterfaceMaterial);
+    let noise = Fbm::<Perlin>::new(7);
+    let fbm = |p: Vec3, frequency: f32| {
+        let p = p * frequency;
+        noise.get([p.x as f64, p.y as f64, p.z as f64]) as f32
+    };
+
+    let cloud = {
+        let density = VoxelGrid::from_fn(uvec3(64, 48, 64), |p| {
+            let r = ((p - vec3(0.5, 0.45, 0.5)) * vec3(1.0, 1.3, 1.0)).length();
+            (2.0 * (0.4 - r) / 0.4 + 1.2 * fbm(p, 4.0)).clamp(0.0, 1.0)
+        });
+        let bounds = Bounds3::new(vec3(-2.6, 0.0, -0.8), vec3(-0.2, 1.8, 1.6));
+        let center = bounds.centroid();
+        let rotation = Affine3A::from_translation(center)
+            * Affine3A::from_quat(Quat::from_rotation_y(0.5))
+            * Affine3A::from_translation(-center);
+        GridMedium::new(density, bounds, Vec3::splat(0.1), Vec3::splat(12.0))
+            .with_transform(rotation)
+            .with_anisotropy(0.6)
+    };
+
+    // the plume of the fire is loaded from a grid file with its temperature
+    let fire = GridMedium::load(
+        "./assets/volumes/fire.fluxgrid",
+        Bounds3::new(vec3(0.4, 0.0, -0.4), vec3(1.6, 2.4, 0.8)),
+        Vec3::splat(4.0),
+        Vec3::splat(1.0),
+    )
+    .unwrap_or_else(|err| panic!("Failed to load fire grid: {}", err))
+    .with_emission(20.0);
+
+    let primitives = [cloud, fire]
+        .into_iter()
+        .map(|medium| {
+            let boundary = medium.boundary();
+            let medium: Arc<dyn Medium> = Arc::new(medium);
+            Primitive::new(boundary, interface.clone()).with_media(Some(medium), None)
+        })
+        .collect();
+
+    gallery(primitives)
+}
@@ -0,0 +1,53 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    textures::{ConstantTexture, NoiseTexture, Texture},
+    Material, Scene, SubsurfaceMaterial,
+};
+
+use super::util::material_gallery;
+
+/// Skin, marble, wax, jade and milk in front, wax with an increasing mean free path in the
+/// middle, and rough, anisotropic and textured variations of skin and marble behind.
+pub fn subsurface() -> Scene {
+    let constant = |value: f32| Rc::new(ConstantTexture::new(value));
+    let color = |value: Vec3| Rc::new(ConstantTexture::new(value));
+    let material = |albedo: Vec3, mean_free_path: Vec3| {
+        SubsurfaceMaterial::new(color(albedo), color(mean_free_path))
+    };
+
+    let skin = || material(vec3(0.8, 0.5, 0.4), vec3(0.08, 0.03, 0.015));
+    let marble = || material(vec3(0.85, 0.83, 0.8), vec3(0.1, 0.08, 0.06));
+    let wax = |mean_free_path: f32| {
+        material(
+            vec3(0.9, 0.7, 0.35),
+            Vec3::splat(mean_free_path) * vec3(1.0, 0.7, 0.4),
+        )
+    };
+
+    let materials: Vec<Rc<dyn Material>> = vec![
+        Rc::new(skin().with_anisotropy(0.8)),
+        Rc::new(marble()),
+        Rc::new(wax(0.05)),
+        Rc::new(material(vec3(0.3, 0.7, 0.4), vec3(0.04, 0.15, 0.06)).with_ior(1.6)),
+        Rc::new(material(Vec3::splat(0.95), vec3(0.05, 0.04, 0.03)).with_ior(1.35)),
+    ];
+
+    let waxes = [0.005, 0.02, 0.05, 0.1, 0.3]
+        .into_iter()
+        .map(|mean_free_path| -> Rc<dyn Material> { Rc::new(wax(mean_free_path)) })
+        .collect();
+
+    let veins: Rc<dyn Texture<Vec3>> = Rc::new(NoiseTexture::new(0.1));
+    let variations: Vec<Rc<dyn Material>> = vec![
+        Rc::new(skin().with_roughness(constant(0.4))),
+        Rc::new(skin().with_anisotropy(-0.5)),
+        Rc::new(marble().with_roughness(constant(0.2))),
+        Rc::new(SubsurfaceMaterial::new(veins, color(vec3(0.1, 0.08, 0.06)))),
+        Rc::new(wax(0.05).with_roughness(constant(0.6))),
+    ];
+
+    material_gallery(vec![materials, waxes, variations])
+}
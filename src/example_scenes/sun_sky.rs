@@ -0,0 +1,41 @@
+use glam::{uvec2, vec3, Vec3};
+
+use crate::flux::{
+    lights::{Light, PreethamSkyLight, SunLight, SunPosition},
+    PerspectiveCamera, Scene,
+};
+
+use super::util::material_demo_aggregate;
+
+/// The material demo outdoors, on a summer afternoon in Zurich.
+pub fn sun_sky() -> Scene {
+    let camera = {
+        let resolution = uvec2(1024, 1024);
+        let look_from = vec3(0.0, 4.0, -8.0);
+        let look_at = vec3(0.0, 2.0, 0.0);
+
+        Box::new(PerspectiveCamera::new(
+            resolution,
+            look_from,
+            look_at,
+            50.0,
+            0.025,
+            look_at.distance(look_from),
+        ))
+    };
+
+    let aggregate = material_demo_aggregate();
+
+    let sun_position = SunPosition::from_location(47.37, 8.54, 6, 21, 16.0);
+    let turbidity = 3.0;
+
+    let sky: Box<dyn Light> = Box::new(PreethamSkyLight::new(
+        sun_position,
+        turbidity,
+        Vec3::splat(0.3),
+    ));
+    let sun: Box<dyn Light> = Box::new(SunLight::new(sun_position, turbidity));
+    let lights = vec![sky, sun];
+
+    Scene::new(camera, aggregate, lights)
+}
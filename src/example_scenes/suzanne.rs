@@ -6,13 +6,14 @@ use glam::{uvec2, vec3, Affine3A, Vec3};
 use crate::{
     example_scenes::util::load_obj,
     flux::{
+        lights::{EnvironmentLight, Light},
         shapes::{Floor, SubdivisionMesh, Transform},
         textures::{CheckerTexture, ConstantTexture},
         MetalMaterial, PerspectiveCamera, Primitive, Scene,
     },
 };
 
-use super::util::{build_matte_constant, hdr_light_dome};
+use super::util::build_matte_constant;
 
 pub fn suzanne() -> Scene {
     let camera = {
@@ -31,7 +32,12 @@ pub fn suzanne() -> Scene {
 
     let aggregate =
         build_aggregate().unwrap_or_else(|err| panic!("Failed to build wavefront scene: {}", err));
-    let lights = vec![];
+    let light_dome: Box<dyn Light> = Box::new(
+        EnvironmentLight::load("./assets/lightprobes/pisa.exr")
+            .unwrap_or_else(|err| panic!("Failed to load environment light: {}", err))
+            .with_intensity(2.0),
+    );
+    let lights = vec![light_dome];
 
     Scene::new(camera, aggregate, lights)
 }
@@ -63,7 +69,5 @@ fn build_aggregate() -> Result<Vec<Primitive>> {
         Primitive::new(shape, mat)
     };
 
-    let light_dome = hdr_light_dome("./assets/lightprobes/pisa.exr");
-
-    Ok(vec![floor, suzanne, light_dome])
+    Ok(vec![floor, suzanne])
 }
@@ -0,0 +1,102 @@
+use std::{f32::consts::PI, rc::Rc};
+
+use glam::{uvec2, vec3, Quat, Vec3};
+
+use crate::{
+    example_scenes::util::build_matte_constant,
+    flux::{
+        animation::{AnimatedTransform, CameraKeyframes, Keyframes},
+        shapes::{Floor, QuadBox, Sphere, Transform},
+        textures::ConstantTexture,
+        MetalMaterial, PerspectiveCamera, Primitive, Scene,
+    },
+};
+
+use super::default_sky_light;
+
+const FRAMES: f32 = 120.0;
+
+pub fn turntable() -> Scene {
+    let camera = {
+        let resolution = uvec2(800, 450);
+        let look_at = vec3(0.0, 0.75, 0.0);
+        let keyframes = CameraKeyframes::new(
+            Keyframes::new(vec![
+                (1.0, vec3(0.0, 2.0, -6.0)),
+                (FRAMES, vec3(0.0, 3.0, -5.0)),
+            ]),
+            Keyframes::constant(look_at),
+        );
+
+        let look_from = keyframes.position.evaluate(1.0);
+        Box::new(
+            PerspectiveCamera::new(
+                resolution,
+                look_from,
+                look_at,
+                35.0,
+                0.0,
+                look_at.distance(look_from),
+            )
+            .with_keyframes(keyframes),
+        )
+    };
+
+    let aggregate = build_aggregate();
+    let lights = vec![default_sky_light()];
+
+    Scene::new(camera, aggregate, lights)
+}
+
+fn build_aggregate() -> Vec<Primitive> {
+    let floor = {
+        let mat = build_matte_constant(Vec3::splat(0.5));
+        let shape = Box::new(Floor::new());
+        Primitive::new(shape, mat)
+    };
+
+    let spinning_box = {
+        let mat = build_matte_constant(vec3(0.65, 0.05, 0.05));
+        let shape = Box::new(QuadBox::new(1.0, 1.0, 1.0));
+
+        // A full turn, split into quarter turns so the slerp doesn't take the short way back
+        let rotation = Keyframes::new(
+            (0..=4)
+                .map(|i| {
+                    let t = i as f32 / 4.0;
+                    let frame = 1.0 + t * (FRAMES - 1.0);
+                    (frame, Quat::from_rotation_y(t * 2.0 * PI))
+                })
+                .collect(),
+        );
+        let animation = AnimatedTransform::new(
+            Keyframes::constant(Vec3::ONE),
+            rotation,
+            Keyframes::constant(vec3(0.0, 0.5, 0.0)),
+        );
+        let shape = Box::new(Transform::animated(animation, shape));
+        Primitive::new(shape, mat)
+    };
+
+    let bouncing_sphere = {
+        let mat = {
+            let tex = Rc::new(ConstantTexture::new(vec3(0.8, 0.6, 0.2)));
+            Rc::new(MetalMaterial::new(tex, 0.05))
+        };
+        let shape = Box::new(Sphere::new(Vec3::ZERO, 0.5));
+        let translation = Keyframes::new(vec![
+            (1.0, vec3(2.0, 0.5, 0.0)),
+            (FRAMES / 2.0, vec3(2.0, 2.0, 0.0)),
+            (FRAMES, vec3(2.0, 0.5, 0.0)),
+        ]);
+        let animation = AnimatedTransform::new(
+            Keyframes::constant(Vec3::ONE),
+            Keyframes::constant(Quat::IDENTITY),
+            translation,
+        );
+        let shape = Box::new(Transform::animated(animation, shape));
+        Primitive::new(shape, mat)
+    };
+
+    vec![floor, spinning_box, bouncing_sphere]
+}
@@ -1,8 +1,14 @@
+use std::rc::Rc;
+
 use glam::{uvec2, vec3, Vec3};
 
 use crate::{
-    example_scenes::util::{build_diffuse_constant, build_matte_constant},
-    flux::{shapes::Quad, Camera, PerspectiveCamera, Primitive},
+    example_scenes::util::build_matte_constant,
+    flux::{
+        shapes::{Quad, TriangleMesh},
+        textures::ConstantTexture,
+        Camera, DiffuseLightMaterial, PerspectiveCamera, Primitive,
+    },
 };
 
 pub fn cornell_box_camera(box_size: f32) -> Box<dyn Camera> {
@@ -62,7 +68,10 @@ pub fn empty_cornell_box_prims(box_size: f32) -> Vec<Primitive> {
     };
 
     let light = {
-        let light_mat = build_diffuse_constant(Vec3::splat(15.0));
+        let light_mat = {
+            let tex = Rc::new(ConstantTexture::new(Vec3::splat(15.0)));
+            Rc::new(DiffuseLightMaterial::new(tex).with_two_sided(false))
+        };
 
         let size = 0.1 * box_size;
         let y = box_size / 2.0 - 32.0 * f32::EPSILON;
@@ -72,7 +81,11 @@ pub fn empty_cornell_box_prims(box_size: f32) -> Vec<Primitive> {
         let rb = vec3(size, y, size);
         let lb = vec3(-size, y, size);
 
-        let shape = Box::new(Quad::new([lf, rf, rb, lb]));
+        // triangles are sampled as area lights, with the vertices ordered to face down
+        let shape = Box::new(TriangleMesh::new(
+            vec![lf, rf, rb, lb],
+            vec![0, 1, 2, 2, 3, 0],
+        ));
         Primitive::new(shape, light_mat)
     };
 
@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use glam::{uvec2, vec3, Vec3};
+
+use crate::flux::{
+    lights::Light,
+    shapes::{Floor, Sphere},
+    textures::{CheckerTexture, ConstantTexture},
+    Material, MatteMaterial, PerspectiveCamera, Primitive, Scene,
+};
+
+use crate::example_scenes::default_sky_light;
+
+/// Lays out spheres with the given materials on a checkered floor under the default sky, one row
+/// of up to five spheres per entry, with the first row in front.
+pub fn material_gallery(rows: Vec<Vec<Rc<dyn Material>>>) -> Scene {
+    let spheres = rows
+        .into_iter()
+        .enumerate()
+        .flat_map(|(row, materials)| {
+            materials
+                .into_iter()
+                .enumerate()
+                .map(move |(column, mat)| gallery_sphere(row, column, mat))
+        })
+        .collect();
+
+    gallery(spheres)
+}
+
+/// The sphere at the given row and column of a material gallery.
+pub fn gallery_sphere(row: usize, column: usize, material: Rc<dyn Material>) -> Primitive {
+    let center = vec3(2.0 - column as f32, 0.4, 2.0 * row as f32 - 2.0);
+    Primitive::new(Box::new(Sphere::new(center, 0.4)), material)
+}
+
+/// Places the primitives on the checkered floor of a material gallery, under the default sky.
+pub fn gallery(primitives: Vec<Primitive>) -> Scene {
+    gallery_with_lights(primitives, vec![default_sky_light()])
+}
+
+/// Places the primitives on the checkered floor of a material gallery, lit by `lights`.
+pub fn gallery_with_lights(primitives: Vec<Primitive>, lights: Vec<Box<dyn Light>>) -> Scene {
+    let camera = {
+        let resolution = uvec2(1024, 768);
+        let look_from = vec3(0.0, 3.5, -7.5);
+        let look_at = vec3(0.0, 1.0, 0.0);
+
+        Box::new(PerspectiveCamera::new(
+            resolution,
+            look_from,
+            look_at,
+            45.0,
+            0.0,
+            look_at.distance(look_from),
+        ))
+    };
+
+    let floor = {
+        let even = Rc::new(ConstantTexture::new(Vec3::splat(0.7)));
+        let odd = Rc::new(ConstantTexture::new(Vec3::splat(0.5)));
+        let tex = Rc::new(CheckerTexture::new(0.5, even, odd));
+        Primitive::new(Box::new(Floor::new()), Rc::new(MatteMaterial::new(tex)))
+    };
+
+    let mut aggregate = vec![floor];
+    aggregate.extend(primitives);
+
+    Scene::new(camera, aggregate, lights)
+}
@@ -1,25 +1,48 @@
+use std::ptr::null_mut;
+
 use embree4_sys::{
-    rtcAttachGeometryByID, rtcCommitGeometry, rtcCommitScene, rtcNewDevice, rtcNewScene,
-    rtcReleaseGeometry, rtcSetSceneBuildQuality, rtcSetSceneFlags, RTCBuildQuality, RTCScene,
-    RTCSceneFlags,
+    rtcAttachGeometryByID, rtcCommitGeometry, rtcCommitScene, rtcGetGeometry, rtcGetSceneFlags,
+    rtcIntersect1, rtcNewDevice, rtcNewScene, rtcOccluded1, rtcReleaseGeometry, rtcReleaseScene,
+    rtcSetGeometryBuildQuality, rtcSetSceneBuildQuality, rtcSetSceneFlags, RTCBuildQuality,
+    RTCDevice, RTCFeatureFlags, RTCFilterFunctionNArguments, RTCHit, RTCIntersectArguments,
+    RTCOccludedArguments, RTCRay, RTCRayHit, RTCRayQueryContext, RTCRayQueryFlags, RTCScene,
+    RTCSceneFlags, RTC_INVALID_GEOMETRY_ID,
 };
+use glam::vec3;
 use measure_time::trace_time;
 
-use super::primitive::Primitive;
+use super::{interaction::Interaction, primitive::Primitive, ray::Ray};
 
 pub struct EmbreeAccel {
     pub scene: RTCScene,
+    device: RTCDevice,
+    /// Whether any primitive has an opacity texture, which requires the filter callback.
+    has_opacity: bool,
+    /// Whether the scene was switched to updates of its BVH by `refit`.
+    dynamic: bool,
 }
 
 impl EmbreeAccel {
     pub unsafe fn build(primitives: &[Primitive]) -> EmbreeAccel {
-        trace_time!("building accel");
-
         let device = rtcNewDevice(b"verbose=0" as *const _ as _);
+        let scene = Self::build_scene(device, primitives);
+        Self {
+            scene,
+            device,
+            has_opacity: has_opacity(primitives),
+            dynamic: false,
+        }
+    }
+
+    unsafe fn build_scene(device: RTCDevice, primitives: &[Primitive]) -> RTCScene {
+        trace_time!("building accel");
 
         let scene = rtcNewScene(device);
         rtcSetSceneBuildQuality(scene, RTCBuildQuality::HIGH);
-        rtcSetSceneFlags(scene, RTCSceneFlags::ROBUST);
+        rtcSetSceneFlags(
+            scene,
+            RTCSceneFlags::ROBUST | RTCSceneFlags::FILTER_FUNCTION_IN_ARGUMENTS,
+        );
 
         for (id, prim) in primitives.iter().enumerate() {
             let geometry_id = id as u32;
@@ -32,6 +55,174 @@ impl EmbreeAccel {
 
         rtcCommitScene(scene);
 
-        Self { scene }
+        scene
+    }
+
+    /// Throws away the current scene and builds a new one from scratch.
+    pub unsafe fn rebuild(&mut self, primitives: &[Primitive]) {
+        rtcReleaseScene(self.scene);
+        self.scene = Self::build_scene(self.device, primitives);
+        self.has_opacity = has_opacity(primitives);
+        self.dynamic = false;
     }
+
+    /// Updates the geometries of the given primitives in place and recommits the scene. The
+    /// BVHs of the instanced sub-scenes are kept, and the first call makes the scene dynamic, so
+    /// that Embree refits the top level over the updated instances instead of rebuilding it.
+    pub unsafe fn refit(&mut self, primitives: &[Primitive], ids: &[usize]) {
+        trace_time!("refitting accel");
+
+        if !self.dynamic {
+            let flags = rtcGetSceneFlags(self.scene);
+            rtcSetSceneFlags(self.scene, flags | RTCSceneFlags::DYNAMIC);
+            rtcSetSceneBuildQuality(self.scene, RTCBuildQuality::LOW);
+            self.dynamic = true;
+        }
+
+        for &id in ids {
+            let geometry = rtcGetGeometry(self.scene, id as u32);
+            rtcSetGeometryBuildQuality(geometry, RTCBuildQuality::REFIT);
+            primitives[id].shape.update_geometry(geometry);
+        }
+
+        rtcCommitScene(self.scene);
+    }
+
+    /// Finds the closest hit of the ray, skipping the parts of the primitives cut out by their
+    /// opacity.
+    pub unsafe fn intersect(&self, primitives: &[Primitive], ray: &Ray, ray_hit: &mut RTCRayHit) {
+        if !self.has_opacity {
+            rtcIntersect1(self.scene, ray_hit, null_mut());
+            return;
+        }
+
+        let mut context = FilterContext::new(primitives, ray);
+        let mut args = RTCIntersectArguments {
+            flags: RTCRayQueryFlags::INVOKE_ARGUMENT_FILTER,
+            feature_mask: RTCFeatureFlags::RTC_FEATURE_FLAG_ALL,
+            context: &mut context as *mut FilterContext as *mut RTCRayQueryContext,
+            filter: Some(opacity_filter),
+            intersect: None,
+        };
+        rtcIntersect1(self.scene, ray_hit, &mut args);
+    }
+
+    /// Tests whether any opaque part of a primitive blocks the ray.
+    pub unsafe fn occluded(&self, primitives: &[Primitive], ray: &Ray, rtc_ray: &mut RTCRay) {
+        if !self.has_opacity {
+            rtcOccluded1(self.scene, rtc_ray, null_mut());
+            return;
+        }
+
+        let mut context = FilterContext::new(primitives, ray);
+        let mut args = RTCOccludedArguments {
+            flags: RTCRayQueryFlags::INVOKE_ARGUMENT_FILTER,
+            feature_mask: RTCFeatureFlags::RTC_FEATURE_FLAG_ALL,
+            context: &mut context as *mut FilterContext as *mut RTCRayQueryContext,
+            filter: Some(opacity_filter),
+            occluded: None,
+        };
+        rtcOccluded1(self.scene, rtc_ray, &mut args);
+    }
+}
+
+fn has_opacity(primitives: &[Primitive]) -> bool {
+    primitives.iter().any(|prim| prim.opacity.is_some())
+}
+
+/// The ray query context handed to the filter callback. Embree only knows about the leading
+/// context of its own, the rest gives the callback access to the primitives and the ray.
+#[repr(C)]
+struct FilterContext<'a> {
+    context: RTCRayQueryContext,
+    primitives: &'a [Primitive],
+    ray: &'a Ray,
+}
+
+impl<'a> FilterContext<'a> {
+    fn new(primitives: &'a [Primitive], ray: &'a Ray) -> Self {
+        Self {
+            context: RTCRayQueryContext {
+                instID: [RTC_INVALID_GEOMETRY_ID],
+            },
+            primitives,
+            ray,
+        }
+    }
+}
+
+/// Rejects hits on transparent parts of primitives with an opacity texture, for both the closest
+/// hit and occlusion queries.
+unsafe extern "C" fn opacity_filter(args: *const RTCFilterFunctionNArguments) {
+    let args = &*args;
+    // we only trace single rays, so there's exactly one hit to filter
+    if *args.valid == 0 {
+        return;
+    }
+
+    let context = &*(args.context as *const FilterContext);
+    let rtc_ray = &*(args.ray as *const RTCRay);
+    let hit = &*(args.hit as *const RTCHit);
+
+    let prim_idx = hit.geomID as usize;
+    let primitive = &context.primitives[prim_idx];
+    let Some(opacity) = &primitive.opacity else {
+        return;
+    };
+
+    // The ray and the normal are in the local space of instances, but the distance is the same
+    // in both spaces.
+    let t = rtc_ray.tfar;
+    let n = vec3(hit.Ng_x, hit.Ng_y, hit.Ng_z).normalize();
+    let (dpdu, dpdv) = n.any_orthonormal_pair();
+    let int = Interaction {
+        t,
+        p: context.ray.at(t),
+        n,
+        ns: n,
+        dpdu,
+        dpdv,
+        time: context.ray.time,
+        wavelengths: context.ray.wavelengths,
+        primitive,
+        prim_idx,
+        face_idx: hit.primID as usize,
+    };
+
+    let alpha = opacity.evaluate(&int);
+    if alpha >= 1.0 {
+        return;
+    }
+    if alpha <= 0.0 || hash_hit(context.ray, hit, t) >= alpha {
+        *args.valid = 0;
+    }
+}
+
+/// Hashes the ray and the hit to a number in [0, 1), to decide whether a partially opaque hit
+/// is kept. Unlike a random number, the hash gives the same answer when Embree reports a hit
+/// more than once.
+fn hash_hit(ray: &Ray, hit: &RTCHit, t: f32) -> f32 {
+    let words = [
+        ray.origin.x.to_bits(),
+        ray.origin.y.to_bits(),
+        ray.origin.z.to_bits(),
+        ray.direction.x.to_bits(),
+        ray.direction.y.to_bits(),
+        ray.direction.z.to_bits(),
+        hit.geomID,
+        hit.primID,
+        t.to_bits(),
+    ];
+
+    // the finalizer of MurmurHash3, applied after mixing in every word
+    let hash = words.iter().fold(0u64, |hash, &word| {
+        let mut h = hash ^ word as u64;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51afd7ed558ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+        h ^ (h >> 33)
+    });
+
+    (hash >> 40) as f32 / (1u64 << 24) as f32
 }
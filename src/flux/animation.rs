@@ -0,0 +1,167 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+use glam::{Affine3A, Mat4, Quat, Vec3};
+
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        (1.0 - t) * self + t * other
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec3::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Quat {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// A list of values keyed by frame number, linearly interpolated in between. Values before the
+/// first and after the last key are held constant.
+#[derive(Clone, Debug)]
+pub struct Keyframes<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Keyframes<T> {
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "Keyframes require at least one key");
+        keys.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        Self { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.keys.len() > 1
+    }
+
+    pub fn evaluate(&self, frame: f32) -> T {
+        let (first_frame, first) = self.keys[0];
+        if frame <= first_frame {
+            return first;
+        }
+
+        for window in self.keys.windows(2) {
+            let (f0, v0) = window[0];
+            let (f1, v1) = window[1];
+            if frame <= f1 {
+                let t = (frame - f0) / (f1 - f0);
+                return v0.lerp(v1, t);
+            }
+        }
+
+        self.keys[self.keys.len() - 1].1
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimatedTransform {
+    scale: Keyframes<Vec3>,
+    rotation: Keyframes<Quat>,
+    translation: Keyframes<Vec3>,
+}
+
+impl AnimatedTransform {
+    pub fn new(
+        scale: Keyframes<Vec3>,
+        rotation: Keyframes<Quat>,
+        translation: Keyframes<Vec3>,
+    ) -> Self {
+        Self {
+            scale,
+            rotation,
+            translation,
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.scale.is_animated() || self.rotation.is_animated() || self.translation.is_animated()
+    }
+
+    pub fn evaluate(&self, frame: f32) -> Affine3A {
+        Affine3A::from_scale_rotation_translation(
+            self.scale.evaluate(frame),
+            self.rotation.evaluate(frame),
+            self.translation.evaluate(frame),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CameraKeyframes {
+    pub position: Keyframes<Vec3>,
+    pub look_at: Keyframes<Vec3>,
+}
+
+impl CameraKeyframes {
+    pub fn new(position: Keyframes<Vec3>, look_at: Keyframes<Vec3>) -> Self {
+        Self { position, look_at }
+    }
+
+    /// The camera-to-world matrix for the given frame.
+    pub fn view_matrix(&self, frame: f32) -> Mat4 {
+        let position = self.position.evaluate(frame);
+        let look_at = self.look_at.evaluate(frame);
+        Mat4::look_at_lh(position, look_at, Vec3::Y).inverse()
+    }
+}
+
+/// An inclusive range of frames, parsed from either a single frame number (`42`) or a range in
+/// the form `start..end` (`1..120`).
+#[derive(Clone, Copy, Debug)]
+pub struct FrameRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl FrameRange {
+    pub fn iter(&self) -> RangeInclusive<u32> {
+        self.start..=self.end
+    }
+}
+
+impl FromStr for FrameRange {
+    type Err = FrameRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_frame = |frame: &str| {
+            frame
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| FrameRangeError::InvalidFrame(frame.to_string()))
+        };
+
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse_frame(start)?, parse_frame(end)?),
+            None => {
+                let frame = parse_frame(s)?;
+                (frame, frame)
+            }
+        };
+
+        if end < start {
+            Err(FrameRangeError::Empty(start, end))
+        } else {
+            Ok(Self { start, end })
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrameRangeError {
+    #[error("Invalid frame number '{0}'")]
+    InvalidFrame(String),
+    #[error("Frame range {0}..{1} is empty")]
+    Empty(u32, u32),
+}
@@ -1,3 +1,6 @@
+use glam::{Vec2, Vec3};
+
+#[derive(Clone, Copy, Debug)]
 pub struct Bounds2<T> {
     pub min: T,
     pub max: T,
@@ -8,3 +11,89 @@ impl<T> Bounds2<T> {
         Self { min, max }
     }
 }
+
+impl Bounds2<Vec2> {
+    /// Bounds that don't contain any point, acting as the identity for `union`.
+    pub const EMPTY: Self = Self {
+        min: Vec2::INFINITY,
+        max: Vec2::NEG_INFINITY,
+    };
+
+    pub fn union(&self, p: Vec2) -> Self {
+        Self::new(self.min.min(p), self.max.max(p))
+    }
+
+    pub fn contains(&self, p: Vec2) -> bool {
+        p.cmpge(self.min).all() && p.cmple(self.max).all()
+    }
+
+    pub fn expand(&self, delta: f32) -> Self {
+        Self::new(self.min - delta, self.max + delta)
+    }
+
+    pub fn diagonal(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn area(&self) -> f32 {
+        let d = self.diagonal();
+        d.x * d.y
+    }
+
+    pub fn lerp(&self, t: Vec2) -> Vec2 {
+        self.min + t * self.diagonal()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds3<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T> Bounds3<T> {
+    pub fn new(min: T, max: T) -> Self {
+        Self { min, max }
+    }
+}
+
+#[allow(dead_code)]
+impl Bounds3<Vec3> {
+    /// Bounds that don't contain any point, acting as the identity for `union`.
+    pub const EMPTY: Self = Self {
+        min: Vec3::INFINITY,
+        max: Vec3::NEG_INFINITY,
+    };
+
+    pub fn union(&self, p: Vec3) -> Self {
+        Self::new(self.min.min(p), self.max.max(p))
+    }
+
+    pub fn union_bounds(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn diagonal(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = self.diagonal();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// The position of `p` relative to the corners, with `min` at 0 and `max` at 1.
+    pub fn offset(&self, p: Vec3) -> Vec3 {
+        let d = self.diagonal();
+        let o = p - self.min;
+        Vec3::select(d.cmpgt(Vec3::ZERO), o / d, Vec3::ZERO)
+    }
+}
@@ -0,0 +1,109 @@
+use std::{f32::consts::PI, fmt::Debug, path::Path};
+
+use anyhow::{Context, Result};
+use glam::{vec2, Vec2};
+
+use crate::flux::{distribution::Distribution2D, uniform_sample_disk};
+
+/// The shape of a lens aperture, sampled in the unit square [-1, 1]² and scaled by the lens
+/// radius of the camera. Out-of-focus highlights take on this shape.
+pub enum ApertureShape {
+    Circle,
+    /// A regular polygon formed by `blades` straight aperture blades, rotated by `rotation`
+    /// degrees.
+    Polygon {
+        blades: u32,
+        rotation: f32,
+    },
+    Mask(ApertureMask),
+}
+
+pub struct Aperture {
+    shape: ApertureShape,
+    /// Horizontal squeeze factor of anamorphic lenses. Values greater than 1 give oval bokeh,
+    /// taller than wide.
+    squeeze: f32,
+}
+
+impl Default for Aperture {
+    fn default() -> Self {
+        Self::new(ApertureShape::Circle)
+    }
+}
+
+#[allow(dead_code)]
+impl Aperture {
+    pub fn new(shape: ApertureShape) -> Self {
+        Self {
+            shape,
+            squeeze: 1.0,
+        }
+    }
+
+    pub fn with_squeeze(mut self, squeeze: f32) -> Self {
+        self.squeeze = squeeze;
+        self
+    }
+
+    /// Samples a point on the aperture. The samples are distributed uniformly over the shape, or
+    /// proportional to the transmission for masks.
+    pub fn sample(&self, u: Vec2) -> Vec2 {
+        let p = match &self.shape {
+            ApertureShape::Circle => uniform_sample_disk(u),
+            ApertureShape::Polygon { blades, rotation } => {
+                sample_polygon(*blades, rotation.to_radians(), u)
+            }
+            ApertureShape::Mask(mask) => mask.sample(u),
+        };
+
+        vec2(p.x / self.squeeze, p.y)
+    }
+}
+
+/// A grayscale image describing the transmission of the aperture, with white being fully open.
+/// The image covers the unit square [-1, 1]².
+pub struct ApertureMask {
+    distribution: Distribution2D,
+}
+
+impl ApertureMask {
+    pub fn load<P: AsRef<Path> + Debug>(path: P) -> Result<Self> {
+        let img = image::open(&path)
+            .with_context(|| format!("Failed to load aperture mask {:?}", path))?
+            .to_luma32f();
+
+        let (width, height) = img.dimensions();
+        let distribution = Distribution2D::new(img.as_raw(), width as usize, height as usize);
+
+        Ok(Self { distribution })
+    }
+
+    fn sample(&self, u: Vec2) -> Vec2 {
+        let (uv, _pdf) = self.distribution.sample_continuous(u);
+        // flip y from image coordinate space
+        vec2(2.0 * uv.x - 1.0, 1.0 - 2.0 * uv.y)
+    }
+}
+
+/// Samples a regular polygon inscribed into the unit circle, by choosing one of the triangles
+/// between the center and an edge and sampling it uniformly.
+fn sample_polygon(blades: u32, rotation: f32, u: Vec2) -> Vec2 {
+    let blades = blades.max(3);
+
+    // reuse the first sample dimension for picking the triangle
+    let scaled = u.x * blades as f32;
+    let index = (scaled as u32).min(blades - 1);
+    let u = vec2(scaled - index as f32, u.y);
+
+    let angle = 2.0 * PI / blades as f32;
+    let phi0 = rotation + index as f32 * angle;
+    let v0 = vec2(phi0.cos(), phi0.sin());
+    let v1 = vec2((phi0 + angle).cos(), (phi0 + angle).sin());
+
+    // uniform barycentric coordinates
+    let su0 = u.x.sqrt();
+    let b0 = 1.0 - su0;
+    let b1 = u.y * su0;
+
+    b0 * v0 + b1 * v1
+}
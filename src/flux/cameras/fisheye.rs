@@ -0,0 +1,107 @@
+use glam::{vec2, vec3, Mat4, UVec2, Vec3};
+
+use crate::flux::{animation::CameraKeyframes, ray::Ray, CameraSample};
+
+use super::{Camera, CameraRay};
+
+#[derive(Clone, Copy, Debug)]
+pub enum FisheyeMapping {
+    /// The distance from the image center is proportional to the angle from the view direction.
+    Equidistant,
+    /// Every pixel covers the same solid angle.
+    Equisolid,
+}
+
+/// A fisheye camera with a circular image, inscribed into the smaller dimension of the film.
+/// Samples outside of the image circle don't generate any rays.
+pub struct FisheyeCamera {
+    pub resolution: UVec2,
+    view_matrix: Mat4,
+    theta_max: f32,
+    mapping: FisheyeMapping,
+    keyframes: Option<CameraKeyframes>,
+}
+
+#[allow(dead_code)]
+impl FisheyeCamera {
+    pub fn new(
+        resolution: UVec2,
+        position: Vec3,
+        look_at: Vec3,
+        fov: f32,
+        mapping: FisheyeMapping,
+    ) -> Self {
+        let view_matrix = Mat4::look_at_lh(position, look_at, Vec3::Y).inverse();
+        let theta_max = (fov / 2.0).to_radians();
+
+        Self {
+            resolution,
+            view_matrix,
+            theta_max,
+            mapping,
+            keyframes: None,
+        }
+    }
+
+    pub fn with_keyframes(mut self, keyframes: CameraKeyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay> {
+        let resolution = self.resolution.as_vec2();
+        let radius = resolution.min_element() / 2.0;
+
+        // normalized position in the image circle, with y pointing up
+        let p = (sample.p_film - resolution / 2.0) / radius;
+        let p = vec2(p.x, -p.y);
+
+        let r = p.length();
+        if r > 1.0 {
+            return None;
+        }
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.theta_max,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.theta_max / 2.0).sin()).asin(),
+        };
+
+        let (sin_phi, cos_phi) = if r > 0.0 {
+            (p.y / r, p.x / r)
+        } else {
+            (0.0, 1.0)
+        };
+
+        let direction = vec3(theta.sin() * cos_phi, theta.sin() * sin_phi, theta.cos());
+
+        let direction = self.view_matrix.transform_vector3(direction).normalize();
+        let origin = self.view_matrix.transform_point3(Vec3::ZERO);
+
+        let ray = Ray::new(origin, direction, sample.time);
+        Some(CameraRay::new(ray))
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.view_matrix
+    }
+
+    fn set_frame(&mut self, frame: f32) {
+        if let Some(keyframes) = &self.keyframes {
+            self.view_matrix = keyframes.view_matrix(frame);
+        }
+    }
+
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        self.keyframes.as_ref()
+    }
+
+    fn set_keyframes(&mut self, keyframes: CameraKeyframes) {
+        self.keyframes = Some(keyframes);
+    }
+}
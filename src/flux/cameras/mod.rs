@@ -1,12 +1,69 @@
+mod aperture;
+mod fisheye;
+mod orthographic;
 mod perspective;
+mod realistic;
+mod spherical;
 
+pub use aperture::*;
+pub use fisheye::*;
+pub use orthographic::*;
 pub use perspective::*;
+pub use realistic::*;
+pub use spherical::*;
 
-use glam::UVec2;
+use glam::{Mat4, UVec2};
+use strum::EnumString;
 
-use super::{ray::Ray, CameraSample};
+use super::{animation::CameraKeyframes, ray::Ray, CameraSample};
 
 pub trait Camera {
     fn resolution(&self) -> UVec2;
-    fn ray(&self, sample: &CameraSample) -> Ray;
+    /// Generates the primary ray for the given sample. Returns `None` for samples that don't map
+    /// to any direction, e.g. outside the image circle of a fisheye lens.
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay>;
+
+    /// The camera-to-world matrix of the current frame, looking along +Z in camera space.
+    fn view_matrix(&self) -> Mat4;
+
+    /// Evaluates the camera keyframes for the given frame, if there are any.
+    fn set_frame(&mut self, _frame: f32) {}
+
+    /// The keyframes the camera follows in animations, if it has any.
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        None
+    }
+
+    /// Replaces the keyframes of the camera, which takes effect with the next frame.
+    fn set_keyframes(&mut self, _keyframes: CameraKeyframes) {}
+
+    /// Replaces the aperture shape of cameras with a thin lens. Other cameras ignore it.
+    fn set_aperture(&mut self, _aperture: Aperture) {}
+}
+
+pub struct CameraRay {
+    pub ray: Ray,
+    /// Factor for the radiance carried along the ray, e.g. to account for vignetting.
+    pub weight: f32,
+}
+
+impl CameraRay {
+    pub fn new(ray: Ray) -> Self {
+        Self { ray, weight: 1.0 }
+    }
+}
+
+#[derive(Debug, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum CameraType {
+    Perspective,
+    #[strum(serialize = "orthographic", serialize = "ortho")]
+    Orthographic,
+    #[strum(serialize = "spherical", serialize = "equirect")]
+    Spherical,
+    Ods,
+    #[strum(serialize = "fisheye-equidistant", serialize = "fisheye")]
+    FisheyeEquidistant,
+    FisheyeEquisolid,
+    Realistic,
 }
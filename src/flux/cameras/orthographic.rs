@@ -0,0 +1,113 @@
+use glam::{vec3, Mat4, UVec2, Vec3};
+
+use crate::flux::{animation::CameraKeyframes, ray::Ray, CameraSample};
+
+use super::{Aperture, Camera, CameraRay};
+
+pub struct OrthographicCamera {
+    pub resolution: UVec2,
+    half_width: f32,
+    half_height: f32,
+    view_matrix: Mat4,
+    lens_radius: f32,
+    focus_dist: f32,
+    aperture: Aperture,
+    keyframes: Option<CameraKeyframes>,
+}
+
+#[allow(dead_code)]
+impl OrthographicCamera {
+    /// Creates a camera with parallel projection. `size` is the width of the view window in world
+    /// units, its height follows from the aspect ratio of the resolution.
+    pub fn new(
+        resolution: UVec2,
+        position: Vec3,
+        look_at: Vec3,
+        size: f32,
+        lens_radius: f32,
+        focus_dist: f32,
+    ) -> Self {
+        let view_matrix = Mat4::look_at_lh(position, look_at, Vec3::Y).inverse();
+
+        let aspect_ratio = resolution.x as f32 / resolution.y as f32;
+
+        let half_width = size / 2.0;
+        let half_height = half_width / aspect_ratio;
+
+        Self {
+            resolution,
+            half_width,
+            half_height,
+            view_matrix,
+            lens_radius,
+            focus_dist,
+            aperture: Aperture::default(),
+            keyframes: None,
+        }
+    }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn with_keyframes(mut self, keyframes: CameraKeyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay> {
+        let uv = sample.p_film / self.resolution.as_vec2();
+
+        // point on the view window, offset by uv coordinates
+        let p_window = vec3(
+            -self.half_width + 2.0 * self.half_width * uv.x,
+            self.half_height - 2.0 * self.half_height * uv.y,
+            0.0,
+        );
+
+        let (origin, direction) = if self.lens_radius > 0.0 {
+            // all rays through the same point on the window meet again on the focal plane
+            let p_lens = self.lens_radius * self.aperture.sample(sample.p_lens);
+            let origin = p_window + p_lens.extend(0.0);
+            let p_focus = p_window + vec3(0.0, 0.0, self.focus_dist);
+            (origin, p_focus - origin)
+        } else {
+            (p_window, Vec3::Z)
+        };
+
+        let direction = self.view_matrix.transform_vector3(direction).normalize();
+        let origin = self.view_matrix.transform_point3(origin);
+
+        let ray = Ray::new(origin, direction, sample.time);
+        Some(CameraRay::new(ray))
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.view_matrix
+    }
+
+    fn set_frame(&mut self, frame: f32) {
+        if let Some(keyframes) = &self.keyframes {
+            self.view_matrix = keyframes.view_matrix(frame);
+        }
+    }
+
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        self.keyframes.as_ref()
+    }
+
+    fn set_keyframes(&mut self, keyframes: CameraKeyframes) {
+        self.keyframes = Some(keyframes);
+    }
+
+    fn set_aperture(&mut self, aperture: Aperture) {
+        self.aperture = aperture;
+    }
+}
@@ -1,8 +1,8 @@
 use glam::{vec3, Mat4, UVec2, Vec3};
 
-use crate::flux::{ray::Ray, uniform_sample_disk, CameraSample};
+use crate::flux::{animation::CameraKeyframes, ray::Ray, CameraSample};
 
-use super::Camera;
+use super::{Aperture, Camera, CameraRay};
 
 pub struct PerspectiveCamera {
     pub resolution: UVec2,
@@ -11,6 +11,8 @@ pub struct PerspectiveCamera {
     view_matrix: Mat4,
     lens_radius: f32,
     focus_dist: f32,
+    aperture: Aperture,
+    keyframes: Option<CameraKeyframes>,
 }
 
 #[allow(dead_code)]
@@ -37,8 +39,20 @@ impl PerspectiveCamera {
             view_matrix,
             lens_radius,
             focus_dist,
+            aperture: Aperture::default(),
+            keyframes: None,
         }
     }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn with_keyframes(mut self, keyframes: CameraKeyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
 }
 
 impl Camera for PerspectiveCamera {
@@ -46,10 +60,10 @@ impl Camera for PerspectiveCamera {
         self.resolution
     }
 
-    fn ray(&self, sample: &CameraSample) -> Ray {
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay> {
         let uv = sample.p_film / self.resolution.as_vec2();
 
-        let p_lens = self.lens_radius * uniform_sample_disk(sample.p_lens);
+        let p_lens = self.lens_radius * self.aperture.sample(sample.p_lens);
         let origin = p_lens.extend(0.0);
 
         // point on the near plane, offset by uv coordinates
@@ -67,6 +81,29 @@ impl Camera for PerspectiveCamera {
 
         let origin = self.view_matrix.transform_point3(origin);
 
-        Ray::new(origin, direction, sample.time)
+        let ray = Ray::new(origin, direction, sample.time);
+        Some(CameraRay::new(ray))
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.view_matrix
+    }
+
+    fn set_frame(&mut self, frame: f32) {
+        if let Some(keyframes) = &self.keyframes {
+            self.view_matrix = keyframes.view_matrix(frame);
+        }
+    }
+
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        self.keyframes.as_ref()
+    }
+
+    fn set_keyframes(&mut self, keyframes: CameraKeyframes) {
+        self.keyframes = Some(keyframes);
+    }
+
+    fn set_aperture(&mut self, aperture: Aperture) {
+        self.aperture = aperture;
     }
 }
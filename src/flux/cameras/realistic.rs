@@ -0,0 +1,518 @@
+use std::{fmt::Debug, path::Path};
+
+use anyhow::{bail, Context, Result};
+use glam::{vec2, vec3, Mat4, UVec2, Vec2, Vec3};
+use log::{debug, warn};
+use measure_time::debug_time;
+use rayon::prelude::*;
+
+use crate::flux::{
+    animation::CameraKeyframes, ray::Ray, Bounds2, CameraSample, SampledWavelengths,
+};
+
+use super::{Camera, CameraRay};
+
+/// Number of radial segments of the film, each with its own exit pupil bounds.
+const EXIT_PUPIL_SEGMENTS: usize = 64;
+/// Number of rays traced per segment to find the exit pupil bounds.
+const EXIT_PUPIL_SAMPLES: usize = 1 << 16;
+
+/// A single interface of a lens system, i.e. a spherical surface or the aperture stop.
+/// All lengths are in meters.
+#[derive(Clone, Copy, Debug)]
+pub struct LensElement {
+    /// Radius of curvature of the surface, positive for surfaces with their center towards the
+    /// film. Zero denotes the aperture stop.
+    pub curvature_radius: f32,
+    /// Distance to the next interface along the optical axis.
+    pub thickness: f32,
+    /// Index of refraction of the medium behind the interface. Zero for the aperture stop.
+    pub eta: f32,
+    pub aperture_radius: f32,
+}
+
+impl LensElement {
+    fn is_stop(&self) -> bool {
+        self.curvature_radius == 0.0
+    }
+}
+
+/// Loads a lens prescription table, listing the interfaces from the scene side to the film side.
+/// Each row holds the curvature radius, thickness, index of refraction and aperture diameter,
+/// all lengths in millimeters. Lines starting with `#` are ignored.
+pub fn load_lens_file<P: AsRef<Path> + Debug>(path: P) -> Result<Vec<LensElement>> {
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read lens file {:?}", path))?;
+
+    let mut elements = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values = line
+            .split_whitespace()
+            .map(|v| v.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid number in line {} of {:?}", i + 1, path))?;
+
+        let [radius, thickness, eta, aperture] = values[..] else {
+            bail!(
+                "Expected 4 values in line {} of {:?}, got {}",
+                i + 1,
+                path,
+                values.len()
+            );
+        };
+
+        elements.push(LensElement {
+            curvature_radius: radius * 0.001,
+            thickness: thickness * 0.001,
+            eta,
+            aperture_radius: aperture * 0.001 / 2.0,
+        });
+    }
+
+    if elements.is_empty() {
+        bail!("Lens file {:?} doesn't contain any elements", path);
+    }
+
+    Ok(elements)
+}
+
+/// A camera simulating a system of spherical lens elements, following the approach of
+/// "Physically Based Rendering" (Pharr, Jakob, Humphreys). Rays are traced from the film through
+/// all elements, which yields realistic depth of field, distortion and vignetting.
+pub struct RealisticCamera {
+    pub resolution: UVec2,
+    view_matrix: Mat4,
+    elements: Vec<LensElement>,
+    /// Physical extent of the film in meters, centered around the optical axis.
+    film_bounds: Bounds2<Vec2>,
+    film_diagonal: f32,
+    exit_pupil_bounds: Vec<Bounds2<Vec2>>,
+    keyframes: Option<CameraKeyframes>,
+}
+
+#[allow(dead_code)]
+impl RealisticCamera {
+    /// Creates a camera from the given lens elements. `aperture` is the diameter of the aperture
+    /// stop and `film_diagonal` the diagonal of the sensor, both in millimeters. The lens is
+    /// focused on objects at `focus_dist` in front of the film. Fails for lens systems that rays
+    /// parallel to the optical axis can't pass through.
+    pub fn new(
+        resolution: UVec2,
+        position: Vec3,
+        look_at: Vec3,
+        mut elements: Vec<LensElement>,
+        aperture: f32,
+        focus_dist: f32,
+        film_diagonal: f32,
+    ) -> Result<Self> {
+        debug_time!("realistic camera setup");
+
+        let view_matrix = Mat4::look_at_lh(position, look_at, Vec3::Y).inverse();
+
+        for element in elements.iter_mut().filter(|e| e.is_stop()) {
+            let stop_radius = aperture * 0.001 / 2.0;
+            if stop_radius > element.aperture_radius {
+                warn!(
+                    "Aperture diameter {}mm is larger than the maximum of {}mm of the lens, using the maximum",
+                    aperture,
+                    element.aperture_radius * 2000.0
+                );
+            } else {
+                element.aperture_radius = stop_radius;
+            }
+        }
+
+        let film_diagonal = film_diagonal * 0.001;
+        let aspect_ratio = resolution.y as f32 / resolution.x as f32;
+        let x = (film_diagonal.powi(2) / (1.0 + aspect_ratio.powi(2))).sqrt();
+        let y = aspect_ratio * x;
+        let film_bounds = Bounds2::new(vec2(-x, -y) / 2.0, vec2(x, y) / 2.0);
+
+        let mut camera = Self {
+            resolution,
+            view_matrix,
+            elements,
+            film_bounds,
+            film_diagonal,
+            exit_pupil_bounds: Vec::new(),
+            keyframes: None,
+        };
+
+        camera.focus(focus_dist)?;
+
+        let exit_pupil_bounds = (0..EXIT_PUPIL_SEGMENTS)
+            .into_par_iter()
+            .map(|i| {
+                let r0 = i as f32 / EXIT_PUPIL_SEGMENTS as f32 * film_diagonal / 2.0;
+                let r1 = (i + 1) as f32 / EXIT_PUPIL_SEGMENTS as f32 * film_diagonal / 2.0;
+                camera.bound_exit_pupil(r0, r1)
+            })
+            .collect();
+        camera.exit_pupil_bounds = exit_pupil_bounds;
+
+        Ok(camera)
+    }
+
+    pub fn with_keyframes(mut self, keyframes: CameraKeyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
+
+    fn lens_rear_z(&self) -> f32 {
+        self.elements.last().unwrap().thickness
+    }
+
+    fn lens_front_z(&self) -> f32 {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    fn rear_element_radius(&self) -> f32 {
+        self.elements.last().unwrap().aperture_radius
+    }
+
+    /// Moves the lens system relative to the film, so that objects at `focus_dist` are in focus.
+    /// Uses a thick lens approximation of the system.
+    fn focus(&mut self, focus_dist: f32) -> Result<()> {
+        let (pz, fz) = self.thick_lens_approximation()?;
+
+        let f = fz[0] - pz[0];
+        let z = -focus_dist;
+        let c = (pz[1] - z - pz[0]) * (pz[1] - z - 4.0 * f - pz[0]);
+        if c <= 0.0 {
+            bail!(
+                "Can't focus the lens at a distance of {}, the focal length is {}",
+                focus_dist,
+                f
+            );
+        }
+
+        let delta = 0.5 * (pz[1] - z + pz[0] - c.sqrt());
+        self.elements.last_mut().unwrap().thickness += delta;
+
+        debug!(
+            "focal length: {}mm, focus delta: {}mm",
+            f * 1000.0,
+            delta * 1000.0
+        );
+
+        Ok(())
+    }
+
+    /// Returns the z coordinates of the principal planes and focal points of the lens system,
+    /// for the scene side and the film side.
+    fn thick_lens_approximation(&self) -> Result<([f32; 2], [f32; 2])> {
+        // a ray parallel to the optical axis, close enough to pass through the lens
+        let x = 0.001 * self.film_diagonal;
+
+        let r_scene = Ray::new(vec3(x, 0.0, self.lens_front_z() + 1.0), -Vec3::Z, 0.0);
+        let Some(r_film) = self.trace_from_scene(&r_scene) else {
+            bail!("A ray parallel to the optical axis is blocked by the lens from the scene side");
+        };
+        let (pz0, fz0) = cardinal_points(&r_scene, &r_film);
+
+        let r_film = Ray::new(vec3(x, 0.0, self.lens_rear_z() - 1.0), Vec3::Z, 0.0);
+        let Some(r_scene) = self.trace_from_film(&r_film) else {
+            bail!("A ray parallel to the optical axis is blocked by the lens from the film side");
+        };
+        let (pz1, fz1) = cardinal_points(&r_film, &r_scene);
+
+        Ok(([pz0, pz1], [fz0, fz1]))
+    }
+
+    /// Finds the bounds on the rear element of all rays leaving the lens system, for points on the
+    /// film between the radii `r0` and `r1` along the x axis.
+    fn bound_exit_pupil(&self, r0: f32, r1: f32) -> Bounds2<Vec2> {
+        let rear_radius = 1.5 * self.rear_element_radius();
+        let proj_rear_bounds = Bounds2::new(Vec2::splat(-rear_radius), Vec2::splat(rear_radius));
+
+        let mut pupil_bounds = Bounds2::EMPTY;
+        let mut exiting_rays = 0;
+
+        for i in 0..EXIT_PUPIL_SAMPLES {
+            let t = (i as f32 + 0.5) / EXIT_PUPIL_SAMPLES as f32;
+            let p_film = vec3(r0 + t * (r1 - r0), 0.0, 0.0);
+
+            let u = vec2(radical_inverse(2, i), radical_inverse(3, i));
+            let p_rear = proj_rear_bounds.lerp(u);
+
+            // once a point is known to be inside, there's no need to trace rays through it
+            if pupil_bounds.contains(p_rear)
+                || self
+                    .trace_from_film(&raw_ray(p_film, p_rear.extend(self.lens_rear_z()) - p_film))
+                    .is_some()
+            {
+                pupil_bounds = pupil_bounds.union(p_rear);
+                exiting_rays += 1;
+            }
+        }
+
+        if exiting_rays == 0 {
+            return proj_rear_bounds;
+        }
+
+        pupil_bounds
+            .expand(2.0 * proj_rear_bounds.diagonal().length() / (EXIT_PUPIL_SAMPLES as f32).sqrt())
+    }
+
+    /// Samples a point on the rear element that likely lets a ray from `p_film` pass through the
+    /// lens system. Also returns the area of the sampled bounds.
+    fn sample_exit_pupil(&self, p_film: Vec2, u: Vec2) -> (Vec3, f32) {
+        let r_film = p_film.length();
+        let segment = (r_film / (self.film_diagonal / 2.0) * EXIT_PUPIL_SEGMENTS as f32) as usize;
+        let pupil_bounds = self.exit_pupil_bounds[segment.min(EXIT_PUPIL_SEGMENTS - 1)];
+
+        // the bounds were computed along the x axis, rotate them to the film point
+        let p_lens = pupil_bounds.lerp(u);
+        let (sin_theta, cos_theta) = if r_film > 0.0 {
+            (p_film.y / r_film, p_film.x / r_film)
+        } else {
+            (0.0, 1.0)
+        };
+
+        let p_rear = vec3(
+            cos_theta * p_lens.x - sin_theta * p_lens.y,
+            sin_theta * p_lens.x + cos_theta * p_lens.y,
+            self.lens_rear_z(),
+        );
+
+        (p_rear, pupil_bounds.area())
+    }
+
+    /// Traces a ray in camera space from the film through all lens elements. Returns the ray
+    /// leaving the front element, or `None` if the ray is blocked.
+    fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+        // lens space looks down -Z, with the film at z = 0
+        let mut origin = ray.origin * vec3(1.0, 1.0, -1.0);
+        let mut direction = ray.direction * vec3(1.0, 1.0, -1.0);
+        let mut element_z = 0.0;
+
+        for (i, element) in self.elements.iter().enumerate().rev() {
+            element_z -= element.thickness;
+
+            let (t, normal) = if element.is_stop() {
+                if direction.z >= 0.0 {
+                    return None;
+                }
+                ((element_z - origin.z) / direction.z, Vec3::ZERO)
+            } else {
+                let z_center = element_z + element.curvature_radius;
+                intersect_spherical_element(element.curvature_radius, z_center, origin, direction)?
+            };
+
+            let p_hit = origin + t * direction;
+            if p_hit.truncate().length_squared() > element.aperture_radius.powi(2) {
+                return None;
+            }
+            origin = p_hit;
+
+            if !element.is_stop() {
+                let eta_i = element.eta;
+                let eta_t = match i {
+                    0 => 1.0,
+                    _ if self.elements[i - 1].eta == 0.0 => 1.0,
+                    _ => self.elements[i - 1].eta,
+                };
+                direction = refract(-direction.normalize(), normal, eta_i / eta_t)?;
+            }
+        }
+
+        Some(raw_ray(
+            origin * vec3(1.0, 1.0, -1.0),
+            direction * vec3(1.0, 1.0, -1.0),
+        ))
+    }
+
+    /// Traces a ray in camera space from the scene through all lens elements towards the film.
+    /// Returns the ray leaving the rear element, or `None` if the ray is blocked.
+    fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+        let mut origin = ray.origin * vec3(1.0, 1.0, -1.0);
+        let mut direction = ray.direction * vec3(1.0, 1.0, -1.0);
+        let mut element_z = -self.lens_front_z();
+
+        for (i, element) in self.elements.iter().enumerate() {
+            let (t, normal) = if element.is_stop() {
+                ((element_z - origin.z) / direction.z, Vec3::ZERO)
+            } else {
+                let z_center = element_z + element.curvature_radius;
+                intersect_spherical_element(element.curvature_radius, z_center, origin, direction)?
+            };
+
+            let p_hit = origin + t * direction;
+            if p_hit.truncate().length_squared() > element.aperture_radius.powi(2) {
+                return None;
+            }
+            origin = p_hit;
+
+            if !element.is_stop() {
+                let eta_i = match i {
+                    0 => 1.0,
+                    _ if self.elements[i - 1].eta == 0.0 => 1.0,
+                    _ => self.elements[i - 1].eta,
+                };
+                let eta_t = if element.eta != 0.0 { element.eta } else { 1.0 };
+                direction = refract(-direction.normalize(), normal, eta_i / eta_t)?;
+            }
+
+            element_z += element.thickness;
+        }
+
+        Some(raw_ray(
+            origin * vec3(1.0, 1.0, -1.0),
+            direction * vec3(1.0, 1.0, -1.0),
+        ))
+    }
+}
+
+impl Camera for RealisticCamera {
+    fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay> {
+        let uv = sample.p_film / self.resolution.as_vec2();
+
+        // the lens flips the image, so the film is mirrored horizontally
+        let p_film = self.film_bounds.lerp(uv);
+        let p_film = vec3(-p_film.x, p_film.y, 0.0);
+
+        let (p_rear, pupil_area) = self.sample_exit_pupil(p_film.truncate(), sample.p_lens);
+        let film_direction = (p_rear - p_film).normalize();
+
+        let lens_ray = self.trace_from_film(&raw_ray(p_film, film_direction))?;
+
+        let direction = self
+            .view_matrix
+            .transform_vector3(lens_ray.direction)
+            .normalize();
+        let origin = self.view_matrix.transform_point3(lens_ray.origin);
+
+        // cos^4 falloff and the relative size of the sampled exit pupil cause vignetting
+        let cos_theta = film_direction.z;
+        let weight = cos_theta.powi(4) * pupil_area / self.exit_pupil_bounds[0].area();
+
+        Some(CameraRay {
+            ray: Ray::new(origin, direction, sample.time),
+            weight,
+        })
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.view_matrix
+    }
+
+    fn set_frame(&mut self, frame: f32) {
+        if let Some(keyframes) = &self.keyframes {
+            self.view_matrix = keyframes.view_matrix(frame);
+        }
+    }
+
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        self.keyframes.as_ref()
+    }
+
+    fn set_keyframes(&mut self, keyframes: CameraKeyframes) {
+        self.keyframes = Some(keyframes);
+    }
+}
+
+/// Creates a ray without normalizing the direction, as the lens tracing works on tiny scales.
+fn raw_ray(origin: Vec3, direction: Vec3) -> Ray {
+    Ray {
+        origin,
+        direction,
+        time: 0.0,
+        medium: None,
+        wavelengths: SampledWavelengths::Rgb,
+    }
+}
+
+/// Returns the z coordinates of the principal plane and the focal point for a ray parallel to the
+/// optical axis, given the ray entering and the ray leaving the lens system.
+fn cardinal_points(r_in: &Ray, r_out: &Ray) -> (f32, f32) {
+    let tf = -r_out.origin.x / r_out.direction.x;
+    let fz = -r_out.at(tf).z;
+
+    let tp = (r_in.origin.x - r_out.origin.x) / r_out.direction.x;
+    let pz = -r_out.at(tp).z;
+
+    (pz, fz)
+}
+
+fn intersect_spherical_element(
+    radius: f32,
+    z_center: f32,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<(f32, Vec3)> {
+    let o = origin - vec3(0.0, 0.0, z_center);
+
+    let a = direction.length_squared();
+    let b = 2.0 * direction.dot(o);
+    let c = o.length_squared() - radius.powi(2);
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let q = if b < 0.0 {
+        -0.5 * (b - root)
+    } else {
+        -0.5 * (b + root)
+    };
+    let (t0, t1) = (q / a, c / q);
+
+    // depending on the direction and the curvature, either the near or the far hit is on the
+    // element
+    let use_closer_t = (direction.z > 0.0) ^ (radius < 0.0);
+    let t = if use_closer_t { t0.min(t1) } else { t0.max(t1) };
+    if t < 0.0 {
+        return None;
+    }
+
+    let normal = (o + t * direction).normalize();
+    let normal = if normal.dot(-direction) < 0.0 {
+        -normal
+    } else {
+        normal
+    };
+
+    Some((t, normal))
+}
+
+/// Refracts the incident direction `wi` at a surface with normal `n`. `eta` is the ratio of the
+/// indices of refraction of the incident and the transmitted medium. Returns `None` on total
+/// internal reflection.
+fn refract(wi: Vec3, n: Vec3, eta: f32) -> Option<Vec3> {
+    let cos_theta_i = n.dot(wi);
+    let sin2_theta_i = (1.0 - cos_theta_i.powi(2)).max(0.0);
+    let sin2_theta_t = eta.powi(2) * sin2_theta_i;
+
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(eta * -wi + (eta * cos_theta_i - cos_theta_t) * n)
+}
+
+fn radical_inverse(base: usize, mut i: usize) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_n = 1.0;
+    let mut reversed = 0;
+
+    while i > 0 {
+        reversed = reversed * base + i % base;
+        inv_base_n *= inv_base;
+        i /= base;
+    }
+
+    ((reversed as f64 * inv_base_n) as f32).min(1.0 - f32::EPSILON)
+}
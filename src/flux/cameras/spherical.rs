@@ -0,0 +1,102 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, vec3, Mat4, UVec2, Vec3};
+
+use crate::flux::{animation::CameraKeyframes, ray::Ray, CameraSample};
+
+use super::{Camera, CameraRay};
+
+/// A 360° camera, mapping the film to an equirectangular (latitude/longitude) image. The center
+/// of the image looks into the view direction.
+pub struct SphericalCamera {
+    pub resolution: UVec2,
+    view_matrix: Mat4,
+    /// Interpupillary distance for omnidirectional stereo. `None` renders a monoscopic image.
+    ipd: Option<f32>,
+    keyframes: Option<CameraKeyframes>,
+}
+
+#[allow(dead_code)]
+impl SphericalCamera {
+    pub fn new(resolution: UVec2, position: Vec3, look_at: Vec3) -> Self {
+        let view_matrix = Mat4::look_at_lh(position, look_at, Vec3::Y).inverse();
+        Self {
+            resolution,
+            view_matrix,
+            ipd: None,
+            keyframes: None,
+        }
+    }
+
+    /// Creates an omnidirectional stereo (ODS) camera, rendering the left eye into the upper and
+    /// the right eye into the lower half of the image.
+    pub fn stereo(resolution: UVec2, position: Vec3, look_at: Vec3, ipd: f32) -> Self {
+        Self {
+            ipd: Some(ipd),
+            ..Self::new(resolution, position, look_at)
+        }
+    }
+
+    pub fn with_keyframes(mut self, keyframes: CameraKeyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
+}
+
+impl Camera for SphericalCamera {
+    fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    fn ray(&self, sample: &CameraSample) -> Option<CameraRay> {
+        let uv = sample.p_film / self.resolution.as_vec2();
+
+        // In stereo mode, each eye gets one half of the image
+        let (uv, eye) = match self.ipd {
+            Some(_) if uv.y < 0.5 => (vec2(uv.x, 2.0 * uv.y), -1.0),
+            Some(_) => (vec2(uv.x, 2.0 * uv.y - 1.0), 1.0),
+            None => (uv, 0.0),
+        };
+
+        let phi = 2.0 * PI * (uv.x - 0.5);
+        let theta = PI / 2.0 - PI * uv.y;
+
+        let direction = vec3(
+            theta.cos() * phi.sin(),
+            theta.sin(),
+            theta.cos() * phi.cos(),
+        );
+
+        // The eyes sit on a circle around the camera position, tangential to the viewing
+        // direction. The left eye is offset to the left of each direction, the right eye to
+        // the right.
+        let origin = match self.ipd {
+            Some(ipd) => eye * ipd / 2.0 * vec3(phi.cos(), 0.0, -phi.sin()),
+            None => Vec3::ZERO,
+        };
+
+        let direction = self.view_matrix.transform_vector3(direction).normalize();
+        let origin = self.view_matrix.transform_point3(origin);
+
+        let ray = Ray::new(origin, direction, sample.time);
+        Some(CameraRay::new(ray))
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.view_matrix
+    }
+
+    fn set_frame(&mut self, frame: f32) {
+        if let Some(keyframes) = &self.keyframes {
+            self.view_matrix = keyframes.view_matrix(frame);
+        }
+    }
+
+    fn keyframes(&self) -> Option<&CameraKeyframes> {
+        self.keyframes.as_ref()
+    }
+
+    fn set_keyframes(&mut self, keyframes: CameraKeyframes) {
+        self.keyframes = Some(keyframes);
+    }
+}
@@ -0,0 +1,45 @@
+use glam::{vec3, Vec3};
+
+/// The range of visible wavelengths in nm over which spectra are integrated.
+pub const VISIBLE_WAVELENGTHS: (f32, f32) = (360.0, 830.0);
+
+/// The CIE 1931 2° color matching functions at the wavelength `lambda` in nm, using the
+/// multi-lobe Gaussian fit by Wyman, Sloan and Shirley.
+pub fn cie_xyz(lambda: f32) -> Vec3 {
+    let g = |mu: f32, sigma_below: f32, sigma_above: f32| {
+        let sigma = if lambda < mu {
+            sigma_below
+        } else {
+            sigma_above
+        };
+        (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+    };
+
+    vec3(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// The luminance (Y) of a linear sRGB color.
+pub fn luminance(c: Vec3) -> f32 {
+    c.dot(vec3(0.2126, 0.7152, 0.0722))
+}
+
+/// Converts CIE XYZ to linear sRGB with a D65 white point.
+pub fn xyz_to_srgb(xyz: Vec3) -> Vec3 {
+    vec3(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// Converts a chromaticity `x`, `y` and luminance `lum` to CIE XYZ.
+pub fn xyy_to_xyz(x: f32, y: f32, lum: f32) -> Vec3 {
+    if y == 0.0 {
+        return Vec3::ZERO;
+    }
+    vec3(x / y * lum, lum, (1.0 - x - y) / y * lum)
+}
@@ -0,0 +1,136 @@
+use glam::{vec2, Vec2};
+
+/// A piecewise-constant 1D distribution over [0, 1), sampled by inverting its CDF.
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    func_int: f32,
+}
+
+#[allow(dead_code)]
+impl Distribution1D {
+    pub fn new(func: Vec<f32>) -> Self {
+        assert!(!func.is_empty(), "distribution requires at least one value");
+
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1].abs() / n as f32;
+        }
+
+        let func_int = cdf[n];
+        if func_int == 0.0 {
+            // fall back to a uniform distribution
+            for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+                *c = i as f32 / n as f32;
+            }
+        } else {
+            for c in cdf.iter_mut().skip(1) {
+                *c /= func_int;
+            }
+        }
+
+        Self {
+            func,
+            cdf,
+            func_int,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// The integral of the function over [0, 1).
+    pub fn integral(&self) -> f32 {
+        self.func_int
+    }
+
+    /// Samples a continuous value in [0, 1). Returns the value, its pdf and the index of the
+    /// segment it lies in.
+    pub fn sample_continuous(&self, u: f32) -> (f32, f32, usize) {
+        let offset = self.find_segment(u);
+
+        let mut du = u - self.cdf[offset];
+        let segment_width = self.cdf[offset + 1] - self.cdf[offset];
+        if segment_width > 0.0 {
+            du /= segment_width;
+        }
+
+        let pdf = self.segment_pdf(offset);
+        let x = (offset as f32 + du) / self.count() as f32;
+
+        (x.min(1.0 - f32::EPSILON), pdf, offset)
+    }
+
+    /// Samples one of the segments. Returns its index and the discrete probability.
+    pub fn sample_discrete(&self, u: f32) -> (usize, f32) {
+        let offset = self.find_segment(u);
+        (offset, self.discrete_pdf(offset))
+    }
+
+    /// The density of a continuous value `x` in [0, 1).
+    pub fn pdf(&self, x: f32) -> f32 {
+        let offset = ((x * self.count() as f32) as usize).min(self.count() - 1);
+        self.segment_pdf(offset)
+    }
+
+    pub fn discrete_pdf(&self, index: usize) -> f32 {
+        self.cdf[index + 1] - self.cdf[index]
+    }
+
+    fn segment_pdf(&self, index: usize) -> f32 {
+        if self.func_int > 0.0 {
+            self.func[index].abs() / self.func_int
+        } else {
+            1.0
+        }
+    }
+
+    /// Finds the last segment whose CDF value is smaller than or equal to `u`.
+    fn find_segment(&self, u: f32) -> usize {
+        let first_greater = self.cdf.partition_point(|&c| c <= u);
+        first_greater.saturating_sub(1).min(self.count() - 1)
+    }
+}
+
+/// A piecewise-constant 2D distribution over [0, 1)², built from row-major function values.
+/// Samples the row first and then the column within the row.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+#[allow(dead_code)]
+impl Distribution2D {
+    pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+        assert_eq!(func.len(), width * height);
+
+        let conditional = func
+            .chunks_exact(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect::<Vec<_>>();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Samples a point in [0, 1)², with x along the columns and y along the rows. Returns the
+    /// point and its pdf.
+    pub fn sample_continuous(&self, u: Vec2) -> (Vec2, f32) {
+        let (y, pdf_y, row) = self.marginal.sample_continuous(u.y);
+        let (x, pdf_x, _) = self.conditional[row].sample_continuous(u.x);
+
+        (vec2(x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, p: Vec2) -> f32 {
+        let height = self.conditional.len();
+        let row = ((p.y * height as f32) as usize).min(height - 1);
+
+        self.marginal.pdf(p.y) * self.conditional[row].pdf(p.x)
+    }
+}
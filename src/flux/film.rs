@@ -1,5 +1,5 @@
 use glam::{vec3, UVec2, Vec2, Vec3};
-use image::{Rgb, RgbImage};
+use image::{Rgb, Rgb32FImage, RgbImage};
 
 #[derive(Clone, Debug)]
 pub struct Film {
@@ -87,6 +87,14 @@ impl Film {
         }
     }
 
+    /// Returns the linear color values, e.g. for saving high dynamic range EXR files.
+    pub fn to_rgb_f32_image(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.resolution.x, self.resolution.y, |x, y| {
+            let color = self.pixel(x, y).color();
+            Rgb([color.x, color.y, color.z])
+        })
+    }
+
     pub fn to_srgb_image(&self) -> RgbImage {
         RgbImage::from_fn(self.resolution.x, self.resolution.y, |x, y| {
             let pixel = self.pixel(x, y);
@@ -0,0 +1,42 @@
+use glam::{vec3, Vec3};
+
+/// An orthonormal basis, used to express directions in the local shading frame of a surface
+/// with the normal along +Z.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub x: Vec3,
+    pub y: Vec3,
+    pub z: Vec3,
+}
+
+impl Frame {
+    /// A frame around the unit vector `z`, with an arbitrary but consistent orientation of the
+    /// tangents.
+    pub fn from_z(z: Vec3) -> Self {
+        let (x, y) = z.any_orthonormal_pair();
+        Self { x, y, z }
+    }
+
+    /// A frame around the unit vector `z` with its x axis along the projection of `x` onto the
+    /// plane, e.g. the surface tangent `dpdu`, so that anisotropy follows the parameterization.
+    /// Falls back to an arbitrary tangent where `x` is parallel to `z`.
+    pub fn from_xz(x: Vec3, z: Vec3) -> Self {
+        let x = (x - z * z.dot(x)).normalize_or_zero();
+        if x == Vec3::ZERO {
+            return Self::from_z(z);
+        }
+        Self {
+            x,
+            y: z.cross(x),
+            z,
+        }
+    }
+
+    pub fn to_local(self, v: Vec3) -> Vec3 {
+        vec3(v.dot(self.x), v.dot(self.y), v.dot(self.z))
+    }
+
+    pub fn to_world(self, v: Vec3) -> Vec3 {
+        v.x * self.x + v.y * self.y + v.z * self.z
+    }
+}
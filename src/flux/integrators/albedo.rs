@@ -16,8 +16,22 @@ impl AlbedoIntegrator {
 impl Integrator for AlbedoIntegrator {
     fn li(&self, scene: &Scene, ray: &Ray, rng: &mut StdRng) -> LiResult {
         match scene.intersect(ray) {
+            Some(int) if int.primitive.material.is_interface() => {
+                // boundaries of media aren't visible, so the guide shows what lies behind them
+                let continued = int
+                    .spawn_ray_towards(ray.direction)
+                    .with_wavelengths(ray.wavelengths);
+                let result = self.li(scene, &continued, rng);
+                LiResult {
+                    li: result.li,
+                    rays: 1 + result.rays,
+                    groups: vec![],
+                }
+            }
             Some(int) => {
-                let le = int.primitive.material.emitted(&int);
+                let le = ray
+                    .wavelengths
+                    .uplift(int.primitive.material.emitted(&int, -ray.direction));
 
                 match int.primitive.material.scatter(ray, &int, rng) {
                     Some(srec) => {
@@ -27,24 +41,32 @@ impl Integrator for AlbedoIntegrator {
                             LiResult {
                                 li: le + srec.attenuation,
                                 rays: 1,
+                                groups: vec![],
                             }
                         } else {
-                            let result = self.li(scene, &srec.scattered, rng);
+                            let scattered = srec.scattered.with_wavelengths(ray.wavelengths);
+                            let result = self.li(scene, &scattered, rng);
                             LiResult {
                                 li: le + srec.attenuation * result.li,
                                 rays: 1 + result.rays,
+                                groups: vec![],
                             }
                         }
                     }
-                    None => LiResult { li: le, rays: 1 },
+                    None => LiResult {
+                        li: le,
+                        rays: 1,
+                        groups: vec![],
+                    },
                 }
             }
             None => {
                 let background_radiance =
                     scene.lights.iter().map(|light| light.le(ray)).sum::<Vec3>();
                 LiResult {
-                    li: background_radiance,
+                    li: ray.wavelengths.uplift(background_radiance),
                     rays: 1,
+                    groups: vec![],
                 }
             }
         }
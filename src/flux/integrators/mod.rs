@@ -6,14 +6,55 @@ pub use albedo::*;
 pub use normal::*;
 pub use path::*;
 
-use glam::Vec3;
+use glam::Vec4;
 use rand::rngs::StdRng;
 
 use super::{ray::Ray, Scene};
 
 pub struct LiResult {
-    pub li: Vec3,
+    /// The radiance at the wavelengths of the ray, see `SampledWavelengths`.
+    pub li: Vec4,
     pub rays: usize,
+    /// The radiance split by the light group of its emitter, see `Scene::light_group_names`.
+    /// Empty for scenes without light groups, otherwise it sums to `li`.
+    pub groups: Vec<Vec4>,
+}
+
+impl LiResult {
+    /// No radiance, with a zero entry for every light group of the scene.
+    pub fn zero(scene: &Scene, rays: usize) -> Self {
+        Self {
+            li: Vec4::ZERO,
+            rays,
+            groups: vec![Vec4::ZERO; scene.light_group_names().len()],
+        }
+    }
+
+    /// Adds radiance emitted by a light of the group `group`.
+    pub fn add_light(&mut self, group: usize, radiance: Vec4) {
+        self.li += radiance;
+        if let Some(g) = self.groups.get_mut(group) {
+            *g += radiance;
+        }
+    }
+
+    /// Adds the radiance of another result scaled by `scale`, like the throughput of a bounce,
+    /// and counts its rays.
+    pub fn add_scaled(&mut self, other: &LiResult, scale: Vec4) {
+        self.li += scale * other.li;
+        self.rays += other.rays;
+        for (g, other) in self.groups.iter_mut().zip(&other.groups) {
+            *g += scale * *other;
+        }
+    }
+
+    pub fn scaled(mut self, scale: f32) -> Self {
+        self.li *= scale;
+        for g in &mut self.groups {
+            *g *= scale;
+        }
+        self
+    }
 }
 
 pub trait Integrator: Sync {
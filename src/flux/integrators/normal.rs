@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::Vec4;
 use rand::rngs::StdRng;
 
 use crate::flux::{ray::Ray, Scene};
@@ -15,14 +15,32 @@ impl NormalIntegrator {
 
 impl Integrator for NormalIntegrator {
     fn li(&self, scene: &Scene, ray: &Ray, _rng: &mut StdRng) -> LiResult {
-        match scene.intersect(ray) {
+        let mut ray = ray.clone();
+        let mut rays = 1;
+
+        // boundaries of media aren't visible, so the guide shows what lies behind them
+        let hit = loop {
+            match scene.intersect(&ray) {
+                Some(int) if int.primitive.material.is_interface() => {
+                    ray = int
+                        .spawn_ray_towards(ray.direction)
+                        .with_wavelengths(ray.wavelengths);
+                    rays += 1;
+                }
+                hit => break hit,
+            }
+        };
+
+        match hit {
             Some(int) => LiResult {
-                li: (int.n + 1.0) / 2.0,
-                rays: 1,
+                li: ray.wavelengths.uplift((int.ns + 1.0) / 2.0),
+                rays,
+                groups: vec![],
             },
             None => LiResult {
-                li: Vec3::ZERO,
-                rays: 1,
+                li: Vec4::ZERO,
+                rays,
+                groups: vec![],
             },
         }
     }
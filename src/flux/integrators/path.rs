@@ -1,10 +1,19 @@
-use glam::Vec3;
+use std::{borrow::Cow, sync::Arc};
+
+use glam::{Vec3, Vec4};
 use rand::{rngs::StdRng, Rng};
 
-use crate::flux::{ray::Ray, Scene};
+use crate::flux::{
+    interaction::Interaction, lights::LightSampleContext, media::Medium, power_heuristic, ray::Ray,
+    SampledWavelengths, Scene, Subsurface,
+};
 
 use super::{Integrator, LiResult};
 
+/// Relative distance before the sampled light point at which shadow rays stop, so they don't
+/// hit the light source itself.
+const SHADOW_EPSILON: f32 = 0.0001;
+
 pub struct PathTracingIntegrator {
     min_depth: u32,
     max_depth: u32,
@@ -20,60 +29,408 @@ impl PathTracingIntegrator {
         }
     }
 
-    fn li_internal(&self, scene: &Scene, ray: &Ray, rng: &mut StdRng, depth: u32) -> LiResult {
+    fn li_internal(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        rng: &mut StdRng,
+        depth: u32,
+        prev: Option<&PrevVertex>,
+    ) -> LiResult {
         if depth > self.max_depth {
-            return LiResult {
-                li: Vec3::ZERO,
-                rays: 0,
-            };
+            return LiResult::zero(scene, 0);
         }
 
         let rr_factor = if depth > self.min_depth {
             let q = 1.0 - self.rr_stop_prob;
             let s: f32 = rng.gen();
             if s < q {
-                return LiResult {
-                    li: Vec3::ZERO,
-                    rays: 0,
-                };
+                return LiResult::zero(scene, 0);
             }
             1.0 / q
         } else {
             1.0
         };
 
-        match scene.intersect(ray) {
+        let mut result = LiResult::zero(scene, 1);
+        let hit = scene.intersect(ray);
+
+        // the ray may scatter in its medium before it reaches the surface
+        let mut weight = Vec4::ONE;
+        if let Some(medium) = &ray.medium {
+            let t_max = hit.as_ref().map_or(f32::INFINITY, |int| int.t);
+            let sample = medium.sample(ray, t_max, rng);
+            // emission of the medium belongs to the default group, like emissive surfaces
+            // without area lights
+            if sample.emission != Vec3::ZERO {
+                result.add_light(0, ray.wavelengths.uplift(sample.emission));
+            }
+            if let Some(t) = sample.t {
+                let scattered = self.li_medium(scene, ray, medium, ray.at(t), rng, depth);
+                result.add_scaled(&scattered, ray.wavelengths.uplift(sample.weight));
+                return result.scaled(rr_factor);
+            }
+            if sample.weight == Vec3::ZERO {
+                return result.scaled(rr_factor);
+            }
+            weight = ray.wavelengths.uplift(sample.weight);
+        }
+
+        match hit {
+            Some(int) if int.primitive.material.is_interface() => {
+                // Crossing the boundary of a medium doesn't count as a bounce. The direction
+                // stays the same, so light found behind it is still weighted for `prev`.
+                let medium = int.medium_towards(ray.direction, ray.medium.as_ref());
+                let continued = int
+                    .spawn_ray_towards(ray.direction)
+                    .with_medium(medium)
+                    .with_wavelengths(ray.wavelengths);
+                let li = self.li_internal(scene, &continued, rng, depth, prev);
+                result.add_scaled(&li, weight);
+            }
             Some(int) => {
-                let le = int.primitive.material.emitted(&int);
-
-                match int.primitive.material.scatter(ray, &int, rng) {
-                    Some(srec) => {
-                        let result = self.li_internal(scene, &srec.scattered, rng, depth + 1);
-                        LiResult {
-                            li: rr_factor * (le + srec.attenuation * result.li),
-                            rays: 1 + result.rays,
-                        }
+                let le = int.primitive.material.emitted(&int, -ray.direction);
+                if le != Vec3::ZERO {
+                    let le = ray.wavelengths.uplift(le);
+                    // emissive surfaces without area lights belong to the default group
+                    match scene.area_light(&int) {
+                        Some(light_idx) => result.add_light(
+                            scene.light_group(light_idx),
+                            weight * le * emission_weight(scene, light_idx, ray, prev),
+                        ),
+                        None => result.add_light(0, weight * le),
                     }
-                    None => LiResult {
-                        li: rr_factor * le,
-                        rays: 1,
-                    },
                 }
+
+                let scattered = self.li_scattered(scene, ray, &int, rng, depth);
+                result.add_scaled(&scattered, weight);
             }
             None => {
-                let background_radiance =
-                    scene.lights.iter().map(|light| light.le(ray)).sum::<Vec3>();
-                LiResult {
-                    li: rr_factor * background_radiance,
-                    rays: 1,
+                for (light_idx, light) in scene.lights.iter().enumerate() {
+                    let le = light.le(ray);
+                    if le == Vec3::ZERO {
+                        continue;
+                    }
+                    let le = ray.wavelengths.uplift(le);
+                    result.add_light(
+                        scene.light_group(light_idx),
+                        weight * le * emission_weight(scene, light_idx, ray, prev),
+                    );
+                }
+            }
+        }
+
+        result.scaled(rr_factor)
+    }
+
+    /// Estimates the light scattered at the interaction towards the origin of the ray, from
+    /// light sampling and by continuing the path.
+    fn li_scattered(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        int: &Interaction,
+        rng: &mut StdRng,
+        depth: u32,
+    ) -> LiResult {
+        let mut result = LiResult::zero(scene, 0);
+        let material = &int.primitive.material;
+
+        // the path splits up by wavelength, so only the hero wavelength carries on, weighted
+        // for the wavelengths of the camera ray that the result is converted with
+        let terminated = material
+            .is_dispersive()
+            .then(|| ray.wavelengths.terminate_secondary())
+            .flatten();
+        let (ray, weight) = match terminated {
+            Some(wavelengths) => (
+                Cow::Owned(ray.clone().with_wavelengths(wavelengths)),
+                SampledWavelengths::hero_weight(),
+            ),
+            None => (Cow::Borrowed(ray), Vec4::ONE),
+        };
+        let ray = ray.as_ref();
+
+        let Some(srec) = material.scatter(ray, int, rng) else {
+            return result;
+        };
+
+        let direct = self.sample_light(scene, ray, &Vertex::Surface(int), rng);
+        result.add_scaled(&direct, weight);
+
+        // perturbed shading normals may scatter through the surface
+        if !int.is_consistent(-ray.direction, srec.scattered.direction) {
+            return result;
+        }
+
+        let medium = int.medium_towards(srec.scattered.direction, ray.medium.as_ref());
+        let scattered = srec
+            .scattered
+            .with_medium(medium)
+            .with_wavelengths(ray.wavelengths);
+        let indirect = match material.subsurface(int) {
+            Some(interior) if scattered.direction.dot(int.n) < 0.0 => {
+                self.li_subsurface(scene, &interior, &scattered, rng, depth + 1)
+            }
+            _ => {
+                let vertex = PrevVertex {
+                    ctx: LightSampleContext::from(int),
+                    prim_idx: Some(int.prim_idx),
+                    bsdf_pdf: srec.pdf,
+                };
+                self.li_internal(scene, &scattered, rng, depth + 1, Some(&vertex))
+            }
+        };
+        result.add_scaled(&indirect, weight * srec.attenuation);
+        result
+    }
+
+    /// Follows a ray refracted into a subsurface material with a random walk through the medium
+    /// inside, and continues the path where the walk reaches the boundary again. Light can't be
+    /// sampled from inside, so the walk only finds it after leaving.
+    fn li_subsurface(
+        &self,
+        scene: &Scene,
+        interior: &Subsurface,
+        ray: &Ray,
+        rng: &mut StdRng,
+        depth: u32,
+    ) -> LiResult {
+        if depth > self.max_depth {
+            return LiResult::zero(scene, 0);
+        }
+
+        let walk = interior.random_walk(scene, ray, rng);
+        let mut result = LiResult::zero(scene, walk.rays);
+        if let Some((int, exit)) = walk.exit {
+            // the path leaves into the medium it entered from
+            let exit = exit
+                .with_medium(ray.medium.clone())
+                .with_wavelengths(ray.wavelengths);
+            let scattered = self.li_scattered(scene, &exit, &int, rng, depth);
+            result.add_scaled(&scattered, ray.wavelengths.uplift(walk.weight));
+        }
+        result
+    }
+
+    /// Estimates the light scattered at the point `p` in the medium of the ray towards its
+    /// origin, from light sampling and by continuing the path in a direction sampled from the
+    /// phase function.
+    fn li_medium(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        medium: &Arc<dyn Medium>,
+        p: Vec3,
+        rng: &mut StdRng,
+        depth: u32,
+    ) -> LiResult {
+        let mut result = self.sample_light(
+            scene,
+            ray,
+            &Vertex::Medium {
+                p,
+                time: ray.time,
+                medium,
+            },
+            rng,
+        );
+
+        // the direction is sampled exactly proportional to the phase function
+        let phase = medium.phase();
+        let wi = phase.sample(ray.direction, rng.gen());
+        let vertex = PrevVertex {
+            ctx: LightSampleContext::medium(p, ray.time),
+            prim_idx: None,
+            bsdf_pdf: Some(phase.p(ray.direction, wi)),
+        };
+        let scattered = Ray::new(p, wi, ray.time)
+            .with_medium(Some(medium.clone()))
+            .with_wavelengths(ray.wavelengths);
+        let indirect = self.li_internal(scene, &scattered, rng, depth + 1, Some(&vertex));
+        result.add_scaled(&indirect, Vec4::ONE);
+        result
+    }
+
+    /// Estimates the direct light at the vertex by sampling one light, chosen by the light
+    /// sampler of the scene. Area lights can also be found by scattered rays, so their
+    /// contribution is weighted with multiple importance sampling.
+    fn sample_light(
+        &self,
+        scene: &Scene,
+        ray: &Ray,
+        vertex: &Vertex,
+        rng: &mut StdRng,
+    ) -> LiResult {
+        let no_light = LiResult::zero(scene, 0);
+        let ctx = vertex.ctx();
+
+        let Some((light_idx, light_pmf)) = scene.light_sampler.sample(&ctx, rng.gen()) else {
+            return no_light;
+        };
+        let light = &scene.lights[light_idx];
+
+        let linking = scene.light_linking(light_idx);
+        let illuminated = vertex
+            .prim_idx()
+            .is_none_or(|prim_idx| linking.illuminates(prim_idx));
+        if !linking.diffuse || !illuminated {
+            return no_light;
+        }
+
+        let Some(ls) = light.sample_li(scene, &ctx, rng.gen()) else {
+            return no_light;
+        };
+        if ls.pdf == 0.0 || ls.li == Vec3::ZERO {
+            return no_light;
+        }
+
+        let Some((f, scattering_pdf)) = vertex.eval(-ray.direction, ls.wi, rng) else {
+            return no_light;
+        };
+        if f == Vec4::ZERO {
+            return no_light;
+        }
+
+        let mut rays = 0;
+        let mut tr = Vec3::ONE;
+        if linking.shadow {
+            let shadow_ray = vertex.spawn_ray(ray, ls.wi);
+            (tr, rays) = scene.transmittance(shadow_ray, ls.dist * (1.0 - SHADOW_EPSILON), rng);
+            if tr == Vec3::ZERO {
+                return LiResult::zero(scene, rays);
+            }
+        }
+
+        // lights without shadows aren't added by scattered rays, see `emission_weight`
+        let pdf = ls.pdf * light_pmf;
+        let weight = if light.is_delta() || !linking.shadow {
+            1.0
+        } else {
+            power_heuristic(pdf, scattering_pdf)
+        };
+
+        let mut result = LiResult::zero(scene, rays);
+        // the factors are uplifted separately, so that their spectra are multiplied
+        let lambda = &ray.wavelengths;
+        result.add_light(
+            scene.light_group(light_idx),
+            weight * f * lambda.uplift(tr) * lambda.uplift(ls.li) / pdf,
+        );
+        result
+    }
+}
+
+/// A point at which light is scattered towards the origin of a ray.
+enum Vertex<'a> {
+    Surface(&'a Interaction<'a>),
+    Medium {
+        p: Vec3,
+        time: f32,
+        medium: &'a Arc<dyn Medium>,
+    },
+}
+
+impl Vertex<'_> {
+    fn ctx(&self) -> LightSampleContext {
+        match self {
+            Vertex::Surface(int) => LightSampleContext::from(*int),
+            Vertex::Medium { p, time, .. } => LightSampleContext::medium(*p, *time),
+        }
+    }
+
+    /// The primitive for light linking. Media are illuminated by all lights.
+    fn prim_idx(&self) -> Option<usize> {
+        match self {
+            Vertex::Surface(int) => Some(int.prim_idx),
+            Vertex::Medium { .. } => None,
+        }
+    }
+
+    /// The scattering of light arriving from `wi` towards `wo`, including the cosine term on
+    /// surfaces, and the density of sampling `wi`.
+    fn eval(&self, wo: Vec3, wi: Vec3, rng: &mut StdRng) -> Option<(Vec4, f32)> {
+        match self {
+            Vertex::Surface(int) => {
+                if !int.is_consistent(wo, wi) {
+                    return None;
                 }
+                let bsdf = int.primitive.material.eval(wo, wi, int, rng)?;
+                Some((bsdf.f * wi.dot(int.ns).abs(), bsdf.pdf))
+            }
+            Vertex::Medium { medium, .. } => {
+                let p = medium.phase().p(-wo, wi);
+                Some((Vec4::splat(p), p))
             }
         }
     }
+
+    /// Spawns a ray towards `direction` in the medium it travels through, given the ray that
+    /// arrived at the vertex.
+    fn spawn_ray(&self, ray: &Ray, direction: Vec3) -> Ray {
+        match self {
+            Vertex::Surface(int) => {
+                let medium = int.medium_towards(direction, ray.medium.as_ref());
+                int.spawn_ray_towards(direction).with_medium(medium)
+            }
+            Vertex::Medium { p, time, medium } => {
+                Ray::new(*p, direction, *time).with_medium(Some(Arc::clone(medium)))
+            }
+        }
+    }
+}
+
+/// The previous vertex of a path, required to weight light found by scattered rays.
+struct PrevVertex {
+    ctx: LightSampleContext,
+    /// The primitive for light linking, `None` for vertices in media.
+    prim_idx: Option<usize>,
+    /// The density of the scattered direction, `None` for specular bounces.
+    bsdf_pdf: Option<f32>,
+}
+
+/// Weights the emission of the light `light_idx` found by `ray`, honoring the light linking.
+/// After non-specular bounces, the light may also have been found by light sampling at the
+/// previous vertex, so both strategies are combined with multiple importance sampling.
+fn emission_weight(scene: &Scene, light_idx: usize, ray: &Ray, prev: Option<&PrevVertex>) -> f32 {
+    let linking = scene.light_linking(light_idx);
+
+    let Some(prev) = prev else {
+        return if linking.camera { 1.0 } else { 0.0 };
+    };
+    let illuminated = prev
+        .prim_idx
+        .is_none_or(|prim_idx| linking.illuminates(prim_idx));
+    if !illuminated {
+        return 0.0;
+    }
+
+    let Some(bsdf_pdf) = prev.bsdf_pdf else {
+        return if linking.specular { 1.0 } else { 0.0 };
+    };
+    if !linking.diffuse {
+        return 0.0;
+    }
+
+    let light = &scene.lights[light_idx];
+    let light_pdf =
+        scene.light_sampler.pmf(&prev.ctx, light_idx) * light.pdf_li(&prev.ctx, ray.direction);
+
+    // without shadows, light sampling alone accounts for the directions it can sample
+    if !linking.shadow && light_pdf > 0.0 {
+        return 0.0;
+    }
+
+    power_heuristic(bsdf_pdf, light_pdf)
 }
 
 impl Integrator for PathTracingIntegrator {
     fn li(&self, scene: &Scene, ray: &Ray, rng: &mut StdRng) -> LiResult {
-        self.li_internal(scene, ray, rng, 0)
+        // camera rays start in the medium of the camera
+        let ray = Ray {
+            medium: scene.camera_medium.clone(),
+            ..*ray
+        };
+        self.li_internal(scene, &ray, rng, 0, None)
     }
 }
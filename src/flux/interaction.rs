@@ -1,13 +1,30 @@
+use std::sync::Arc;
+
 use glam::{vec3, Vec3};
 
-use super::{primitive::Primitive, ray::Ray};
+use super::{media::Medium, primitive::Primitive, ray::Ray, SampledWavelengths};
 
 pub struct Interaction<'a> {
     pub t: f32,
     pub p: Vec3,
+    /// The geometric normal.
     pub n: Vec3,
+    /// The shading normal, perturbed by normal and bump maps. Always on the same side of the
+    /// surface as `n`.
+    pub ns: Vec3,
+    /// The partial derivatives of the position with respect to the texture coordinates, spanning
+    /// the tangent plane. Shapes without a parameterization use an arbitrary tangent frame.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub time: f32,
+    /// The wavelengths of the ray that found the interaction.
+    pub wavelengths: SampledWavelengths,
     pub primitive: &'a Primitive,
+    /// Index of the primitive in the scene.
+    pub prim_idx: usize,
+    /// Index of the face within the shape, like the triangle of a mesh. Zero for shapes with a
+    /// single face.
+    pub face_idx: usize,
 }
 
 impl<'a> Interaction<'a> {
@@ -15,6 +32,41 @@ impl<'a> Interaction<'a> {
         let origin = offset_ray_origin(self.p, 8.0 * self.n);
         Ray::new(origin, direction, self.time)
     }
+
+    /// Spawns a ray towards `direction`, offset to the side of the surface it leaves on. Used for
+    /// shadow rays, which may leave on either side.
+    pub fn spawn_ray_towards(&self, direction: Vec3) -> Ray {
+        let n = if direction.dot(self.n) < 0.0 {
+            -self.n
+        } else {
+            self.n
+        };
+        let origin = offset_ray_origin(self.p, 8.0 * n);
+        Ray::new(origin, direction, self.time)
+    }
+
+    /// The medium a ray leaving towards `direction` travels through, given the medium of the ray
+    /// that arrived. Only surfaces with a medium interface change the medium.
+    pub fn medium_towards(
+        &self,
+        direction: Vec3,
+        current: Option<&Arc<dyn Medium>>,
+    ) -> Option<Arc<dyn Medium>> {
+        match &self.primitive.medium_interface {
+            Some(interface) if direction.dot(self.n) > 0.0 => interface.outside.clone(),
+            Some(interface) => interface.inside.clone(),
+            None => current.cloned(),
+        }
+    }
+
+    /// Whether the geometric and the shading normal agree on whether light arriving from `wi`
+    /// and leaving towards `wo` is reflected or transmitted. Directions they disagree on would
+    /// leak light through the surface.
+    pub fn is_consistent(&self, wo: Vec3, wi: Vec3) -> bool {
+        let reflect = wo.dot(self.n) * wi.dot(self.n) > 0.0;
+        let reflect_shading = wo.dot(self.ns) * wi.dot(self.ns) > 0.0;
+        reflect == reflect_shading
+    }
 }
 
 fn offset_ray_origin(p: Vec3, d: Vec3) -> Vec3 {
@@ -0,0 +1,414 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, Affine3A, Vec2, Vec3};
+
+use crate::flux::{
+    interaction::Interaction, uniform_cone_pdf, uniform_sample_sphere, Bounds3, Primitive,
+    SampledWavelengths, Scene,
+};
+
+use super::{DirectionCone, Light, LightBounds, LightSample, LightSampleContext};
+
+/// Number of stratified samples per dimension to estimate the average emitted radiance.
+const POWER_SAMPLES: usize = 4;
+
+/// Triangles covering a smaller solid angle are sampled by area, since spherical triangle
+/// sampling becomes numerically unstable. The same holds for triangles covering almost the
+/// entire hemisphere.
+const MIN_SPHERICAL_SAMPLE_AREA: f32 = 3e-4;
+const MAX_SPHERICAL_SAMPLE_AREA: f32 = 6.22;
+
+/// World space geometry of an emissive shape, which can be sampled by area or solid angle.
+#[derive(Clone, Copy, Debug)]
+pub enum AreaShape {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// A triangle facing the side its vertices are ordered counter-clockwise on.
+    Triangle([Vec3; 3]),
+}
+
+impl AreaShape {
+    /// Applies the transform to the shape. Spheres are assumed to be scaled uniformly.
+    pub fn transformed(&self, transform: &Affine3A) -> Self {
+        match *self {
+            AreaShape::Sphere { center, radius } => {
+                let m = transform.matrix3;
+                let scale = m
+                    .x_axis
+                    .length()
+                    .max(m.y_axis.length())
+                    .max(m.z_axis.length());
+                AreaShape::Sphere {
+                    center: transform.transform_point3(center),
+                    radius: radius * scale,
+                }
+            }
+            AreaShape::Triangle(vertices) => {
+                AreaShape::Triangle(vertices.map(|v| transform.transform_point3(v)))
+            }
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        match *self {
+            AreaShape::Sphere { radius, .. } => 4.0 * PI * radius * radius,
+            AreaShape::Triangle([p0, p1, p2]) => 0.5 * (p1 - p0).cross(p2 - p0).length(),
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds3<Vec3> {
+        match *self {
+            AreaShape::Sphere { center, radius } => Bounds3::new(center - radius, center + radius),
+            AreaShape::Triangle([p0, p1, p2]) => Bounds3::new(p0, p0).union(p1).union(p2),
+        }
+    }
+
+    /// The cone bounding the surface normals.
+    pub fn normals(&self) -> DirectionCone {
+        match *self {
+            AreaShape::Sphere { .. } => DirectionCone::entire_sphere(),
+            AreaShape::Triangle(vertices) => DirectionCone::new(triangle_normal(vertices), 1.0),
+        }
+    }
+
+    /// Samples a point uniformly by area. Returns the point and its surface normal.
+    pub fn sample_area(&self, u: Vec2) -> (Vec3, Vec3) {
+        match *self {
+            AreaShape::Sphere { center, radius } => {
+                let n = uniform_sample_sphere(u);
+                (center + radius * n, n)
+            }
+            AreaShape::Triangle(vertices) => {
+                let b = uniform_sample_triangle(u);
+                let p = b[0] * vertices[0] + b[1] * vertices[1] + b[2] * vertices[2];
+                (p, triangle_normal(vertices))
+            }
+        }
+    }
+
+    /// Samples a point on the shape as seen from `p`. Returns the point, its surface normal and
+    /// the solid angle density.
+    pub fn sample(&self, p: Vec3, u: Vec2) -> Option<(Vec3, Vec3, f32)> {
+        match *self {
+            AreaShape::Sphere { center, radius } => {
+                let dc2 = p.distance_squared(center);
+                if dc2 <= radius * radius {
+                    let (p_light, n) = self.sample_area(u);
+                    let pdf = self.area_to_solid_angle(p, p_light, n, 1.0 / self.area())?;
+                    return Some((p_light, n, pdf));
+                }
+
+                // sample the cone of directions subtended by the sphere
+                let dc = dc2.sqrt();
+                let sin_theta_max2 = radius * radius / dc2;
+                let cos_theta_max = (1.0 - sin_theta_max2).max(0.0).sqrt();
+
+                let cos_theta = (1.0 - u.x) + u.x * cos_theta_max;
+                let sin_theta2 = (1.0 - cos_theta * cos_theta).max(0.0);
+                let phi = 2.0 * PI * u.y;
+
+                // the angle at the sphere center between the sampled point and `p`
+                let ds = dc * cos_theta - (radius * radius - dc2 * sin_theta2).max(0.0).sqrt();
+                let cos_alpha = (dc2 + radius * radius - ds * ds) / (2.0 * dc * radius);
+                let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+
+                let wc = (center - p) / dc;
+                let (wc_x, wc_y) = wc.any_orthonormal_pair();
+                let n =
+                    -(sin_alpha * phi.cos() * wc_x + sin_alpha * phi.sin() * wc_y + cos_alpha * wc);
+
+                Some((center + radius * n, n, uniform_cone_pdf(cos_theta_max)))
+            }
+            AreaShape::Triangle(vertices) => {
+                let solid_angle = spherical_triangle_area(vertices, p);
+                if !(MIN_SPHERICAL_SAMPLE_AREA..=MAX_SPHERICAL_SAMPLE_AREA).contains(&solid_angle) {
+                    let (p_light, n) = self.sample_area(u);
+                    let pdf = self.area_to_solid_angle(p, p_light, n, 1.0 / self.area())?;
+                    return Some((p_light, n, pdf));
+                }
+
+                let (b, pdf) = sample_spherical_triangle(vertices, p, u)?;
+                let p_light = b[0] * vertices[0] + b[1] * vertices[1] + b[2] * vertices[2];
+                Some((p_light, triangle_normal(vertices), pdf))
+            }
+        }
+    }
+
+    /// The solid angle density of `sample` choosing the direction `wi` from `p`.
+    pub fn pdf(&self, p: Vec3, wi: Vec3) -> f32 {
+        match *self {
+            AreaShape::Sphere { center, radius } => {
+                let dc2 = p.distance_squared(center);
+                if dc2 <= radius * radius {
+                    return self
+                        .intersect(p, wi)
+                        .and_then(|(p_light, n)| {
+                            self.area_to_solid_angle(p, p_light, n, 1.0 / self.area())
+                        })
+                        .unwrap_or(0.0);
+                }
+
+                let cos_theta_max = (1.0 - radius * radius / dc2).max(0.0).sqrt();
+                if wi.dot((center - p).normalize()) < cos_theta_max {
+                    return 0.0;
+                }
+                uniform_cone_pdf(cos_theta_max)
+            }
+            AreaShape::Triangle(vertices) => {
+                let Some((p_light, n)) = self.intersect(p, wi) else {
+                    return 0.0;
+                };
+
+                let solid_angle = spherical_triangle_area(vertices, p);
+                if !(MIN_SPHERICAL_SAMPLE_AREA..=MAX_SPHERICAL_SAMPLE_AREA).contains(&solid_angle) {
+                    return self
+                        .area_to_solid_angle(p, p_light, n, 1.0 / self.area())
+                        .unwrap_or(0.0);
+                }
+                1.0 / solid_angle
+            }
+        }
+    }
+
+    /// Finds the first point along the ray from `p` in direction `wi` on the shape.
+    fn intersect(&self, p: Vec3, wi: Vec3) -> Option<(Vec3, Vec3)> {
+        match *self {
+            AreaShape::Sphere { center, radius } => {
+                let oc = p - center;
+                let b = oc.dot(wi);
+                let c = oc.length_squared() - radius * radius;
+                let discriminant = b * b - c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+
+                let sqrt_d = discriminant.sqrt();
+                let t = if -b - sqrt_d > 0.0 {
+                    -b - sqrt_d
+                } else {
+                    -b + sqrt_d
+                };
+                if t <= 0.0 {
+                    return None;
+                }
+
+                let p_light = p + t * wi;
+                Some((p_light, (p_light - center) / radius))
+            }
+            AreaShape::Triangle([p0, p1, p2]) => {
+                // Möller-Trumbore
+                let e1 = p1 - p0;
+                let e2 = p2 - p0;
+                let s1 = wi.cross(e2);
+                let divisor = s1.dot(e1);
+                if divisor == 0.0 {
+                    return None;
+                }
+
+                let s = p - p0;
+                let b1 = s.dot(s1) / divisor;
+                let s2 = s.cross(e1);
+                let b2 = wi.dot(s2) / divisor;
+                let t = e2.dot(s2) / divisor;
+                if b1 < 0.0 || b2 < 0.0 || b1 + b2 > 1.0 || t <= 0.0 {
+                    return None;
+                }
+
+                Some((p + t * wi, triangle_normal([p0, p1, p2])))
+            }
+        }
+    }
+
+    fn area_to_solid_angle(&self, p: Vec3, p_light: Vec3, n: Vec3, pdf_area: f32) -> Option<f32> {
+        let to_light = p_light - p;
+        let dist2 = to_light.length_squared();
+        let cos_theta = n.dot(-to_light.normalize_or_zero()).abs();
+        if dist2 == 0.0 || cos_theta == 0.0 {
+            return None;
+        }
+        Some(pdf_area * dist2 / cos_theta)
+    }
+}
+
+/// A light for one emissive face of a primitive. The emission is evaluated with the material
+/// of the primitive, so emission textures are supported.
+pub struct AreaLight {
+    prim_idx: usize,
+    face_idx: usize,
+    shape: AreaShape,
+    phi: f32,
+    two_sided: bool,
+}
+
+impl AreaLight {
+    pub fn new(prim_idx: usize, face_idx: usize, shape: AreaShape, primitive: &Primitive) -> Self {
+        let mut light = Self {
+            prim_idx,
+            face_idx,
+            shape,
+            phi: 0.0,
+            two_sided: primitive.material.is_two_sided(),
+        };
+
+        // estimate the average radiance over the surface for the emitted power
+        let mut radiance = 0.0;
+        for i in 0..POWER_SAMPLES {
+            for j in 0..POWER_SAMPLES {
+                let u = (vec2(i as f32, j as f32) + 0.5) / POWER_SAMPLES as f32;
+                let (p, n) = shape.sample_area(u);
+                radiance += light.emitted(primitive, p, n, n, 0.0).max_element();
+            }
+        }
+        radiance /= (POWER_SAMPLES * POWER_SAMPLES) as f32;
+        let sides = if light.two_sided { 2.0 } else { 1.0 };
+        light.phi = PI * sides * shape.area() * radiance;
+
+        light
+    }
+
+    /// The radiance leaving the point `p` towards `wo`.
+    fn emitted(&self, primitive: &Primitive, p: Vec3, n: Vec3, wo: Vec3, time: f32) -> Vec3 {
+        let (dpdu, dpdv) = n.any_orthonormal_pair();
+        let int = Interaction {
+            t: 0.0,
+            p,
+            n,
+            ns: n,
+            dpdu,
+            dpdv,
+            time,
+            wavelengths: SampledWavelengths::Rgb,
+            primitive,
+            prim_idx: self.prim_idx,
+            face_idx: self.face_idx,
+        };
+        primitive.material.emitted(&int, wo)
+    }
+}
+
+impl Light for AreaLight {
+    fn sample_li(&self, scene: &Scene, ctx: &LightSampleContext, u: Vec2) -> Option<LightSample> {
+        let (p, n, pdf) = self.shape.sample(ctx.p, u)?;
+
+        let to_light = p - ctx.p;
+        let dist = to_light.length();
+        if dist == 0.0 || pdf == 0.0 {
+            return None;
+        }
+
+        let primitive = &scene.primitives[self.prim_idx];
+        Some(LightSample {
+            li: self.emitted(primitive, p, n, -to_light / dist, ctx.time),
+            wi: to_light / dist,
+            pdf,
+            dist,
+        })
+    }
+
+    fn pdf_li(&self, ctx: &LightSampleContext, wi: Vec3) -> f32 {
+        self.shape.pdf(ctx.p, wi)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::new(
+            self.shape.bounds(),
+            self.phi,
+            self.shape.normals(),
+            0.0,
+            self.two_sided,
+        ))
+    }
+}
+
+fn triangle_normal([p0, p1, p2]: [Vec3; 3]) -> Vec3 {
+    (p1 - p0).cross(p2 - p0).normalize()
+}
+
+/// Samples barycentric coordinates uniformly over a triangle.
+fn uniform_sample_triangle(u: Vec2) -> [f32; 3] {
+    let (b0, b1) = if u.x < u.y {
+        let b0 = u.x / 2.0;
+        (b0, u.y - b0)
+    } else {
+        let b1 = u.y / 2.0;
+        (u.x - b1, b1)
+    };
+    [b0, b1, 1.0 - b0 - b1]
+}
+
+/// The solid angle of the triangle as seen from `p`.
+fn spherical_triangle_area(vertices: [Vec3; 3], p: Vec3) -> f32 {
+    let [a, b, c] = vertices.map(|v| (v - p).normalize());
+    (2.0 * a
+        .dot(b.cross(c))
+        .atan2(1.0 + a.dot(b) + a.dot(c) + b.dot(c)))
+    .abs()
+}
+
+/// Samples a direction uniformly within the solid angle of the triangle as seen from `p`, after
+/// Arvo, "Stratified Sampling of Spherical Triangles" (1995), as implemented in pbrt-v4. Returns
+/// the barycentric coordinates of the point the direction hits and the solid angle density.
+fn sample_spherical_triangle(vertices: [Vec3; 3], p: Vec3, u: Vec2) -> Option<([f32; 3], f32)> {
+    let [a, b, c] = vertices.map(|v| (v - p).normalize());
+
+    // normals of the planes through the edges of the spherical triangle
+    let n_ab = a.cross(b).try_normalize()?;
+    let n_bc = b.cross(c).try_normalize()?;
+    let n_ca = c.cross(a).try_normalize()?;
+
+    // the interior angles of the spherical triangle
+    let alpha = n_ab.angle_between(-n_ca);
+    let beta = n_bc.angle_between(-n_ab);
+    let gamma = n_ca.angle_between(-n_bc);
+
+    // sample a sub-triangle with the area proportional to the first sample
+    let a_pi = alpha + beta + gamma;
+    let area = a_pi - PI;
+    if area <= 0.0 {
+        return None;
+    }
+    let ap_pi = PI + u.x * (a_pi - PI);
+
+    let (cos_alpha, sin_alpha) = (alpha.cos(), alpha.sin());
+    let sin_phi = ap_pi.sin() * cos_alpha - ap_pi.cos() * sin_alpha;
+    let cos_phi = ap_pi.cos() * cos_alpha + ap_pi.sin() * sin_alpha;
+    let k1 = cos_phi + cos_alpha;
+    let k2 = sin_phi - sin_alpha * a.dot(b);
+    let cos_bp = ((k2 + (k2 * cos_phi - k1 * sin_phi) * cos_alpha)
+        / ((k2 * sin_phi + k1 * cos_phi) * sin_alpha))
+        .clamp(-1.0, 1.0);
+    let sin_bp = (1.0 - cos_bp * cos_bp).max(0.0).sqrt();
+    let cp = cos_bp * a + sin_bp * gram_schmidt(c, a).normalize();
+
+    // sample the arc between b and the new vertex
+    let cos_theta = 1.0 - u.y * (1.0 - cp.dot(b));
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let w = cos_theta * b + sin_theta * gram_schmidt(cp, b).normalize();
+
+    // intersect the direction with the triangle for the barycentric coordinates
+    let [p0, p1, p2] = vertices;
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+    let s1 = w.cross(e2);
+    let divisor = s1.dot(e1);
+    if divisor == 0.0 {
+        return Some(([1.0 / 3.0; 3], 1.0 / area));
+    }
+    let s = p - p0;
+    let mut b1 = (s.dot(s1) / divisor).clamp(0.0, 1.0);
+    let mut b2 = (w.dot(s.cross(e1)) / divisor).clamp(0.0, 1.0);
+    if b1 + b2 > 1.0 {
+        let sum = b1 + b2;
+        b1 /= sum;
+        b2 /= sum;
+    }
+
+    Some(([1.0 - b1 - b2, b1, b2], 1.0 / area))
+}
+
+/// The part of `v` orthogonal to the normalized vector `w`.
+fn gram_schmidt(v: Vec3, w: Vec3) -> Vec3 {
+    v - v.dot(w) * w
+}
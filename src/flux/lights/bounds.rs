@@ -0,0 +1,176 @@
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+
+use crate::flux::Bounds3;
+
+/// A cone of directions around the axis `w`.
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionCone {
+    pub w: Vec3,
+    pub cos_theta: f32,
+}
+
+impl DirectionCone {
+    pub fn new(w: Vec3, cos_theta: f32) -> Self {
+        Self { w, cos_theta }
+    }
+
+    pub fn entire_sphere() -> Self {
+        Self::new(Vec3::Z, -1.0)
+    }
+
+    /// The smallest cone containing both cones.
+    pub fn union(&self, other: &Self) -> Self {
+        let theta_a = self.cos_theta.clamp(-1.0, 1.0).acos();
+        let theta_b = other.cos_theta.clamp(-1.0, 1.0).acos();
+        let theta_d = self.w.angle_between(other.w);
+
+        // one cone may already contain the other
+        if (theta_d + theta_b).min(PI) <= theta_a {
+            return *self;
+        }
+        if (theta_d + theta_a).min(PI) <= theta_b {
+            return *other;
+        }
+
+        let theta_o = (theta_a + theta_d + theta_b) / 2.0;
+        if theta_o >= PI {
+            return Self::entire_sphere();
+        }
+
+        // rotate the axis of the first cone towards the second one
+        let axis = self.w.cross(other.w);
+        if axis.length_squared() == 0.0 {
+            return Self::entire_sphere();
+        }
+        let w = Quat::from_axis_angle(axis.normalize(), theta_o - theta_a) * self.w;
+
+        Self::new(w, theta_o.cos())
+    }
+}
+
+/// Conservative bounds of the emission of one or many lights, used to estimate their
+/// contribution at a shading point. Follows the light bounds of the BVH light sampler in pbrt-v4.
+#[derive(Clone, Copy, Debug)]
+pub struct LightBounds {
+    pub bounds: Bounds3<Vec3>,
+    /// Emitted power, as a scalar.
+    pub phi: f32,
+    /// The cone of surface normals, or emission directions, of the light.
+    pub normals: DirectionCone,
+    /// Cosine of the angle beyond the normals at which emission falls off to zero.
+    pub cos_theta_e: f32,
+    pub two_sided: bool,
+}
+
+impl LightBounds {
+    pub fn new(
+        bounds: Bounds3<Vec3>,
+        phi: f32,
+        normals: DirectionCone,
+        cos_theta_e: f32,
+        two_sided: bool,
+    ) -> Self {
+        Self {
+            bounds,
+            phi,
+            normals,
+            cos_theta_e,
+            two_sided,
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        if self.phi == 0.0 {
+            return *other;
+        }
+        if other.phi == 0.0 {
+            return *self;
+        }
+
+        Self {
+            bounds: self.bounds.union_bounds(&other.bounds),
+            phi: self.phi + other.phi,
+            normals: self.normals.union(&other.normals),
+            cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+            two_sided: self.two_sided || other.two_sided,
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        self.bounds.centroid()
+    }
+
+    /// Estimates the contribution of the bounded lights at the point `p` with the surface normal
+    /// `n`, which may be zero for points in media.
+    pub fn importance(&self, p: Vec3, n: Vec3) -> f32 {
+        let pc = self.bounds.centroid();
+        // clamp the distance to avoid the singularity for points inside the bounds
+        let d2 = p
+            .distance_squared(pc)
+            .max(self.bounds.diagonal().length() / 2.0);
+
+        let wi = (p - pc).normalize_or_zero();
+        let mut cos_theta_w = self.normals.w.dot(wi);
+        if self.two_sided {
+            cos_theta_w = cos_theta_w.abs();
+        }
+        let sin_theta_w = safe_sqrt(1.0 - cos_theta_w * cos_theta_w);
+
+        // the angle subtended by the bounds as seen from the point
+        let cos_theta_b = {
+            let radius = self.bounds.diagonal().length() / 2.0;
+            let dist2 = p.distance_squared(pc);
+            if dist2 < radius * radius {
+                -1.0
+            } else {
+                safe_sqrt(1.0 - radius * radius / dist2)
+            }
+        };
+        let sin_theta_b = safe_sqrt(1.0 - cos_theta_b * cos_theta_b);
+
+        // the minimum angle between the emission cone and the direction towards the point
+        let cos_theta_o = self.normals.cos_theta;
+        let sin_theta_o = safe_sqrt(1.0 - cos_theta_o * cos_theta_o);
+        let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+        let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+        let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+
+        let mut importance = self.phi * cos_theta_p / d2;
+
+        // the minimum angle of incidence at the point
+        if n != Vec3::ZERO {
+            let cos_theta_i = wi.dot(n).abs();
+            let sin_theta_i = safe_sqrt(1.0 - cos_theta_i * cos_theta_i);
+            importance *= cos_sub_clamped(sin_theta_i, cos_theta_i, sin_theta_b, cos_theta_b);
+        }
+
+        importance.max(0.0)
+    }
+}
+
+/// cos(max(0, a - b)), given the sines and cosines of both angles.
+fn cos_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        1.0
+    } else {
+        cos_a * cos_b + sin_a * sin_b
+    }
+}
+
+/// sin(max(0, a - b)), given the sines and cosines of both angles.
+fn sin_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+    if cos_a > cos_b {
+        0.0
+    } else {
+        sin_a * cos_b - cos_a * sin_b
+    }
+}
+
+fn safe_sqrt(x: f32) -> f32 {
+    x.max(0.0).sqrt()
+}
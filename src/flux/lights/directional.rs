@@ -0,0 +1,43 @@
+use glam::{Vec2, Vec3};
+
+use crate::flux::Scene;
+
+use super::{Light, LightSample, LightSampleContext};
+
+/// A light infinitely far away, illuminating the scene from a single direction, like the sun.
+pub struct DirectionalLight {
+    /// Normalized direction towards the light.
+    wi: Vec3,
+    radiance: Vec3,
+}
+
+#[allow(dead_code)]
+impl DirectionalLight {
+    /// Creates a light shining along `direction`.
+    pub fn new(direction: Vec3, radiance: Vec3) -> Self {
+        Self {
+            wi: -direction.normalize(),
+            radiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(
+        &self,
+        _scene: &Scene,
+        _ctx: &LightSampleContext,
+        _u: Vec2,
+    ) -> Option<LightSample> {
+        Some(LightSample {
+            li: self.radiance,
+            wi: self.wi,
+            pdf: 1.0,
+            dist: f32::INFINITY,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,127 @@
+use std::{f32::consts::PI, fmt::Debug, path::Path};
+
+use anyhow::{Context, Result};
+use glam::{vec2, vec3, Quat, Vec2, Vec3};
+use measure_time::debug_time;
+
+use crate::flux::{color::luminance, distribution::Distribution2D, ray::Ray, Scene};
+
+use super::{Light, LightSample, LightSampleContext};
+
+/// Image based lighting from a high dynamic range environment map in latitude/longitude layout.
+/// Directions are importance sampled proportional to the luminance of the map.
+pub struct EnvironmentLight {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+    distribution: Distribution2D,
+    intensity: f32,
+    /// Rotation from the map into world space.
+    rotation: Quat,
+}
+
+#[allow(dead_code)]
+impl EnvironmentLight {
+    /// Loads a float environment map, e.g. from an EXR or HDR file.
+    pub fn load<P: AsRef<Path> + Debug>(path: P) -> Result<Self> {
+        debug_time!("load environment map");
+
+        let img = image::open(&path)
+            .with_context(|| format!("Failed to load environment map {:?}", path))?
+            .into_rgb32f();
+
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        let pixels: Vec<Vec3> = img.pixels().map(|p| Vec3::from(p.0)).collect();
+
+        // weight by sin(theta) to account for the distortion of the rows towards the poles
+        let func: Vec<f32> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                let theta = PI * ((i / width) as f32 + 0.5) / height as f32;
+                luminance(*pixel) * theta.sin()
+            })
+            .collect();
+        let distribution = Distribution2D::new(&func, width, height);
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            distribution,
+            intensity: 1.0,
+            rotation: Quat::IDENTITY,
+        })
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    fn lookup(&self, uv: Vec2) -> Vec3 {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        self.intensity * self.pixels[y * self.width + x]
+    }
+
+    /// Maps a world space direction to image coordinates, with the top row looking up.
+    fn direction_to_uv(&self, w: Vec3) -> Vec2 {
+        let w = self.rotation.inverse() * w;
+        let theta = w.y.clamp(-1.0, 1.0).acos();
+        let phi = (-w.z).atan2(w.x) + PI;
+        vec2(phi / (2.0 * PI), theta / PI)
+    }
+
+    fn uv_to_direction(&self, uv: Vec2) -> Vec3 {
+        let theta = PI * uv.y;
+        let phi = 2.0 * PI * uv.x - PI;
+        let w = vec3(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            -theta.sin() * phi.sin(),
+        );
+        self.rotation * w
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn le(&self, ray: &Ray) -> Vec3 {
+        self.lookup(self.direction_to_uv(ray.direction))
+    }
+
+    fn sample_li(&self, _scene: &Scene, _ctx: &LightSampleContext, u: Vec2) -> Option<LightSample> {
+        let (uv, map_pdf) = self.distribution.sample_continuous(u);
+        if map_pdf == 0.0 {
+            return None;
+        }
+
+        let sin_theta = (PI * uv.y).sin();
+        if sin_theta == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            li: self.lookup(uv),
+            wi: self.uv_to_direction(uv),
+            pdf: map_pdf / (2.0 * PI * PI * sin_theta),
+            dist: f32::INFINITY,
+        })
+    }
+
+    fn pdf_li(&self, _ctx: &LightSampleContext, wi: Vec3) -> f32 {
+        let uv = self.direction_to_uv(wi);
+
+        let sin_theta = (PI * uv.y).sin();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        self.distribution.pdf(uv) / (2.0 * PI * PI * sin_theta)
+    }
+}
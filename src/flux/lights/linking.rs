@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+/// Controls which primitives a light illuminates and which kinds of rays see it. Primitives are
+/// identified by their index in the scene, so a transformed instance is linked as a whole.
+#[derive(Clone, Debug)]
+pub struct LightLinking {
+    /// If set, the light only illuminates these primitives.
+    include: Option<HashSet<usize>>,
+    /// Primitives the light doesn't illuminate.
+    exclude: HashSet<usize>,
+    /// Whether the light is seen directly by camera rays.
+    pub camera: bool,
+    /// Whether the light contributes to diffuse and glossy reflection, either through light
+    /// sampling or after a non-specular bounce.
+    pub diffuse: bool,
+    /// Whether the light is seen after a specular bounce, like in mirrors or through glass.
+    pub specular: bool,
+    /// Whether the light casts shadows. Lights without shadows are only found by light sampling.
+    pub shadow: bool,
+}
+
+impl Default for LightLinking {
+    fn default() -> Self {
+        Self {
+            include: None,
+            exclude: HashSet::new(),
+            camera: true,
+            diffuse: true,
+            specular: true,
+            shadow: true,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl LightLinking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the light to only illuminate the given primitives.
+    pub fn with_include(mut self, prims: impl IntoIterator<Item = usize>) -> Self {
+        self.include = Some(prims.into_iter().collect());
+        self
+    }
+
+    pub fn with_exclude(mut self, prims: impl IntoIterator<Item = usize>) -> Self {
+        self.exclude = prims.into_iter().collect();
+        self
+    }
+
+    pub fn with_camera(mut self, visible: bool) -> Self {
+        self.camera = visible;
+        self
+    }
+
+    pub fn with_diffuse(mut self, visible: bool) -> Self {
+        self.diffuse = visible;
+        self
+    }
+
+    pub fn with_specular(mut self, visible: bool) -> Self {
+        self.specular = visible;
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Whether the light illuminates the primitive `prim_idx`.
+    pub fn illuminates(&self, prim_idx: usize) -> bool {
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|include| include.contains(&prim_idx));
+        included && !self.exclude.contains(&prim_idx)
+    }
+}
@@ -1,13 +1,108 @@
+mod area;
+mod bounds;
+mod directional;
+mod environment;
 mod infinite;
+mod linking;
+mod point;
+mod preetham;
+mod profile;
+mod sampler;
 mod sky;
+mod spot;
+mod sun;
+mod tree;
 
+pub use area::*;
+pub use bounds::*;
+pub use directional::*;
+pub use environment::*;
 pub use infinite::*;
+pub use linking::*;
+pub use point::*;
+pub use preetham::*;
+pub use profile::*;
+pub use sampler::*;
 pub use sky::*;
+pub use spot::*;
+pub use sun::*;
+pub use tree::*;
 
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 
-use super::ray::Ray;
+use super::{interaction::Interaction, ray::Ray, Scene};
 
 pub trait Light {
-    fn le(&self, ray: &Ray) -> Vec3;
+    /// Radiance arriving along a ray that escaped the scene.
+    fn le(&self, _ray: &Ray) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// Samples a direction from the point of `ctx` towards the light. Returns `None` for lights
+    /// that can't be sampled explicitly, which are then only found by rays escaping the scene.
+    fn sample_li(
+        &self,
+        _scene: &Scene,
+        _ctx: &LightSampleContext,
+        _u: Vec2,
+    ) -> Option<LightSample> {
+        None
+    }
+
+    /// The solid angle density of `sample_li` choosing the direction `wi`. Always zero for delta
+    /// lights, since no other sampling technique can find them.
+    fn pdf_li(&self, _ctx: &LightSampleContext, _wi: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Whether the light is described by a delta distribution, either in position or direction.
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Bounds of the emission for choosing between many lights. `None` for infinitely distant
+    /// lights, which can't be bounded.
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+/// The point light is sampled for, either on a surface or in a medium.
+#[derive(Clone, Copy)]
+pub struct LightSampleContext {
+    pub p: Vec3,
+    /// The geometric normal of the surface, zero for points in media.
+    pub n: Vec3,
+    pub time: f32,
+}
+
+impl LightSampleContext {
+    /// A point in a medium, which receives light from all directions.
+    pub fn medium(p: Vec3, time: f32) -> Self {
+        Self {
+            p,
+            n: Vec3::ZERO,
+            time,
+        }
+    }
+}
+
+impl From<&Interaction<'_>> for LightSampleContext {
+    fn from(int: &Interaction) -> Self {
+        Self {
+            p: int.p,
+            n: int.n,
+            time: int.time,
+        }
+    }
+}
+
+pub struct LightSample {
+    /// Incident radiance at the point.
+    pub li: Vec3,
+    /// Normalized direction from the interaction towards the light.
+    pub wi: Vec3,
+    pub pdf: f32,
+    /// Distance to the sampled point on the light, which has to be unoccluded.
+    pub dist: f32,
 }
@@ -0,0 +1,52 @@
+use std::f32::consts::PI;
+
+use glam::{Vec2, Vec3};
+
+use crate::flux::{Bounds3, Scene};
+
+use super::{DirectionCone, Light, LightBounds, LightSample, LightSampleContext};
+
+/// An isotropic point light. `intensity` is the emitted power per solid angle.
+pub struct PointLight {
+    position: Vec3,
+    intensity: Vec3,
+}
+
+#[allow(dead_code)]
+impl PointLight {
+    pub fn new(position: Vec3, intensity: Vec3) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, _scene: &Scene, ctx: &LightSampleContext, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.position - ctx.p;
+        let dist_squared = to_light.length_squared();
+        let dist = dist_squared.sqrt();
+
+        Some(LightSample {
+            li: self.intensity / dist_squared,
+            wi: to_light / dist,
+            pdf: 1.0,
+            dist,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::new(
+            Bounds3::new(self.position, self.position),
+            4.0 * PI * self.intensity.max_element(),
+            DirectionCone::entire_sphere(),
+            0.0,
+            false,
+        ))
+    }
+}
@@ -0,0 +1,153 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use glam::{vec3, Vec3};
+
+use crate::flux::{
+    color::{xyy_to_xyz, xyz_to_srgb},
+    ray::Ray,
+};
+
+use super::{sun_radiance, sun_solid_angle, Light, SunPosition, SUN_SKY_RADIANCE_SCALE};
+
+/// The analytic daylight model of Preetham, Shirley and Smits, "A Practical Analytic Model for
+/// Daylight" (1999). The sky below the horizon is a diffuse ground plane, lit by the sky and
+/// the sun. Pairs with `SunLight` for the sun disk itself.
+pub struct PreethamSkyLight {
+    /// Normalized direction towards the sun, kept above the horizon where the model is valid.
+    sun_direction: Vec3,
+    sun_theta: f32,
+    /// Perez coefficients A-E for the luminance Y and the chromaticities x and y.
+    perez: [[f32; 5]; 3],
+    /// Y, x and y in the zenith.
+    zenith: [f32; 3],
+    ground: Vec3,
+    intensity: f32,
+}
+
+#[allow(dead_code)]
+impl PreethamSkyLight {
+    /// Creates the sky for the given sun position. `turbidity` describes the haziness of the
+    /// atmosphere, from 2 for a clear to about 10 for a hazy sky.
+    pub fn new(sun: SunPosition, turbidity: f32, ground_albedo: Vec3) -> Self {
+        let t = turbidity;
+
+        let sun_direction = SunPosition {
+            elevation: sun.elevation.max(0.0),
+            ..sun
+        }
+        .direction();
+        let sun_theta = sun_direction.y.clamp(0.0, 1.0).acos();
+
+        #[rustfmt::skip]
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_theta);
+        // zenith luminance in kcd/m²
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let t_vec = vec3(t * t, t, 1.0);
+        let theta = [sun_theta.powi(3), sun_theta.powi(2), sun_theta, 1.0];
+        #[rustfmt::skip]
+        let zenith_x = chromaticity(t_vec, theta, [
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        #[rustfmt::skip]
+        let zenith_yc = chromaticity(t_vec, theta, [
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let mut sky = Self {
+            sun_direction,
+            sun_theta,
+            perez,
+            zenith: [zenith_y.max(0.0), zenith_x, zenith_yc],
+            ground: Vec3::ZERO,
+            intensity: 1.0,
+        };
+
+        // the ground reflects the light of the sky and the sun diffusely
+        let sun_irradiance =
+            sun_radiance(sun, turbidity) * sun_solid_angle() * sun.direction().y.max(0.0);
+        sky.ground = ground_albedo * (sky.sky_irradiance() + sun_irradiance) / PI;
+
+        sky
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// The radiance of the sky above the horizon.
+    fn sky_radiance(&self, w: Vec3) -> Vec3 {
+        // the model breaks down at the horizon, so keep the view slightly above it
+        let theta = w.y.clamp(0.001, 1.0).acos().min(FRAC_PI_2 - 0.001);
+        let gamma = w.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let [lum, x, y] = [0, 1, 2].map(|i| {
+            let f = perez(self.perez[i], theta, gamma) / perez(self.perez[i], 0.0, self.sun_theta);
+            self.zenith[i] * f
+        });
+
+        let rgb = xyz_to_srgb(xyy_to_xyz(x, y, lum));
+        rgb.max(Vec3::ZERO) * SUN_SKY_RADIANCE_SCALE
+    }
+
+    /// Integrates the sky radiance over the upper hemisphere, for the irradiance on the ground.
+    fn sky_irradiance(&self) -> Vec3 {
+        const THETA_STEPS: usize = 32;
+        const PHI_STEPS: usize = 64;
+
+        let d_theta = FRAC_PI_2 / THETA_STEPS as f32;
+        let d_phi = 2.0 * PI / PHI_STEPS as f32;
+
+        let mut irradiance = Vec3::ZERO;
+        for i in 0..THETA_STEPS {
+            let theta = (i as f32 + 0.5) * d_theta;
+            for j in 0..PHI_STEPS {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let w = vec3(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                irradiance += self.sky_radiance(w) * theta.cos() * theta.sin() * d_theta * d_phi;
+            }
+        }
+
+        irradiance
+    }
+}
+
+impl Light for PreethamSkyLight {
+    fn le(&self, ray: &Ray) -> Vec3 {
+        let radiance = if ray.direction.y >= 0.0 {
+            self.sky_radiance(ray.direction)
+        } else {
+            self.ground
+        };
+
+        self.intensity * radiance
+    }
+}
+
+/// The Perez luminance distribution for a view with zenith angle `theta` and angle `gamma` to
+/// the sun.
+fn perez([a, b, c, d, e]: [f32; 5], theta: f32, gamma: f32) -> f32 {
+    (1.0 + a * (b / theta.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Evaluates the zenith chromaticity polynomial in turbidity and sun zenith angle.
+fn chromaticity(t: Vec3, theta: [f32; 4], m: [[f32; 4]; 3]) -> f32 {
+    (0..3)
+        .map(|i| t[i] * (0..4).map(|j| m[i][j] * theta[j]).sum::<f32>())
+        .sum()
+}
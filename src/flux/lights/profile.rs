@@ -0,0 +1,125 @@
+use std::{fmt::Debug, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+/// A rotationally symmetric angular emission profile, normalized to a maximum of 1. Angles are
+/// measured from the main axis of the light.
+pub struct LightProfile {
+    /// Ascending angles in degrees.
+    angles: Vec<f32>,
+    values: Vec<f32>,
+}
+
+#[allow(dead_code)]
+impl LightProfile {
+    pub fn new(angles: Vec<f32>, values: Vec<f32>) -> Self {
+        assert_eq!(angles.len(), values.len());
+        assert!(
+            !angles.is_empty(),
+            "light profile requires at least one sample"
+        );
+
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        let values = if max > 0.0 {
+            values.iter().map(|v| v / max).collect()
+        } else {
+            values
+        };
+
+        Self { angles, values }
+    }
+
+    /// Loads a photometric profile in the IES LM-63 format. The candela values of all horizontal
+    /// angles are averaged, so the profile only depends on the vertical angle.
+    pub fn load_ies<P: AsRef<Path> + Debug>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read IES profile {:?}", path))?;
+
+        // skip the keyword header up to the tilt specification
+        let mut lines = content.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find(|line| line.starts_with("TILT="))
+            .with_context(|| format!("Missing TILT line in IES profile {:?}", path))?;
+
+        let values = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid number in IES profile {:?}", path))?;
+        let mut values = values.into_iter();
+        let mut next = || {
+            values
+                .next()
+                .with_context(|| format!("Unexpected end of IES profile {:?}", path))
+        };
+
+        if tilt == "TILT=INCLUDE" {
+            // lamp-to-luminaire geometry, followed by the pairs of tilt angles and factors
+            next()?;
+            let pairs = next()? as usize;
+            for _ in 0..2 * pairs {
+                next()?;
+            }
+        } else if tilt != "TILT=NONE" {
+            bail!("Unsupported tilt file in IES profile {:?}", path);
+        }
+
+        let _lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        // photometric type, units, dimensions, ballast factor, future use and input watts
+        for _ in 0..8 {
+            next()?;
+        }
+
+        let vertical_angles = (0..vertical_count)
+            .map(|_| next())
+            .collect::<Result<Vec<_>>>()?;
+        for _ in 0..horizontal_count {
+            next()?;
+        }
+
+        let mut candela = vec![0.0; vertical_count];
+        for _ in 0..horizontal_count {
+            for value in candela.iter_mut() {
+                *value += multiplier * next()? / horizontal_count as f32;
+            }
+        }
+
+        Ok(Self::new(vertical_angles, candela))
+    }
+
+    /// Evaluates the profile for the angle `theta` in radians, interpolating linearly between the
+    /// samples. Angles outside of the profile don't receive any light.
+    pub fn evaluate(&self, theta: f32) -> f32 {
+        let angle = theta.to_degrees();
+
+        let first = self.angles[0];
+        let last = self.angles[self.angles.len() - 1];
+        if angle < first || angle > last {
+            return 0.0;
+        }
+
+        if self.angles.len() == 1 {
+            return self.values[0];
+        }
+
+        let i = self
+            .angles
+            .partition_point(|&a| a <= angle)
+            .clamp(1, self.angles.len() - 1);
+        let (a0, a1) = (self.angles[i - 1], self.angles[i]);
+        let t = if a1 > a0 {
+            (angle - a0) / (a1 - a0)
+        } else {
+            0.0
+        };
+
+        (1.0 - t) * self.values[i - 1] + t * self.values[i]
+    }
+}
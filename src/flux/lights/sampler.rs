@@ -0,0 +1,149 @@
+use strum::EnumString;
+
+use crate::flux::distribution::Distribution1D;
+
+use super::{Light, LightSampleContext, LightTreeSampler};
+
+/// Chooses one of the scene lights for next event estimation.
+pub trait LightSampler {
+    /// Samples a light for the point of `ctx`. Returns the index of the light and the probability
+    /// of choosing it.
+    fn sample(&self, ctx: &LightSampleContext, u: f32) -> Option<(usize, f32)>;
+
+    /// The probability of `sample` choosing the light `light_idx` for the point.
+    fn pmf(&self, ctx: &LightSampleContext, light_idx: usize) -> f32;
+}
+
+#[derive(Clone, Copy, Debug, Default, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum LightSamplerType {
+    Uniform,
+    Power,
+    #[default]
+    Tree,
+}
+
+pub fn build_light_sampler(
+    sampler_type: LightSamplerType,
+    lights: &[Box<dyn Light>],
+) -> Box<dyn LightSampler> {
+    match sampler_type {
+        LightSamplerType::Uniform => Box::new(UniformLightSampler::new(lights.len())),
+        LightSamplerType::Power => Box::new(PowerLightSampler::new(lights)),
+        LightSamplerType::Tree => Box::new(LightTreeSampler::new(lights)),
+    }
+}
+
+/// Chooses all lights with the same probability.
+pub struct UniformLightSampler {
+    count: usize,
+}
+
+impl UniformLightSampler {
+    pub fn new(count: usize) -> Self {
+        Self { count }
+    }
+}
+
+impl LightSampler for UniformLightSampler {
+    fn sample(&self, _ctx: &LightSampleContext, u: f32) -> Option<(usize, f32)> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let idx = ((u * self.count as f32) as usize).min(self.count - 1);
+        Some((idx, 1.0 / self.count as f32))
+    }
+
+    fn pmf(&self, _ctx: &LightSampleContext, _light_idx: usize) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            1.0 / self.count as f32
+        }
+    }
+}
+
+/// Chooses bounded lights proportional to their emitted power, independent of the shading
+/// point. Infinite lights are chosen uniformly, with the bounded lights counting as one of them.
+pub struct PowerLightSampler {
+    infinite: Vec<usize>,
+    bounded: Vec<usize>,
+    distribution: Option<Distribution1D>,
+    /// Index into `bounded` for every scene light.
+    bounded_idx: Vec<Option<usize>>,
+}
+
+impl PowerLightSampler {
+    pub fn new(lights: &[Box<dyn Light>]) -> Self {
+        let mut infinite = vec![];
+        let mut bounded = vec![];
+        let mut phi = vec![];
+        let mut bounded_idx = vec![None; lights.len()];
+
+        for (idx, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) if bounds.phi > 0.0 => {
+                    bounded_idx[idx] = Some(bounded.len());
+                    bounded.push(idx);
+                    phi.push(bounds.phi);
+                }
+                Some(_) => {}
+                None => infinite.push(idx),
+            }
+        }
+
+        let distribution = (!phi.is_empty()).then(|| Distribution1D::new(phi));
+
+        Self {
+            infinite,
+            bounded,
+            distribution,
+            bounded_idx,
+        }
+    }
+}
+
+impl LightSampler for PowerLightSampler {
+    fn sample(&self, _ctx: &LightSampleContext, u: f32) -> Option<(usize, f32)> {
+        let p_infinite = infinite_probability(self.infinite.len(), !self.bounded.is_empty());
+        if u < p_infinite {
+            return Some(sample_infinite(&self.infinite, u / p_infinite, p_infinite));
+        }
+
+        let distribution = self.distribution.as_ref()?;
+        let u = ((u - p_infinite) / (1.0 - p_infinite)).min(ONE_MINUS_EPSILON);
+        let (idx, pmf) = distribution.sample_discrete(u);
+        Some((self.bounded[idx], (1.0 - p_infinite) * pmf))
+    }
+
+    fn pmf(&self, _ctx: &LightSampleContext, light_idx: usize) -> f32 {
+        let p_infinite = infinite_probability(self.infinite.len(), !self.bounded.is_empty());
+
+        match (&self.distribution, self.bounded_idx[light_idx]) {
+            (Some(distribution), Some(idx)) => (1.0 - p_infinite) * distribution.discrete_pdf(idx),
+            _ if self.infinite.contains(&light_idx) => p_infinite / self.infinite.len() as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+/// The largest float below one, to keep remapped sample values in [0, 1).
+pub const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// The probability of choosing one of the infinite lights, which are treated like a single
+/// bounded light each.
+pub fn infinite_probability(infinite_count: usize, has_bounded: bool) -> f32 {
+    let total = infinite_count + has_bounded as usize;
+    if total == 0 {
+        0.0
+    } else {
+        infinite_count as f32 / total as f32
+    }
+}
+
+/// Chooses one of the infinite lights uniformly.
+pub fn sample_infinite(infinite: &[usize], u: f32, p_infinite: f32) -> (usize, f32) {
+    let idx = ((u * infinite.len() as f32) as usize).min(infinite.len() - 1);
+    (infinite[idx], p_infinite / infinite.len() as f32)
+}
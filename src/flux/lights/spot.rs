@@ -0,0 +1,106 @@
+use std::f32::consts::PI;
+
+use glam::{Vec2, Vec3};
+
+use crate::flux::{Bounds3, Scene};
+
+use super::{DirectionCone, Light, LightBounds, LightProfile, LightSample, LightSampleContext};
+
+/// A point light emitting in a cone around its direction, with a smooth falloff towards the
+/// edge of the cone.
+pub struct SpotLight {
+    position: Vec3,
+    /// Normalized direction of the cone axis.
+    direction: Vec3,
+    intensity: Vec3,
+    cos_total_width: f32,
+    cos_falloff_start: f32,
+    profile: Option<LightProfile>,
+}
+
+#[allow(dead_code)]
+impl SpotLight {
+    /// Creates a spot light at `position`, pointing at `look_at`. The cone has a half-angle of
+    /// `total_width` degrees, and the intensity falls off beyond `falloff_start` degrees.
+    pub fn new(
+        position: Vec3,
+        look_at: Vec3,
+        intensity: Vec3,
+        total_width: f32,
+        falloff_start: f32,
+    ) -> Self {
+        Self {
+            position,
+            direction: (look_at - position).normalize(),
+            intensity,
+            cos_total_width: total_width.to_radians().cos(),
+            cos_falloff_start: falloff_start.min(total_width).to_radians().cos(),
+            profile: None,
+        }
+    }
+
+    /// Modulates the emission by a measured angular distribution, with the vertical angle of the
+    /// profile measured from the cone axis.
+    pub fn with_profile(mut self, profile: LightProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    fn falloff(&self, w: Vec3) -> f32 {
+        let cos_theta = w.dot(self.direction);
+
+        let cone = if cos_theta < self.cos_total_width {
+            0.0
+        } else if cos_theta >= self.cos_falloff_start {
+            1.0
+        } else {
+            let delta = (cos_theta - self.cos_total_width)
+                / (self.cos_falloff_start - self.cos_total_width);
+            delta.powi(4)
+        };
+
+        match &self.profile {
+            Some(profile) => cone * profile.evaluate(cos_theta.clamp(-1.0, 1.0).acos()),
+            None => cone,
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, _scene: &Scene, ctx: &LightSampleContext, _u: Vec2) -> Option<LightSample> {
+        let to_light = self.position - ctx.p;
+        let dist_squared = to_light.length_squared();
+        let dist = dist_squared.sqrt();
+        let wi = to_light / dist;
+
+        let falloff = self.falloff(-wi);
+        if falloff == 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            li: self.intensity * falloff / dist_squared,
+            wi,
+            pdf: 1.0,
+            dist,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        // like pbrt-v4, bound the power by an isotropic point light of the same intensity
+        let theta_falloff = self.cos_falloff_start.acos();
+        let theta_total = self.cos_total_width.acos();
+
+        Some(LightBounds::new(
+            Bounds3::new(self.position, self.position),
+            4.0 * PI * self.intensity.max_element(),
+            DirectionCone::new(self.direction, self.cos_falloff_start),
+            (theta_total - theta_falloff).cos(),
+            false,
+        ))
+    }
+}
@@ -0,0 +1,179 @@
+use std::f32::consts::PI;
+
+use glam::{vec3, Mat3, Vec2, Vec3};
+
+use crate::flux::{ray::Ray, uniform_cone_pdf, uniform_sample_cone, Scene};
+
+use super::{Light, LightSample, LightSampleContext};
+
+/// Angular radius of the sun disk as seen from earth, in degrees.
+const SUN_ANGULAR_RADIUS: f32 = 0.2665;
+/// Luminance of the sun disk outside of the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f32 = 1.6e6;
+
+/// Scales radiance in kcd/m² to scene radiance, so a white surface lit by the sun is roughly 1.
+pub const SUN_SKY_RADIANCE_SCALE: f32 = 1.0 / 30.0;
+
+/// The position of the sun in the sky, with the azimuth measured clockwise from north. The
+/// world is oriented with +Y up, +Z north and +X east.
+#[derive(Clone, Copy, Debug)]
+pub struct SunPosition {
+    /// Angle above the horizon in degrees.
+    pub elevation: f32,
+    /// Angle from north towards east in degrees.
+    pub azimuth: f32,
+}
+
+#[allow(dead_code)]
+impl SunPosition {
+    pub fn new(elevation: f32, azimuth: f32) -> Self {
+        Self { elevation, azimuth }
+    }
+
+    /// Computes the sun position at a location on earth, given in degrees of latitude (north
+    /// positive) and longitude (east positive), for a date of a non-leap year and the time of
+    /// day in hours UTC. Uses the approximations of the NOAA solar calculator.
+    pub fn from_location(latitude: f32, longitude: f32, month: u32, day: u32, hour: f32) -> Self {
+        const DAYS_BEFORE_MONTH: [u32; 12] =
+            [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+        let day_of_year = DAYS_BEFORE_MONTH[(month.clamp(1, 12) - 1) as usize] + day;
+
+        // fractional year in radians
+        let g = 2.0 * PI / 365.0 * (day_of_year as f32 - 1.0 + (hour - 12.0) / 24.0);
+
+        let eq_time = 229.18
+            * (0.000075 + 0.001868 * g.cos()
+                - 0.032077 * g.sin()
+                - 0.014615 * (2.0 * g).cos()
+                - 0.040849 * (2.0 * g).sin());
+        let declination = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin()
+            - 0.006758 * (2.0 * g).cos()
+            + 0.000907 * (2.0 * g).sin()
+            - 0.002697 * (3.0 * g).cos()
+            + 0.00148 * (3.0 * g).sin();
+
+        // true solar time in minutes and the hour angle
+        let solar_time = hour * 60.0 + eq_time + 4.0 * longitude;
+        let hour_angle = (solar_time / 4.0 - 180.0).to_radians();
+
+        let lat = latitude.to_radians();
+        let cos_zenith =
+            lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos();
+        let elevation = 90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees();
+
+        // azimuth measured from south towards west, turned to be measured from north
+        let azimuth = hour_angle
+            .sin()
+            .atan2(hour_angle.cos() * lat.sin() - declination.tan() * lat.cos())
+            .to_degrees()
+            + 180.0;
+
+        Self { elevation, azimuth }
+    }
+
+    /// The normalized direction towards the sun.
+    pub fn direction(&self) -> Vec3 {
+        let elevation = self.elevation.to_radians();
+        let azimuth = self.azimuth.to_radians();
+        vec3(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        )
+    }
+}
+
+/// The sun disk, with its radiance attenuated by the atmosphere according to the sun elevation
+/// and the turbidity. Pairs with `PreethamSkyLight`.
+pub struct SunLight {
+    /// Normalized direction towards the sun.
+    direction: Vec3,
+    /// Rotation from the local cone space, looking along +Z, into world space.
+    to_world: Mat3,
+    radiance: Vec3,
+    cos_theta_max: f32,
+}
+
+#[allow(dead_code)]
+impl SunLight {
+    pub fn new(sun: SunPosition, turbidity: f32) -> Self {
+        let direction = sun.direction();
+        let (tangent, bitangent) = direction.any_orthonormal_pair();
+
+        Self {
+            direction,
+            to_world: Mat3::from_cols(tangent, bitangent, direction),
+            radiance: sun_radiance(sun, turbidity),
+            cos_theta_max: SUN_ANGULAR_RADIUS.to_radians().cos(),
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.radiance *= intensity;
+        self
+    }
+}
+
+impl Light for SunLight {
+    fn le(&self, ray: &Ray) -> Vec3 {
+        if ray.direction.dot(self.direction) >= self.cos_theta_max {
+            self.radiance
+        } else {
+            Vec3::ZERO
+        }
+    }
+
+    fn sample_li(&self, _scene: &Scene, _ctx: &LightSampleContext, u: Vec2) -> Option<LightSample> {
+        if self.radiance == Vec3::ZERO {
+            return None;
+        }
+
+        let wi = self.to_world * uniform_sample_cone(u, self.cos_theta_max);
+
+        Some(LightSample {
+            li: self.radiance,
+            wi,
+            pdf: uniform_cone_pdf(self.cos_theta_max),
+            dist: f32::INFINITY,
+        })
+    }
+
+    fn pdf_li(&self, _ctx: &LightSampleContext, wi: Vec3) -> f32 {
+        if self.radiance != Vec3::ZERO && wi.dot(self.direction) >= self.cos_theta_max {
+            uniform_cone_pdf(self.cos_theta_max)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The radiance of the sun disk after passing through the atmosphere, using the Rayleigh and
+/// aerosol transmittance of Preetham et al. at representative wavelengths of the RGB primaries.
+pub fn sun_radiance(sun: SunPosition, turbidity: f32) -> Vec3 {
+    if sun.elevation <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let zenith = 90.0 - sun.elevation;
+    // relative optical mass of the air along the path to the sun
+    let mass = 1.0 / (zenith.to_radians().cos() + 0.15 * (93.885 - zenith).powf(-1.253));
+
+    // Ångström's turbidity formula
+    let alpha = 1.3;
+    let beta = 0.04608 * turbidity - 0.04586;
+
+    // wavelengths in micrometers
+    let lambdas = vec3(0.65, 0.55, 0.45);
+    let transmittance = Vec3::from(lambdas.to_array().map(|lambda| {
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * mass).exp();
+        let aerosol = (-beta * lambda.powf(-alpha) * mass).exp();
+        rayleigh * aerosol
+    }));
+
+    SUN_LUMINANCE * SUN_SKY_RADIANCE_SCALE * transmittance
+}
+
+/// The solid angle covered by the sun disk.
+pub fn sun_solid_angle() -> f32 {
+    2.0 * PI * (1.0 - SUN_ANGULAR_RADIUS.to_radians().cos())
+}
@@ -0,0 +1,275 @@
+use std::f32::consts::PI;
+
+use glam::Vec3;
+use measure_time::debug_time;
+
+use crate::flux::Bounds3;
+
+use super::{
+    infinite_probability, sample_infinite, Light, LightBounds, LightSampleContext, LightSampler,
+    ONE_MINUS_EPSILON,
+};
+
+/// Number of buckets along each axis to evaluate splits of the light tree.
+const SPLIT_BUCKETS: usize = 12;
+
+/// Importance samples bounded lights with a bounding volume hierarchy over their emission
+/// bounds, descending towards the lights that contribute most to the shading point. Based on the
+/// BVH light sampler of pbrt-v4, after Conty Estevez and Kulla, "Importance Sampling of Many
+/// Lights with Adaptive Tree Splitting" (2018).
+pub struct LightTreeSampler {
+    infinite: Vec<usize>,
+    nodes: Vec<LightNode>,
+    /// The path from the root to the leaf of every scene light, one bit per level with the
+    /// first level in the lowest bit. Set bits descend into the second child.
+    trails: Vec<Option<u64>>,
+}
+
+struct LightNode {
+    bounds: LightBounds,
+    kind: LightNodeKind,
+}
+
+enum LightNodeKind {
+    Leaf {
+        light_idx: usize,
+    },
+    /// The first child directly follows its parent.
+    Interior {
+        second_child: usize,
+    },
+}
+
+impl LightTreeSampler {
+    pub fn new(lights: &[Box<dyn Light>]) -> Self {
+        debug_time!("build light tree");
+
+        let mut infinite = vec![];
+        let mut bounded = vec![];
+        for (idx, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) if bounds.phi > 0.0 => bounded.push((idx, bounds)),
+                Some(_) => {}
+                None => infinite.push(idx),
+            }
+        }
+
+        let mut sampler = Self {
+            infinite,
+            nodes: Vec::with_capacity(2 * bounded.len()),
+            trails: vec![None; lights.len()],
+        };
+        if !bounded.is_empty() {
+            sampler.build(&mut bounded, 0, 0);
+        }
+
+        sampler
+    }
+
+    /// Recursively builds the subtree for the lights and returns its root node index.
+    fn build(&mut self, lights: &mut [(usize, LightBounds)], trail: u64, depth: u32) -> usize {
+        let node_idx = self.nodes.len();
+
+        if let [(light_idx, bounds)] = *lights {
+            self.nodes.push(LightNode {
+                bounds,
+                kind: LightNodeKind::Leaf { light_idx },
+            });
+            self.trails[light_idx] = Some(trail);
+            return node_idx;
+        }
+
+        assert!(depth < 64, "light tree is too deep");
+
+        let mid = split_lights(lights);
+
+        // reserve the parent, so the first child directly follows it
+        self.nodes.push(LightNode {
+            bounds: lights[0].1,
+            kind: LightNodeKind::Interior { second_child: 0 },
+        });
+        let first = self.build(&mut lights[..mid], trail, depth + 1);
+        let second = self.build(&mut lights[mid..], trail | (1 << depth), depth + 1);
+
+        let bounds = self.nodes[first].bounds.union(&self.nodes[second].bounds);
+        self.nodes[node_idx] = LightNode {
+            bounds,
+            kind: LightNodeKind::Interior {
+                second_child: second,
+            },
+        };
+
+        node_idx
+    }
+
+    fn child_importance(&self, node_idx: usize, second_child: usize, p: Vec3, n: Vec3) -> [f32; 2] {
+        [node_idx + 1, second_child].map(|child| self.nodes[child].bounds.importance(p, n))
+    }
+}
+
+impl LightSampler for LightTreeSampler {
+    fn sample(&self, ctx: &LightSampleContext, u: f32) -> Option<(usize, f32)> {
+        let p_infinite = infinite_probability(self.infinite.len(), !self.nodes.is_empty());
+        if u < p_infinite {
+            return Some(sample_infinite(&self.infinite, u / p_infinite, p_infinite));
+        }
+
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut u = ((u - p_infinite) / (1.0 - p_infinite)).min(ONE_MINUS_EPSILON);
+        let mut pmf = 1.0 - p_infinite;
+        let mut node_idx = 0;
+
+        loop {
+            let node = &self.nodes[node_idx];
+            match node.kind {
+                LightNodeKind::Leaf { light_idx } => {
+                    // the importance of the root was never checked on the way down
+                    if node_idx > 0 || node.bounds.importance(ctx.p, ctx.n) > 0.0 {
+                        return Some((light_idx, pmf));
+                    }
+                    return None;
+                }
+                LightNodeKind::Interior { second_child } => {
+                    let [c0, c1] = self.child_importance(node_idx, second_child, ctx.p, ctx.n);
+                    if c0 == 0.0 && c1 == 0.0 {
+                        return None;
+                    }
+
+                    let p0 = c0 / (c0 + c1);
+                    if u < p0 {
+                        u = (u / p0).min(ONE_MINUS_EPSILON);
+                        pmf *= p0;
+                        node_idx += 1;
+                    } else {
+                        u = ((u - p0) / (1.0 - p0)).min(ONE_MINUS_EPSILON);
+                        pmf *= 1.0 - p0;
+                        node_idx = second_child;
+                    }
+                }
+            }
+        }
+    }
+
+    fn pmf(&self, ctx: &LightSampleContext, light_idx: usize) -> f32 {
+        let p_infinite = infinite_probability(self.infinite.len(), !self.nodes.is_empty());
+
+        let Some(mut trail) = self.trails[light_idx] else {
+            if self.infinite.contains(&light_idx) {
+                return p_infinite / self.infinite.len() as f32;
+            }
+            return 0.0;
+        };
+
+        let mut pmf = 1.0 - p_infinite;
+        let mut node_idx = 0;
+
+        while let LightNodeKind::Interior { second_child } = self.nodes[node_idx].kind {
+            let ci = self.child_importance(node_idx, second_child, ctx.p, ctx.n);
+            let child = (trail & 1) as usize;
+            if ci[child] == 0.0 {
+                return 0.0;
+            }
+
+            pmf *= ci[child] / (ci[0] + ci[1]);
+            node_idx = if child == 0 {
+                node_idx + 1
+            } else {
+                second_child
+            };
+            trail >>= 1;
+        }
+
+        pmf
+    }
+}
+
+/// Partitions the lights with the split of the lowest cost and returns the index of the first
+/// light of the second half.
+fn split_lights(lights: &mut [(usize, LightBounds)]) -> usize {
+    let (bounds, centroid_bounds) = lights.iter().fold(
+        (Bounds3::EMPTY, Bounds3::EMPTY),
+        |(bounds, centroids): (Bounds3<Vec3>, Bounds3<Vec3>), (_, light)| {
+            (
+                bounds.union_bounds(&light.bounds),
+                centroids.union(light.centroid()),
+            )
+        },
+    );
+
+    let bucket = |light: &LightBounds, dim: usize| {
+        let b = (SPLIT_BUCKETS as f32 * centroid_bounds.offset(light.centroid())[dim]) as usize;
+        b.min(SPLIT_BUCKETS - 1)
+    };
+
+    let mut best: Option<(f32, usize, usize)> = None;
+    for dim in 0..3 {
+        if centroid_bounds.max[dim] == centroid_bounds.min[dim] {
+            continue;
+        }
+
+        let mut buckets: [Option<LightBounds>; SPLIT_BUCKETS] = [None; SPLIT_BUCKETS];
+        for (_, light) in lights.iter() {
+            let b = bucket(light, dim);
+            buckets[b] = Some(match buckets[b] {
+                Some(other) => other.union(light),
+                None => *light,
+            });
+        }
+
+        for split in 0..SPLIT_BUCKETS - 1 {
+            let cost = |range: &[Option<LightBounds>]| {
+                range
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .reduce(|a, b| a.union(&b))
+                    .map_or(0.0, |b| split_cost(&b, &bounds, dim))
+            };
+            let cost = cost(&buckets[..=split]) + cost(&buckets[split + 1..]);
+
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, dim, split));
+            }
+        }
+    }
+
+    let mid = match best {
+        Some((_, dim, split)) => {
+            lights.sort_by_key(|(_, light)| bucket(light, dim) > split);
+            lights.partition_point(|(_, light)| bucket(light, dim) <= split)
+        }
+        None => lights.len() / 2,
+    };
+
+    if mid == 0 || mid == lights.len() {
+        lights.len() / 2
+    } else {
+        mid
+    }
+}
+
+/// The cost of a subtree, following the surface area orientation heuristic: the power of the
+/// lights times the solid angle of their emission and the surface area of their bounds. Long
+/// and thin splits along `dim` are penalized.
+fn split_cost(light: &LightBounds, bounds: &Bounds3<Vec3>, dim: usize) -> f32 {
+    let cos_theta_o = light.normals.cos_theta;
+    let theta_o = cos_theta_o.clamp(-1.0, 1.0).acos();
+    let theta_e = light.cos_theta_e.clamp(-1.0, 1.0).acos();
+    let theta_w = (theta_o + theta_e).min(PI);
+    let sin_theta_o = (1.0 - cos_theta_o * cos_theta_o).max(0.0).sqrt();
+
+    let m_omega = 2.0 * PI * (1.0 - cos_theta_o)
+        + PI / 2.0
+            * (2.0 * theta_w * sin_theta_o
+                - (theta_o - 2.0 * theta_w).cos()
+                - 2.0 * theta_o * sin_theta_o
+                + cos_theta_o);
+
+    let diagonal = bounds.diagonal();
+    let kr = diagonal.max_element() / diagonal[dim];
+
+    light.phi * m_omega * kr * light.bounds.surface_area()
+}
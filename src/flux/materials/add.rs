@@ -0,0 +1,56 @@
+use std::rc::Rc;
+
+use glam::Vec3;
+use rand::rngs::StdRng;
+
+use crate::flux::{interaction::Interaction, ray::Ray};
+
+use super::{BsdfEval, BxdfType, Material, ScatterRec, Subsurface};
+
+/// Layers the emission of a second material, like a `DiffuseLightMaterial` with a textured
+/// decal, onto a surface. The surface scatters light like the first material, and emits the
+/// light of both.
+pub struct AddMaterial {
+    material: Rc<dyn Material>,
+    emission: Rc<dyn Material>,
+}
+
+impl AddMaterial {
+    pub fn new(material: Rc<dyn Material>, emission: Rc<dyn Material>) -> Self {
+        Self { material, emission }
+    }
+}
+
+impl Material for AddMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        self.material.scatter(ray, int, rng)
+    }
+
+    fn emitted(&self, int: &Interaction, wo: Vec3) -> Vec3 {
+        self.material.emitted(int, wo) + self.emission.emitted(int, wo)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive() || self.emission.is_emissive()
+    }
+
+    fn is_two_sided(&self) -> bool {
+        self.material.is_two_sided() || self.emission.is_two_sided()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, rng: &mut StdRng) -> Option<BsdfEval> {
+        self.material.eval(wo, wi, int, rng)
+    }
+
+    fn subsurface(&self, int: &Interaction) -> Option<Subsurface> {
+        self.material.subsurface(int)
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        self.material.bxdf_type()
+    }
+}
@@ -0,0 +1,456 @@
+use std::{f32::consts::PI, rc::Rc};
+
+use glam::{vec3, Vec3, Vec4};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{
+    interaction::Interaction,
+    power_heuristic,
+    ray::Ray,
+    textures::{ConstantTexture, Texture},
+    Frame,
+};
+
+use super::{
+    microfacet::{fresnel_dielectric, refract, DielectricBxdf, TrowbridgeReitz},
+    BsdfEval, BxdfType, Material, ScatterRec,
+};
+
+/// The maximum number of round trips through the layer before a walk is terminated.
+const MAX_DEPTH: u32 = 10;
+
+/// Round trips after which walks with low throughput are terminated by Russian roulette.
+const RR_DEPTH: u32 = 3;
+
+/// A base material under a smooth or rough dielectric coating, like varnished wood or car paint.
+/// Light may bounce between the base and the coating any number of times, which is evaluated
+/// with the stochastic position-free random walk of Guo et al., "Position-Free Monte Carlo
+/// Simulation for Arbitrary Layered BSDFs" (2018), following the layered BxDF of pbrt-v4. The
+/// walk is unbiased, so the coating conserves energy.
+///
+/// The coating absorbs light depending on the distance travelled through it, but doesn't
+/// scatter. The base is treated as opaque.
+pub struct CoatedMaterial {
+    base: Rc<dyn Material>,
+    ior: f32,
+    roughness: Rc<dyn Texture<f32>>,
+    thickness: f32,
+    /// The fraction of light left after travelling a unit distance inside the coating.
+    absorption: Vec3,
+}
+
+#[allow(dead_code)]
+impl CoatedMaterial {
+    /// A smooth and clear coating with the index of refraction 1.5.
+    pub fn new(base: Rc<dyn Material>) -> Self {
+        Self {
+            base,
+            ior: 1.5,
+            roughness: Rc::new(ConstantTexture::new(0.0)),
+            thickness: 0.01,
+            absorption: Vec3::ONE,
+        }
+    }
+
+    pub fn with_ior(mut self, ior: f32) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// Sets the perceptual roughness of the coating in [0, 1].
+    pub fn with_roughness(mut self, roughness: Rc<dyn Texture<f32>>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Sets the thickness of the coating, which only matters for absorption.
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Absorbs light inside the coating, so that white light has the color `color` after
+    /// travelling the distance `distance`.
+    pub fn with_absorption(mut self, color: Vec3, distance: f32) -> Self {
+        let color = color.clamp(Vec3::splat(1e-6), Vec3::ONE);
+        self.absorption = color.powf(1.0 / distance);
+        self
+    }
+
+    /// Sets up the layers for shading the side of the surface `wo` leaves on.
+    fn layers<'a>(&'a self, int: &Interaction<'a>, wo: Vec3) -> Layers<'a> {
+        let side = if wo.dot(int.ns) < 0.0 { -1.0 } else { 1.0 };
+        let (n, ns) = (side * int.n, side * int.ns);
+
+        let alpha = self.roughness.evaluate(int).clamp(0.0, 1.0).powi(2);
+        let distrib = TrowbridgeReitz::new(alpha, alpha);
+        let top = if distrib.is_smooth() {
+            Interface::Smooth { eta: self.ior }
+        } else {
+            Interface::Rough(DielectricBxdf::new(self.ior, distrib))
+        };
+
+        Layers {
+            top,
+            base: self.base.as_ref(),
+            frame: Frame::from_z(ns),
+            int: Interaction { n, ns, ..*int },
+            tr_normal: int.wavelengths.uplift(self.absorption).powf(self.thickness),
+        }
+    }
+}
+
+impl Material for CoatedMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let layers = self.layers(int, -ray.direction);
+        let wo = layers.frame.to_local(-ray.direction);
+
+        // reflect at the coating or enter it
+        let top = layers.top.sample(wo, Lobes::All, false, rng)?;
+        let mut beta = Vec4::splat(top.weight());
+        let mut specular = top.specular;
+        let mut w = top.wi;
+
+        if w.z <= 0.0 {
+            let mut depth = 0;
+            loop {
+                if depth == MAX_DEPTH || !russian_roulette(&mut beta, depth, rng) {
+                    return None;
+                }
+
+                // scatter at the base
+                beta *= layers.tr(w);
+                let bs = layers.sample_base(-w, rng)?;
+                beta *= bs.weight;
+                specular &= bs.pdf.is_none();
+                w = bs.wi;
+
+                // leave through the coating or reflect back down
+                beta *= layers.tr(w);
+                let ts = layers.top.sample(-w, Lobes::All, false, rng)?;
+                beta *= ts.weight();
+                specular &= ts.specular;
+                w = ts.wi;
+
+                if w.z > 0.0 {
+                    break;
+                }
+                depth += 1;
+            }
+        }
+
+        let pdf = (!specular).then(|| layers.pdf(wo, w, rng));
+        Some(ScatterRec {
+            attenuation: beta,
+            scattered: int.spawn_ray_towards(layers.frame.to_world(w)),
+            pdf,
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, rng: &mut StdRng) -> Option<BsdfEval> {
+        let layers = self.layers(int, wo);
+        let (wo, wi) = (layers.frame.to_local(wo), layers.frame.to_local(wi));
+
+        // with a smooth coating, only a rough base can be evaluated
+        if layers.top.is_specular() {
+            let probe = vec3(0.0, 0.0, 1.0);
+            layers.eval_base(probe, probe, rng)?;
+        }
+
+        Some(BsdfEval {
+            f: layers.f(wo, wi, rng),
+            pdf: layers.pdf(wo, wi, rng),
+        })
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Other
+    }
+}
+
+/// Which lobes of an interface to sample.
+#[derive(Clone, Copy)]
+enum Lobes {
+    All,
+    Reflection,
+    Transmission,
+}
+
+/// The dielectric interface at the top of the coating.
+enum Interface {
+    Smooth { eta: f32 },
+    Rough(DielectricBxdf),
+}
+
+/// A direction sampled at the interface. Specular samples have the density 1 for their lobe,
+/// with the delta distribution folded into `f`.
+struct InterfaceSample {
+    wi: Vec3,
+    f: f32,
+    pdf: f32,
+    specular: bool,
+}
+
+impl InterfaceSample {
+    fn weight(&self) -> f32 {
+        self.f * self.wi.z.abs() / self.pdf
+    }
+}
+
+impl Interface {
+    fn is_specular(&self) -> bool {
+        matches!(self, Interface::Smooth { .. })
+    }
+
+    fn f(&self, wo: Vec3, wi: Vec3) -> f32 {
+        match self {
+            Interface::Smooth { .. } => 0.0,
+            Interface::Rough(bxdf) => bxdf.eval(wo, wi).0,
+        }
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        match self {
+            Interface::Smooth { .. } => 0.0,
+            Interface::Rough(bxdf) => bxdf.eval(wo, wi).1,
+        }
+    }
+
+    fn pdf_restricted(&self, wo: Vec3, wi: Vec3) -> f32 {
+        match self {
+            Interface::Smooth { .. } => 0.0,
+            Interface::Rough(bxdf) => bxdf.pdf_restricted(wo, wi),
+        }
+    }
+
+    /// Samples an incident direction from the given lobes. With `adjoint`, `f` is the BSDF with
+    /// the roles of the directions swapped, for light entering along `wo`.
+    fn sample(
+        &self,
+        wo: Vec3,
+        lobes: Lobes,
+        adjoint: bool,
+        rng: &mut StdRng,
+    ) -> Option<InterfaceSample> {
+        match *self {
+            Interface::Smooth { eta } => {
+                let r = fresnel_dielectric(wo.z, eta);
+                let t = 1.0 - r;
+                let (pr, pt) = match lobes {
+                    Lobes::All => (r, t),
+                    Lobes::Reflection => (r, 0.0),
+                    Lobes::Transmission => (0.0, t),
+                };
+                if pr + pt == 0.0 {
+                    return None;
+                }
+
+                if rng.gen::<f32>() * (pr + pt) < pr {
+                    Some(InterfaceSample {
+                        wi: vec3(-wo.x, -wo.y, wo.z),
+                        f: r / wo.z.abs(),
+                        pdf: pr / (pr + pt),
+                        specular: true,
+                    })
+                } else {
+                    let (wi, etap) = refract(wo, Vec3::Z, eta)?;
+                    // radiance is compressed into the smaller solid angle of the denser medium
+                    let scale = if adjoint { 1.0 } else { 1.0 / (etap * etap) };
+                    Some(InterfaceSample {
+                        wi,
+                        f: t * scale / wi.z.abs(),
+                        pdf: pt / (pr + pt),
+                        specular: true,
+                    })
+                }
+            }
+            Interface::Rough(bxdf) => {
+                let (wi, pdf) = match lobes {
+                    Lobes::All => {
+                        let wi = bxdf.sample_wi(wo, rng.gen(), rng.gen())?;
+                        (wi, bxdf.eval(wo, wi).1)
+                    }
+                    Lobes::Reflection => bxdf.sample_restricted(wo, false, rng.gen())?,
+                    Lobes::Transmission => bxdf.sample_restricted(wo, true, rng.gen())?,
+                };
+                let f = if adjoint {
+                    bxdf.eval(wi, wo).0
+                } else {
+                    bxdf.eval(wo, wi).0
+                };
+
+                (pdf > 0.0 && f > 0.0).then_some(InterfaceSample {
+                    wi,
+                    f,
+                    pdf,
+                    specular: false,
+                })
+            }
+        }
+    }
+}
+
+/// A direction sampled at the base, with the weight `f |cos| / pdf`. The density is `None` for
+/// specular bases.
+struct BaseSample {
+    wi: Vec3,
+    weight: Vec4,
+    pdf: Option<f32>,
+}
+
+/// The coating and base at a shading point, in the local frame with +Z on the side of `wo`.
+/// The top of the coating is at the thickness of the layer and the base at zero.
+struct Layers<'a> {
+    top: Interface,
+    base: &'a dyn Material,
+    frame: Frame,
+    /// The interaction with the normals facing `wo`, as seen by the base.
+    int: Interaction<'a>,
+    /// The transmittance through the coating along the normal, at the wavelengths of the
+    /// interaction.
+    tr_normal: Vec4,
+}
+
+impl Layers<'_> {
+    /// The transmittance through the coating along `w`.
+    fn tr(&self, w: Vec3) -> Vec4 {
+        if self.tr_normal == Vec4::ONE {
+            return Vec4::ONE;
+        }
+        self.tr_normal.powf(1.0 / w.z.abs().max(1e-4))
+    }
+
+    fn eval_base(&self, wo: Vec3, wi: Vec3, rng: &mut StdRng) -> Option<BsdfEval> {
+        let (wo, wi) = (self.frame.to_world(wo), self.frame.to_world(wi));
+        self.base.eval(wo, wi, &self.int, rng)
+    }
+
+    /// Samples the base for light leaving towards `wo`. Light transmitted through the base is
+    /// lost.
+    fn sample_base(&self, wo: Vec3, rng: &mut StdRng) -> Option<BaseSample> {
+        let ray = Ray::new(self.int.p, -self.frame.to_world(wo), self.int.time);
+        let srec = self.base.scatter(&ray, &self.int, rng)?;
+        let wi = self.frame.to_local(srec.scattered.direction);
+
+        (wi.z > 0.0).then_some(BaseSample {
+            wi,
+            weight: srec.attenuation,
+            pdf: srec.pdf,
+        })
+    }
+
+    /// Estimates the BSDF with a random walk from `wo`, connecting to `wi` at every bounce off
+    /// the base.
+    fn f(&self, wo: Vec3, wi: Vec3, rng: &mut StdRng) -> Vec4 {
+        if wi.z <= 0.0 {
+            return Vec4::ZERO;
+        }
+
+        // reflection at the coating
+        let mut f = Vec4::splat(self.top.f(wo, wi));
+
+        let Some(wos) = self.top.sample(wo, Lobes::Transmission, false, rng) else {
+            return f;
+        };
+        let Some(wis) = self.top.sample(wi, Lobes::Transmission, true, rng) else {
+            return f;
+        };
+
+        let mut beta = Vec4::splat(wos.weight());
+        let mut w = wos.wi;
+
+        for depth in 0..MAX_DEPTH {
+            if !russian_roulette(&mut beta, depth, rng) {
+                break;
+            }
+
+            // connect the base to wi through the coating
+            beta *= self.tr(w);
+            if let Some(base) = self.eval_base(-w, -wis.wi, rng) {
+                let weight = if self.top.is_specular() {
+                    1.0
+                } else {
+                    power_heuristic(wis.pdf, base.pdf)
+                };
+                f += beta * base.f * self.tr(wis.wi) * wis.weight() * weight;
+            }
+
+            // scatter at the base and connect to wi through the coating
+            let Some(bs) = self.sample_base(-w, rng) else {
+                break;
+            };
+            beta *= bs.weight;
+            w = bs.wi;
+
+            if !self.top.is_specular() {
+                let f_exit = self.top.f(-w, wi);
+                if f_exit > 0.0 {
+                    let weight = match bs.pdf {
+                        Some(pdf) => power_heuristic(pdf, self.top.pdf_restricted(wi, -w)),
+                        None => 1.0,
+                    };
+                    f += beta * self.tr(w) * f_exit * weight;
+                }
+            }
+
+            // reflect back down at the coating
+            beta *= self.tr(w);
+            let Some(rs) = self.top.sample(-w, Lobes::Reflection, false, rng) else {
+                break;
+            };
+            beta *= rs.weight();
+            w = rs.wi;
+        }
+
+        f
+    }
+
+    /// Estimates the density of sampling `wi` with a single bounce off the base. Only used for
+    /// multiple importance sampling, so it's mixed with a uniform density to stay robust.
+    fn pdf(&self, wo: Vec3, wi: Vec3, rng: &mut StdRng) -> f32 {
+        let mut pdf = 0.0;
+
+        if wi.z > 0.0 {
+            pdf += self.top.pdf_restricted(wo, wi);
+
+            let wos = self.top.sample(wo, Lobes::Transmission, false, rng);
+            let wis = self.top.sample(wi, Lobes::Transmission, true, rng);
+            if let (Some(wos), Some(wis)) = (wos, wis) {
+                if self.top.is_specular() {
+                    pdf += self
+                        .eval_base(-wos.wi, -wis.wi, rng)
+                        .map_or(0.0, |base| base.pdf);
+                } else if let Some(bs) = self.sample_base(-wos.wi, rng) {
+                    match bs.pdf {
+                        Some(bs_pdf) => {
+                            let base_pdf = self
+                                .eval_base(-wos.wi, -wis.wi, rng)
+                                .map_or(0.0, |base| base.pdf);
+                            pdf += power_heuristic(wis.pdf, base_pdf) * base_pdf;
+
+                            let top_pdf = self.top.pdf(-bs.wi, wi);
+                            pdf += power_heuristic(bs_pdf, top_pdf) * top_pdf;
+                        }
+                        None => pdf += self.top.pdf(-bs.wi, wi),
+                    }
+                }
+            }
+        }
+
+        0.1 / (4.0 * PI) + 0.9 * pdf
+    }
+}
+
+/// Terminates walks with low throughput after a few round trips. Returns whether the walk
+/// continues, with its throughput compensated.
+fn russian_roulette(beta: &mut Vec4, depth: u32, rng: &mut StdRng) -> bool {
+    let max = beta.max_element();
+    if depth > RR_DEPTH && max < 0.25 {
+        let q = (1.0 - max).max(0.0);
+        if rng.gen::<f32>() < q {
+            return false;
+        }
+        *beta /= 1.0 - q;
+    }
+    true
+}
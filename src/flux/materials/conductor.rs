@@ -0,0 +1,276 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3, Vec4};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{
+    interaction::Interaction,
+    ray::Ray,
+    textures::{ConstantTexture, Texture},
+    Frame, PiecewiseLinearSpectrum, SampledWavelengths, RGB_WAVELENGTHS,
+};
+
+use super::{
+    microfacet::{flip_z, fresnel_conductor, reflect, TrowbridgeReitz},
+    BsdfEval, BxdfType, Material, ScatterRec,
+};
+
+/// Metals with measured complex indices of refraction.
+#[derive(Clone, Copy, Debug)]
+pub enum Metal {
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+    Chrome,
+}
+
+impl Metal {
+    /// The real and imaginary part of the index of refraction, `eta` and `k`, over the visible
+    /// range.
+    pub fn ior(self) -> (PiecewiseLinearSpectrum, PiecewiseLinearSpectrum) {
+        let table: &[(f32, f32, f32)] = match self {
+            Metal::Gold => &GOLD,
+            Metal::Copper => &COPPER,
+            Metal::Aluminium => &ALUMINIUM,
+            Metal::Silver => &SILVER,
+            Metal::Chrome => &CHROME,
+        };
+
+        let lambdas: Vec<f32> = table.iter().map(|&(lambda, _, _)| lambda).collect();
+        (
+            PiecewiseLinearSpectrum::new(lambdas.clone(), table.iter().map(|m| m.1).collect()),
+            PiecewiseLinearSpectrum::new(lambdas, table.iter().map(|m| m.2).collect()),
+        )
+    }
+}
+
+/// A metal with GGX microfacet reflection and the exact Fresnel equations of conductors. The
+/// roughness may differ along the u and v directions of the surface for brushed metals.
+pub struct ConductorMaterial {
+    eta: PiecewiseLinearSpectrum,
+    k: PiecewiseLinearSpectrum,
+    roughness_u: Rc<dyn Texture<f32>>,
+    roughness_v: Rc<dyn Texture<f32>>,
+}
+
+#[allow(dead_code)]
+impl ConductorMaterial {
+    /// A perfectly smooth conductor with the complex index of refraction `eta + i k`.
+    pub fn new(eta: PiecewiseLinearSpectrum, k: PiecewiseLinearSpectrum) -> Self {
+        let smooth = Rc::new(ConstantTexture::new(0.0));
+        Self {
+            eta,
+            k,
+            roughness_u: smooth.clone(),
+            roughness_v: smooth,
+        }
+    }
+
+    pub fn from_metal(metal: Metal) -> Self {
+        let (eta, k) = metal.ior();
+        Self::new(eta, k)
+    }
+
+    /// Sets the perceptual roughness in [0, 1] along both tangents.
+    pub fn with_roughness(self, roughness: Rc<dyn Texture<f32>>) -> Self {
+        self.with_anisotropic_roughness(roughness.clone(), roughness)
+    }
+
+    pub fn with_anisotropic_roughness(
+        mut self,
+        roughness_u: Rc<dyn Texture<f32>>,
+        roughness_v: Rc<dyn Texture<f32>>,
+    ) -> Self {
+        self.roughness_u = roughness_u;
+        self.roughness_v = roughness_v;
+        self
+    }
+
+    fn distrib(&self, int: &Interaction) -> TrowbridgeReitz {
+        let alpha =
+            |roughness: &Rc<dyn Texture<f32>>| roughness.evaluate(int).clamp(0.0, 1.0).powi(2);
+        TrowbridgeReitz::new(alpha(&self.roughness_u), alpha(&self.roughness_v))
+    }
+
+    /// The Fresnel reflectance at every sampled wavelength, or at the wavelengths of the color
+    /// channels in RGB mode.
+    fn fresnel(&self, int: &Interaction, cos_theta: f32) -> Vec4 {
+        let f = |lambda: f32| {
+            fresnel_conductor(
+                cos_theta,
+                self.eta.evaluate(lambda),
+                self.k.evaluate(lambda),
+            )
+        };
+        match int.wavelengths {
+            SampledWavelengths::Rgb => Vec3::from(RGB_WAVELENGTHS.map(f)).extend(0.0),
+            SampledWavelengths::Spectral { lambda, .. } => Vec4::from(lambda.to_array().map(f)),
+        }
+    }
+
+    /// Evaluates the BSDF and the density of `scatter` for local directions, with `wo` above
+    /// the surface.
+    fn eval_local(
+        &self,
+        int: &Interaction,
+        distrib: &TrowbridgeReitz,
+        wo: Vec3,
+        wi: Vec3,
+    ) -> (Vec4, f32) {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return (Vec4::ZERO, 0.0);
+        }
+
+        let wm = (wo + wi).normalize();
+        let fresnel = self.fresnel(int, wo.dot(wm));
+        let f = distrib.d(wm) * distrib.g(wo, wi) * fresnel / (4.0 * wo.z * wi.z);
+        let pdf = distrib.pdf(wo, wm) / (4.0 * wo.dot(wm));
+        (f, pdf)
+    }
+}
+
+impl Material for ConductorMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let distrib = self.distrib(int);
+        let frame = Frame::from_xz(int.dpdu, int.ns);
+
+        // the back of the surface is shaded like its front
+        let wo = frame.to_local(-ray.direction);
+        let flipped = wo.z < 0.0;
+        let wo = if flipped { flip_z(wo) } else { wo };
+
+        let (wi, attenuation, pdf) = if distrib.is_smooth() {
+            let wi = vec3(-wo.x, -wo.y, wo.z);
+            (wi, self.fresnel(int, wi.z), None)
+        } else {
+            let wi = reflect(wo, distrib.sample_wm(wo, rng.gen()));
+            let (f, pdf) = self.eval_local(int, &distrib, wo, wi);
+            if pdf == 0.0 {
+                return None;
+            }
+            (wi, f * wi.z / pdf, Some(pdf))
+        };
+
+        let wi = if flipped { flip_z(wi) } else { wi };
+        Some(ScatterRec {
+            attenuation,
+            scattered: int.spawn_ray_towards(frame.to_world(wi)),
+            pdf,
+        })
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, _rng: &mut StdRng) -> Option<BsdfEval> {
+        let distrib = self.distrib(int);
+        if distrib.is_smooth() {
+            return None;
+        }
+
+        let frame = Frame::from_xz(int.dpdu, int.ns);
+        let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+        let (wo, wi) = if wo.z < 0.0 {
+            (flip_z(wo), flip_z(wi))
+        } else {
+            (wo, wi)
+        };
+
+        let (f, pdf) = self.eval_local(int, &distrib, wo, wi);
+        Some(BsdfEval { f, pdf })
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Specular
+    }
+}
+
+// Measured complex indices of refraction as (wavelength in nm, eta, k), from Johnson and Christy,
+// "Optical Constants of the Noble Metals" (1972) for gold and silver, Palik, "Handbook of Optical
+// Constants of Solids" (1985) for copper, Rakić, "Algorithm for the determination of intrinsic
+// optical constants of metal films" (1995) for aluminium, and Johnson and Christy, "Optical
+// constants of transition metals" (1974) for chrome. Outside the tables, the closest values are
+// used.
+
+const GOLD: [(f32, f32, f32); 15] = [
+    (367.9, 1.48, 1.895),
+    (381.5, 1.46, 1.933),
+    (397.4, 1.47, 1.952),
+    (413.3, 1.46, 1.958),
+    (430.5, 1.45, 1.948),
+    (450.9, 1.38, 1.914),
+    (471.4, 1.31, 1.849),
+    (495.9, 1.04, 1.833),
+    (520.9, 0.62, 2.081),
+    (548.6, 0.43, 2.455),
+    (582.1, 0.29, 2.863),
+    (616.8, 0.21, 3.272),
+    (659.5, 0.14, 3.697),
+    (704.5, 0.13, 4.103),
+    (756.0, 0.14, 4.542),
+];
+
+const COPPER: [(f32, f32, f32); 19] = [
+    (370.1, 1.270, 1.950),
+    (381.5, 1.200, 2.122),
+    (399.9, 1.175, 2.130),
+    (413.3, 1.180, 2.210),
+    (427.5, 1.175, 2.289),
+    (442.8, 1.170, 2.362),
+    (459.2, 1.160, 2.433),
+    (476.9, 1.150, 2.504),
+    (495.9, 1.135, 2.564),
+    (516.6, 1.120, 2.605),
+    (539.1, 1.040, 2.583),
+    (563.6, 0.826, 2.599),
+    (590.4, 0.468, 2.809),
+    (619.9, 0.272, 3.240),
+    (652.5, 0.214, 3.670),
+    (688.8, 0.213, 4.050),
+    (729.3, 0.223, 4.430),
+    (775.0, 0.250, 4.817),
+    (826.7, 0.260, 5.260),
+];
+
+const ALUMINIUM: [(f32, f32, f32); 11] = [
+    (380.0, 0.44, 4.59),
+    (400.0, 0.49, 4.86),
+    (450.0, 0.62, 5.47),
+    (500.0, 0.77, 6.08),
+    (550.0, 0.96, 6.69),
+    (600.0, 1.20, 7.26),
+    (650.0, 1.49, 7.82),
+    (700.0, 1.83, 8.31),
+    (750.0, 2.36, 8.60),
+    (800.0, 2.74, 8.31),
+    (830.0, 2.60, 8.20),
+];
+
+const SILVER: [(f32, f32, f32); 15] = [
+    (367.9, 0.07, 1.657),
+    (381.5, 0.05, 1.864),
+    (397.4, 0.05, 2.070),
+    (413.3, 0.05, 2.275),
+    (430.5, 0.04, 2.462),
+    (450.9, 0.04, 2.657),
+    (471.4, 0.05, 2.869),
+    (495.9, 0.05, 3.093),
+    (520.9, 0.05, 3.324),
+    (548.6, 0.06, 3.586),
+    (582.1, 0.05, 3.858),
+    (616.8, 0.06, 4.152),
+    (659.5, 0.05, 4.483),
+    (704.5, 0.04, 4.838),
+    (756.0, 0.03, 5.242),
+];
+
+const CHROME: [(f32, f32, f32); 10] = [
+    (380.0, 1.80, 2.80),
+    (400.0, 1.96, 2.96),
+    (450.0, 2.32, 3.13),
+    (500.0, 2.75, 3.25),
+    (550.0, 3.18, 3.33),
+    (600.0, 3.23, 3.34),
+    (650.0, 3.11, 3.33),
+    (700.0, 3.07, 3.37),
+    (750.0, 3.11, 3.45),
+    (800.0, 3.18, 3.55),
+];
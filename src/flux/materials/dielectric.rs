@@ -1,73 +1,162 @@
 use std::rc::Rc;
 
-use glam::Vec3;
+use glam::{vec3, Vec3, Vec4};
 use rand::{rngs::StdRng, Rng};
 
-use crate::flux::{interaction::Interaction, ray::Ray, textures::Texture};
-
-use super::{reflect, refract, BxdfType, Material, ScatterRec};
-
+use crate::flux::{
+    interaction::Interaction,
+    ray::Ray,
+    textures::{ConstantTexture, Texture},
+    Frame,
+};
+
+use super::{
+    microfacet::{fresnel_dielectric, refract, DielectricBxdf, TrowbridgeReitz},
+    BsdfEval, BxdfType, Dispersion, Material, ScatterRec,
+};
+
+/// Glass and other dielectrics bounding a closed volume, with the index of refraction `ior`
+/// inside. Smooth surfaces reflect and refract perfectly, rough ones scatter with GGX
+/// microfacets following Walter et al. Both use the exact Fresnel equations.
+///
+/// With dispersion, the index of refraction depends on the wavelength. Spectral rendering then
+/// follows only the hero wavelength of a path after it hits the surface, so that white light is
+/// split into a rainbow, while RGB rendering uses the index at the d line.
+///
+/// Colored glass can absorb light inside the volume following the Beer-Lambert law. The distance
+/// travelled inside is taken from the ray leaving the volume, which assumes that no other
+/// surfaces are inside.
 pub struct DielectricMaterial {
+    /// Tints transmitted light. Reflections at the surface are never tinted.
     kd: Rc<dyn Texture<Vec3>>,
     ior: f32,
+    roughness: Rc<dyn Texture<f32>>,
+    /// The fraction of light left after travelling a unit distance inside the volume.
+    absorption: Vec3,
+    dispersion: Option<Dispersion>,
 }
 
 impl DielectricMaterial {
     pub fn new(kd: Rc<dyn Texture<Vec3>>, ior: f32) -> Self {
-        Self { kd, ior }
+        Self {
+            kd,
+            ior,
+            roughness: Rc::new(ConstantTexture::new(0.0)),
+            absorption: Vec3::ONE,
+            dispersion: None,
+        }
     }
-}
 
-impl Material for DielectricMaterial {
-    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
-        let attenuation = self.kd.evaluate(int);
+    /// Sets the perceptual roughness in [0, 1], like frosted glass for large values.
+    pub fn with_roughness(mut self, roughness: Rc<dyn Texture<f32>>) -> Self {
+        self.roughness = roughness;
+        self
+    }
 
-        // If we hit the back face, we flip the normal
-        let front_face = ray.direction.dot(int.n) < 0.0;
+    /// Makes the index of refraction depend on the wavelength, replacing the one given to `new`.
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.ior = dispersion.d_line_ior();
+        self.dispersion = Some(dispersion);
+        self
+    }
 
-        // TODO: avoid this allocation, maybe pass an extra paramter into
-        // Interaction::spawn_ray to flip the direction?
-        let mut int = Interaction {
-            n: if front_face { int.n } else { -int.n },
-            ..*int
-        };
+    /// Absorbs light inside the volume, so that white light has the color `color` after
+    /// travelling the distance `distance`.
+    pub fn with_absorption(mut self, color: Vec3, distance: f32) -> Self {
+        let color = color.clamp(Vec3::splat(1e-6), Vec3::ONE);
+        self.absorption = color.powf(1.0 / distance);
+        self
+    }
 
-        let refraction_ratio = if front_face { 1.0 / self.ior } else { self.ior };
+    /// The transmittance of the volume along the ray that reached the interaction, if it
+    /// travelled inside, i.e. `wo` points below the surface. The absorption is uplifted first,
+    /// so that the attenuation follows the Beer-Lambert law at every wavelength.
+    fn transmittance(&self, int: &Interaction, wo: Vec3) -> Vec4 {
+        if self.absorption == Vec3::ONE || wo.z >= 0.0 {
+            return Vec4::ONE;
+        }
+        int.wavelengths.uplift(self.absorption).powf(int.t)
+    }
 
-        // INFO: We expect ray directions to be normalized. See Scene::intersect, where we
-        // normalize the normal vector by default.
-        let unit_direction = ray.direction;
+    /// The index of refraction at the hero wavelength of the interaction.
+    fn ior(&self, int: &Interaction) -> f32 {
+        match (&self.dispersion, int.wavelengths.hero()) {
+            (Some(dispersion), Some(lambda)) => dispersion.ior(lambda),
+            _ => self.ior,
+        }
+    }
 
-        let cos_theta = (-unit_direction).dot(int.n).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    /// The color of the transmission from `wi` to `wo`, while reflections aren't tinted.
+    fn tint(&self, int: &Interaction, wo: Vec3, wi: Vec3) -> Vec4 {
+        if wo.z * wi.z < 0.0 {
+            int.wavelengths.uplift(self.kd.evaluate(int))
+        } else {
+            Vec4::ONE
+        }
+    }
 
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let reflecting = cannot_refract || reflectance(cos_theta, refraction_ratio) > rng.gen();
+    fn distrib(&self, int: &Interaction) -> TrowbridgeReitz {
+        let alpha = self.roughness.evaluate(int).clamp(0.0, 1.0).powi(2);
+        TrowbridgeReitz::new(alpha, alpha)
+    }
+}
 
-        let scattered = if reflecting {
-            let direction = reflect(unit_direction, int.n);
-            int.spawn_ray(direction)
+impl Material for DielectricMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let distrib = self.distrib(int);
+        let frame = Frame::from_z(int.ns);
+        let wo = frame.to_local(-ray.direction);
+        let ior = self.ior(int);
+
+        let (wi, attenuation, pdf) = if distrib.is_smooth() {
+            let r = fresnel_dielectric(wo.z, ior);
+            if rng.gen::<f32>() < r {
+                (vec3(-wo.x, -wo.y, wo.z), Vec4::ONE, None)
+            } else {
+                let (wi, etap) = refract(wo, Vec3::Z, ior)?;
+                // radiance is compressed into the smaller solid angle of the denser medium
+                let attenuation = int.wavelengths.uplift(self.kd.evaluate(int)) / (etap * etap);
+                (wi, attenuation, None)
+            }
         } else {
-            let direction = refract(unit_direction, int.n, refraction_ratio);
-            // If we refract the ray, we need to flip the normal direction so we offset the spawned
-            // ray in the correct direction.
-            int.n = -int.n;
-            int.spawn_ray(direction)
+            let bxdf = DielectricBxdf::new(ior, distrib);
+            let wi = bxdf.sample_wi(wo, rng.gen(), rng.gen())?;
+            let (f, pdf) = bxdf.eval(wo, wi);
+            if pdf == 0.0 {
+                return None;
+            }
+            let tint = self.tint(int, wo, wi);
+            (wi, tint * f * wi.z.abs() / pdf, Some(pdf))
         };
 
         Some(ScatterRec {
-            attenuation,
-            scattered,
+            attenuation: self.transmittance(int, wo) * attenuation,
+            scattered: int.spawn_ray_towards(frame.to_world(wi)),
+            pdf,
         })
     }
 
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, _rng: &mut StdRng) -> Option<BsdfEval> {
+        let distrib = self.distrib(int);
+        if distrib.is_smooth() {
+            return None;
+        }
+
+        let frame = Frame::from_z(int.ns);
+        let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+        let (f, pdf) = DielectricBxdf::new(self.ior(int), distrib).eval(wo, wi);
+
+        Some(BsdfEval {
+            f: self.transmittance(int, wo) * self.tint(int, wo, wi) * f,
+            pdf,
+        })
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
     fn bxdf_type(&self) -> BxdfType {
         BxdfType::Other
     }
 }
-
-fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
-    let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
-    let r0 = r0 * r0;
-    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
-}
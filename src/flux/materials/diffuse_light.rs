@@ -7,13 +7,27 @@ use crate::flux::{interaction::Interaction, ray::Ray, textures::Texture};
 
 use super::{BxdfType, Material, ScatterRec};
 
+/// Emits light uniformly in all directions. By default, both sides of a surface emit.
 pub struct DiffuseLightMaterial {
     emit: Rc<dyn Texture<Vec3>>,
+    two_sided: bool,
 }
 
+#[allow(dead_code)]
 impl DiffuseLightMaterial {
     pub fn new(emit: Rc<dyn Texture<Vec3>>) -> Self {
-        Self { emit }
+        Self {
+            emit,
+            two_sided: true,
+        }
+    }
+
+    /// Sets whether both sides of surfaces emit. Otherwise, only the front side emits, which the
+    /// geometric normal points to. For triangles, this is the side the vertices are ordered
+    /// counter-clockwise on.
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
     }
 }
 
@@ -22,10 +36,21 @@ impl Material for DiffuseLightMaterial {
         None
     }
 
-    fn emitted(&self, int: &Interaction) -> Vec3 {
+    fn emitted(&self, int: &Interaction, wo: Vec3) -> Vec3 {
+        if !self.two_sided && wo.dot(int.n) <= 0.0 {
+            return Vec3::ZERO;
+        }
         self.emit.evaluate(int)
     }
 
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    fn is_two_sided(&self) -> bool {
+        self.two_sided
+    }
+
     fn bxdf_type(&self) -> BxdfType {
         BxdfType::Other
     }
@@ -0,0 +1,52 @@
+/// The wavelength in nm of the Fraunhofer d line of helium, at which the index of refraction of
+/// glasses is usually given.
+pub const D_LINE_WAVELENGTH: f32 = 587.56;
+
+/// The index of refraction of a dispersive dielectric as a function of the wavelength, which
+/// splits white light into a rainbow.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    /// n²(λ) = 1 + Σ Bᵢ λ² / (λ² - Cᵢ), with λ in µm and the Cᵢ in µm².
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+    /// n(λ) = A + B / λ² + C / λ⁴, with λ in µm. A simpler fit for the visible range.
+    Cauchy { a: f32, b: f32, c: f32 },
+}
+
+#[allow(dead_code)]
+impl Dispersion {
+    /// Schott N-BK7, the most common optical crown glass.
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.039_612, 0.231_792_34, 1.010_469_4],
+        c: [0.006_000_699, 0.020_017_914, 103.560_65],
+    };
+
+    /// Fused silica after Malitson, with little dispersion.
+    pub const FUSED_SILICA: Self = Self::Sellmeier {
+        b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+        c: [0.004_679_148, 0.013_512_063, 97.934_003],
+    };
+
+    /// Diamond, whose strong dispersion gives the "fire" of cut gems.
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030_625, 0.011_236, 0.0],
+    };
+
+    /// The index of refraction at the wavelength `lambda` in nm.
+    pub fn ior(&self, lambda: f32) -> f32 {
+        let l = lambda / 1000.0;
+        let l2 = l * l;
+        match self {
+            Self::Sellmeier { b, c } => {
+                let n2 = 1.0 + b.iter().zip(c).map(|(b, c)| b * l2 / (l2 - c)).sum::<f32>();
+                n2.max(1.0).sqrt()
+            }
+            Self::Cauchy { a, b, c } => a + b / l2 + c / (l2 * l2),
+        }
+    }
+
+    /// The index of refraction at the d line, used when rendering in RGB.
+    pub fn d_line_ior(&self) -> f32 {
+        self.ior(D_LINE_WAVELENGTH)
+    }
+}
@@ -0,0 +1,23 @@
+use rand::rngs::StdRng;
+
+use crate::flux::{interaction::Interaction, ray::Ray};
+
+use super::{BxdfType, Material, ScatterRec};
+
+/// An invisible surface that only marks the boundary of a medium. Rays pass through it
+/// unchanged, without counting as a bounce.
+pub struct InterfaceMaterial;
+
+impl Material for InterfaceMaterial {
+    fn scatter(&self, _ray: &Ray, _int: &Interaction, _rng: &mut StdRng) -> Option<ScatterRec> {
+        None
+    }
+
+    fn is_interface(&self) -> bool {
+        true
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Other
+    }
+}
@@ -1,11 +1,11 @@
-use std::rc::Rc;
+use std::{f32::consts::FRAC_1_PI, rc::Rc};
 
-use glam::Vec3;
+use glam::{Vec3, Vec4};
 use rand::{rngs::StdRng, Rng};
 
 use crate::flux::{interaction::Interaction, ray::Ray, textures::Texture, uniform_sample_sphere};
 
-use super::{is_near_zero, BxdfType, Material, ScatterRec};
+use super::{is_near_zero, BsdfEval, BxdfType, Material, ScatterRec};
 
 pub struct MatteMaterial {
     kd: Rc<dyn Texture<Vec3>>,
@@ -19,19 +19,37 @@ impl MatteMaterial {
 
 impl Material for MatteMaterial {
     fn scatter(&self, _ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
-        let attenuation = self.kd.evaluate(int);
+        let attenuation = int.wavelengths.uplift(self.kd.evaluate(int));
 
-        let direction = int.n + uniform_sample_sphere(rng.gen());
+        let direction = int.ns + uniform_sample_sphere(rng.gen());
         let direction = if is_near_zero(direction) {
-            int.n
+            int.ns
         } else {
             direction
         };
         let scattered = int.spawn_ray(direction);
+        let pdf = scattered.direction.dot(int.ns).max(0.0) * FRAC_1_PI;
 
         Some(ScatterRec {
             attenuation,
             scattered,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval(&self, _wo: Vec3, wi: Vec3, int: &Interaction, _rng: &mut StdRng) -> Option<BsdfEval> {
+        // scatter samples a cosine distribution around the normal
+        let cos_theta = wi.dot(int.ns);
+        if cos_theta <= 0.0 {
+            return Some(BsdfEval {
+                f: Vec4::ZERO,
+                pdf: 0.0,
+            });
+        }
+
+        Some(BsdfEval {
+            f: int.wavelengths.uplift(self.kd.evaluate(int)) * FRAC_1_PI,
+            pdf: cos_theta * FRAC_1_PI,
         })
     }
 
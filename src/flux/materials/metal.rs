@@ -20,18 +20,19 @@ impl MetalMaterial {
 
 impl Material for MetalMaterial {
     fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
-        let attenuation = self.kd.evaluate(int);
+        let attenuation = int.wavelengths.uplift(self.kd.evaluate(int));
 
-        let reflected = reflect(ray.direction.normalize(), int.n);
+        let reflected = reflect(ray.direction.normalize(), int.ns);
         let direction = reflected + self.fuzz * uniform_sample_sphere(rng.gen());
 
         // Rays scattering to below the originating surface will be cancelled
-        let above_surface = direction.dot(int.n) > 0.0;
+        let above_surface = direction.dot(int.ns) > 0.0;
         if above_surface {
             let scattered = int.spawn_ray(direction);
             Some(ScatterRec {
                 attenuation,
                 scattered,
+                pdf: None,
             })
         } else {
             None
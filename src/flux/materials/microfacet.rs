@@ -0,0 +1,322 @@
+use std::f32::consts::PI;
+
+use glam::{vec3, Vec2, Vec3, Vec4};
+
+use crate::flux::uniform_sample_disk;
+
+/// The smallest roughness along a tangent. Sharper distributions are numerically unstable.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// The Trowbridge-Reitz (GGX) microfacet distribution with anisotropic roughness. Directions are
+/// in the local shading frame, with the surface normal along +Z.
+#[derive(Clone, Copy, Debug)]
+pub struct TrowbridgeReitz {
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f32, alpha_y: f32) -> Self {
+        Self {
+            alpha_x: alpha_x.max(MIN_ALPHA),
+            alpha_y: alpha_y.max(MIN_ALPHA),
+        }
+    }
+
+    /// Maps a perceptual roughness and anisotropy, both in [0, 1], to the roughness along the
+    /// tangents, like the Disney BRDF.
+    pub fn from_roughness(roughness: f32, anisotropic: f32) -> Self {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        let aspect = (1.0 - 0.9 * anisotropic.clamp(0.0, 1.0)).sqrt();
+        Self::new(alpha / aspect, alpha * aspect)
+    }
+
+    /// The density of microfacet normals `wm`.
+    pub fn d(&self, wm: Vec3) -> f32 {
+        let x = wm.x / self.alpha_x;
+        let y = wm.y / self.alpha_y;
+        let e = x * x + y * y + wm.z * wm.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// The Smith auxiliary function, measuring the invisible microfacet area per visible area.
+    fn lambda(&self, w: Vec3) -> f32 {
+        if w.z == 0.0 {
+            return f32::INFINITY;
+        }
+
+        let alpha2_tan2_theta =
+            ((self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2)) / (w.z * w.z);
+        ((1.0 + alpha2_tan2_theta).sqrt() - 1.0) / 2.0
+    }
+
+    /// The fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets visible from both directions, with height correlation.
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of microfacet normals `wm` visible from `w`, which is also the density of
+    /// `sample_wm`.
+    pub fn pdf(&self, w: Vec3, wm: Vec3) -> f32 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Whether the distribution is too sharp to be sampled, so it should be treated as a perfect
+    /// mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) <= MIN_ALPHA
+    }
+
+    /// Samples a microfacet normal visible from `w`, following Heitz, "Sampling the GGX
+    /// Distribution of Visible Normals" (2018). The normal is always in the upper hemisphere.
+    pub fn sample_wm(&self, w: Vec3, u: Vec2) -> Vec3 {
+        // transform to the hemisphere configuration of unit roughness
+        let mut wh = vec3(self.alpha_x * w.x, self.alpha_y * w.y, w.z).normalize();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z < 0.99999 {
+            Vec3::Z.cross(wh).normalize()
+        } else {
+            Vec3::X
+        };
+        let t2 = wh.cross(t1);
+
+        // warp the disk sample to the visible part of the projected hemisphere
+        let mut p = uniform_sample_disk(u);
+        let h = (1.0 - p.x * p.x).max(0.0).sqrt();
+        let s = (1.0 + wh.z) / 2.0;
+        p.y = (1.0 - s) * h + s * p.y;
+
+        let pz = (1.0 - p.length_squared()).max(0.0).sqrt();
+        let nh = p.x * t1 + p.y * t2 + pz * wh;
+
+        vec3(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
+
+/// Microfacet reflection and transmission at a rough dielectric interface, after Walter et al.,
+/// "Microfacet Models for Refraction through Rough Surfaces" (2007). `eta` is the index of
+/// refraction below the surface relative to the one above. The BSDF is scalar, so materials can
+/// tint reflection and transmission separately.
+#[derive(Clone, Copy, Debug)]
+pub struct DielectricBxdf {
+    eta: f32,
+    distrib: TrowbridgeReitz,
+}
+
+impl DielectricBxdf {
+    pub fn new(eta: f32, distrib: TrowbridgeReitz) -> Self {
+        Self { eta, distrib }
+    }
+
+    /// Evaluates the BSDF and the density of `sample_wi` for a pair of local directions.
+    pub fn eval(&self, wo: Vec3, wi: Vec3) -> (f32, f32) {
+        let Some((wm, etap)) = self.half_vector(wo, wi) else {
+            return (0.0, 0.0);
+        };
+
+        let r = fresnel_dielectric(wo.dot(wm), self.eta);
+        let t = 1.0 - r;
+        let pdf = self.lobe_pdf(wo, wi, wm, etap);
+
+        if wo.z * wi.z > 0.0 {
+            let f = self.distrib.d(wm) * self.distrib.g(wo, wi) * r / (4.0 * wi.z * wo.z).abs();
+            (f, pdf * r)
+        } else {
+            let denom = (wi.dot(wm) + wo.dot(wm) / etap).powi(2);
+            // radiance is compressed into the smaller solid angle of the denser medium
+            let f = self.distrib.d(wm)
+                * t
+                * self.distrib.g(wo, wi)
+                * (wi.dot(wm) * wo.dot(wm) / (wi.z * wo.z * denom)).abs()
+                / (etap * etap);
+            (f, pdf * t)
+        }
+    }
+
+    /// The density of `sample_restricted` generating `wi`, which only samples the lobe `wi` is in.
+    pub fn pdf_restricted(&self, wo: Vec3, wi: Vec3) -> f32 {
+        match self.half_vector(wo, wi) {
+            Some((wm, etap)) => self.lobe_pdf(wo, wi, wm, etap),
+            None => 0.0,
+        }
+    }
+
+    /// Samples an incident direction by choosing a visible microfacet and reflecting or
+    /// refracting at it, proportional to the Fresnel reflectance.
+    pub fn sample_wi(&self, wo: Vec3, uc: f32, u: Vec2) -> Option<Vec3> {
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let wm = self.distrib.sample_wm(wo, u);
+        let r = fresnel_dielectric(wo.dot(wm), self.eta);
+
+        let wi = if uc < r {
+            let wi = reflect(wo, wm);
+            (wi.z * wo.z > 0.0).then_some(wi)?
+        } else {
+            let (wi, _) = refract(wo, wm, self.eta)?;
+            (wi.z * wo.z < 0.0).then_some(wi)?
+        };
+
+        Some(wi)
+    }
+
+    /// Samples an incident direction by only reflecting or, if `transmit` is set, only
+    /// refracting at a visible microfacet. Returns the direction and its density.
+    pub fn sample_restricted(&self, wo: Vec3, transmit: bool, u: Vec2) -> Option<(Vec3, f32)> {
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let wm = self.distrib.sample_wm(wo, u);
+        let wi = if transmit {
+            let (wi, _) = refract(wo, wm, self.eta)?;
+            (wi.z * wo.z < 0.0).then_some(wi)?
+        } else {
+            let wi = reflect(wo, wm);
+            (wi.z * wo.z > 0.0).then_some(wi)?
+        };
+
+        let pdf = self.pdf_restricted(wo, wi);
+        (pdf > 0.0).then_some((wi, pdf))
+    }
+
+    /// The generalized half vector of a pair of directions, facing up, and the relative index of
+    /// refraction along `wi`. `None` if the microfacet would face away from either direction.
+    fn half_vector(&self, wo: Vec3, wi: Vec3) -> Option<(Vec3, f32)> {
+        let cos_o = wo.z;
+        let cos_i = wi.z;
+        if cos_o == 0.0 || cos_i == 0.0 {
+            return None;
+        }
+
+        // relative to the side wo leaves on
+        let etap = match (cos_o * cos_i > 0.0, cos_o > 0.0) {
+            (true, _) => 1.0,
+            (false, true) => self.eta,
+            (false, false) => 1.0 / self.eta,
+        };
+        let wm = wi * etap + wo;
+        if wm.length_squared() == 0.0 {
+            return None;
+        }
+        let wm = wm.normalize();
+        let wm = if wm.z < 0.0 { -wm } else { wm };
+
+        // discard back-facing microfacets
+        if wm.dot(wi) * cos_i < 0.0 || wm.dot(wo) * cos_o < 0.0 {
+            return None;
+        }
+
+        Some((wm, etap))
+    }
+
+    /// The density of sampling `wi` through the microfacet `wm`, given the choice of reflection
+    /// or transmission.
+    fn lobe_pdf(&self, wo: Vec3, wi: Vec3, wm: Vec3, etap: f32) -> f32 {
+        if wo.z * wi.z > 0.0 {
+            self.distrib.pdf(wo, wm) / (4.0 * wo.dot(wm).abs())
+        } else {
+            let denom = (wi.dot(wm) + wo.dot(wm) / etap).powi(2);
+            self.distrib.pdf(wo, wm) * wi.dot(wm).abs() / denom
+        }
+    }
+}
+
+/// Mirrors a local direction at the surface, e.g. to shade the back of a surface like its front.
+pub fn flip_z(w: Vec3) -> Vec3 {
+    vec3(w.x, w.y, -w.z)
+}
+
+/// Reflects the outgoing direction `wo` at the normal `n`.
+pub fn reflect(wo: Vec3, n: Vec3) -> Vec3 {
+    -wo + 2.0 * wo.dot(n) * n
+}
+
+/// Refracts the outgoing direction `wo` at the normal `n`, where `eta` is the index of refraction
+/// below the normal relative to the one above. Returns the refracted direction and the relative
+/// index of refraction along it, or `None` for total internal reflection.
+pub fn refract(wo: Vec3, n: Vec3, eta: f32) -> Option<(Vec3, f32)> {
+    let (mut cos_i, mut eta, mut n) = (wo.dot(n), eta, n);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+        n = -n;
+    }
+
+    let sin2_i = (1.0 - cos_i * cos_i).max(0.0);
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let wt = -wo / eta + (cos_i / eta - cos_t) * n;
+    Some((wt, eta))
+}
+
+/// The Fresnel reflectance of a dielectric interface for unpolarized light. `eta` is the index of
+/// refraction below the normal relative to the one above, and `cos_theta_i` is negative for light
+/// arriving from below.
+pub fn fresnel_dielectric(cos_theta_i: f32, eta: f32) -> f32 {
+    let (mut cos_i, mut eta) = (cos_theta_i.clamp(-1.0, 1.0), eta);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+
+    let sin2_i = 1.0 - cos_i * cos_i;
+    let sin2_t = sin2_i / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).max(0.0).sqrt();
+
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// The Fresnel reflectance of a conductor with the complex index of refraction `eta + i k`,
+/// relative to the medium above, at a single wavelength.
+pub fn fresnel_conductor(cos_theta_i: f32, eta: f32, k: f32) -> f32 {
+    let cos_i = cos_theta_i.clamp(0.0, 1.0);
+    let cos2_i = cos_i * cos_i;
+    let sin2_i = 1.0 - cos2_i;
+
+    let t0 = eta * eta - k * k - sin2_i;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).max(0.0).sqrt();
+    let t1 = a2_plus_b2 + cos2_i;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_i * a;
+    let r_s = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2_i * a2_plus_b2 + sin2_i * sin2_i;
+    let t4 = t2 * sin2_i;
+    let r_p = r_s * (t3 - t4) / (t3 + t4);
+
+    (r_p + r_s) / 2.0
+}
+
+/// The weight of Schlick's Fresnel approximation, (1 - cos θ)^5.
+pub fn schlick_weight(cos_theta: f32) -> f32 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+/// Schlick's approximation of the Fresnel reflectance with the reflectance `f0` at normal
+/// incidence.
+pub fn fresnel_schlick(f0: Vec4, cos_theta: f32) -> Vec4 {
+    f0 + (Vec4::ONE - f0) * schlick_weight(cos_theta)
+}
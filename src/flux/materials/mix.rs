@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use glam::{Vec3, Vec4};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{interaction::Interaction, ray::Ray, textures::Texture};
+
+use super::{BsdfEval, BxdfType, Material, ScatterRec, Subsurface};
+
+/// Blends two materials with a mask, which is 0 for the first material and 1 for the second one.
+/// Scattering stochastically selects one of the materials, while evaluation blends both, so
+/// partially masked surfaces stay unbiased. The subsurface medium of either material applies to
+/// all rays refracted into the surface, so a subsurface material should only be mixed with
+/// opaque ones.
+pub struct MixMaterial {
+    a: Rc<dyn Material>,
+    b: Rc<dyn Material>,
+    mask: Rc<dyn Texture<f32>>,
+}
+
+impl MixMaterial {
+    pub fn new(a: Rc<dyn Material>, b: Rc<dyn Material>, mask: Rc<dyn Texture<f32>>) -> Self {
+        Self { a, b, mask }
+    }
+
+    fn amount(&self, int: &Interaction) -> f32 {
+        self.mask.evaluate(int).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let amount = self.amount(int);
+        if amount == 0.0 {
+            return self.a.scatter(ray, int, rng);
+        }
+        if amount == 1.0 {
+            return self.b.scatter(ray, int, rng);
+        }
+
+        let (chosen, other, weight) = if rng.gen::<f32>() < amount {
+            (&self.b, &self.a, amount)
+        } else {
+            (&self.a, &self.b, 1.0 - amount)
+        };
+
+        let srec = chosen.scatter(ray, int, rng)?;
+        let Some(pdf) = srec.pdf else {
+            // the selection probability cancels the weight of specular scattering
+            return Some(srec);
+        };
+
+        // weight the sample by the blended BSDF, as if it was sampled from the mixture
+        let wo = -ray.direction;
+        let wi = srec.scattered.direction;
+        let (other_f_cos, other_pdf) = other
+            .eval(wo, wi, int, rng)
+            .map_or((Vec4::ZERO, 0.0), |other| {
+                (other.f * wi.dot(int.ns).abs(), other.pdf)
+            });
+
+        let pdf_mix = weight * pdf + (1.0 - weight) * other_pdf;
+        let f_cos_mix = weight * srec.attenuation * pdf + (1.0 - weight) * other_f_cos;
+        Some(ScatterRec {
+            attenuation: f_cos_mix / pdf_mix,
+            pdf: Some(pdf_mix),
+            ..srec
+        })
+    }
+
+    fn emitted(&self, int: &Interaction, wo: Vec3) -> Vec3 {
+        let amount = self.amount(int);
+        (1.0 - amount) * self.a.emitted(int, wo) + amount * self.b.emitted(int, wo)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.a.is_emissive() || self.b.is_emissive()
+    }
+
+    fn is_two_sided(&self) -> bool {
+        self.a.is_two_sided() || self.b.is_two_sided()
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.a.is_dispersive() || self.b.is_dispersive()
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, rng: &mut StdRng) -> Option<BsdfEval> {
+        let amount = self.amount(int);
+        let a = (amount < 1.0)
+            .then(|| self.a.eval(wo, wi, int, rng))
+            .flatten();
+        let b = (amount > 0.0)
+            .then(|| self.b.eval(wo, wi, int, rng))
+            .flatten();
+
+        match (a, b) {
+            (None, None) => None,
+            (a, b) => {
+                let zero = || BsdfEval {
+                    f: Vec4::ZERO,
+                    pdf: 0.0,
+                };
+                let (a, b) = (a.unwrap_or_else(zero), b.unwrap_or_else(zero));
+                Some(BsdfEval {
+                    f: (1.0 - amount) * a.f + amount * b.f,
+                    pdf: (1.0 - amount) * a.pdf + amount * b.pdf,
+                })
+            }
+        }
+    }
+
+    fn subsurface(&self, int: &Interaction) -> Option<Subsurface> {
+        let amount = self.amount(int);
+        let a = (amount < 1.0).then(|| self.a.subsurface(int)).flatten();
+        a.or_else(|| (amount > 0.0).then(|| self.b.subsurface(int)).flatten())
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        let (a, b) = (self.a.bxdf_type(), self.b.bxdf_type());
+        if a == b {
+            a
+        } else {
+            BxdfType::Other
+        }
+    }
+}
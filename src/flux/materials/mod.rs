@@ -1,21 +1,52 @@
+mod add;
+mod coated;
+mod conductor;
 mod dielectric;
 mod diffuse_light;
+mod dispersion;
+mod interface;
 mod matte;
 mod metal;
+mod microfacet;
+mod mix;
+mod principled;
+mod subsurface;
+mod thin_dielectric;
 
+pub use add::*;
+pub use coated::*;
+pub use conductor::*;
 pub use dielectric::*;
 pub use diffuse_light::*;
+pub use dispersion::*;
+pub use interface::*;
 pub use matte::*;
 pub use metal::*;
+pub use mix::*;
+pub use principled::*;
+pub use subsurface::*;
+pub use thin_dielectric::*;
 
-use glam::Vec3;
+use glam::{Vec3, Vec4};
 use rand::rngs::StdRng;
 
 use super::{interaction::Interaction, ray::Ray};
 
 pub struct ScatterRec {
-    pub attenuation: Vec3,
+    /// The BSDF times the cosine term over the density of the scattered direction, at the
+    /// wavelengths of the interaction, see `SampledWavelengths`.
+    pub attenuation: Vec4,
     pub scattered: Ray,
+    /// The solid angle density of the scattered direction, matching `Material::eval`. `None` for
+    /// specular scattering.
+    pub pdf: Option<f32>,
+}
+
+/// The BSDF value for a pair of directions at the wavelengths of the interaction, without the
+/// cosine term, and the solid angle density of `scatter` generating the incident direction.
+pub struct BsdfEval {
+    pub f: Vec4,
+    pub pdf: f32,
 }
 
 #[derive(PartialEq)]
@@ -28,10 +59,52 @@ pub enum BxdfType {
 pub trait Material {
     fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec>;
 
-    fn emitted(&self, _int: &Interaction) -> Vec3 {
+    /// Radiance emitted from the surface towards `wo`.
+    fn emitted(&self, _int: &Interaction, _wo: Vec3) -> Vec3 {
         Vec3::ZERO
     }
 
+    /// Whether the material emits light, so surfaces using it are sampled as area lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Whether the material emits light from the back of surfaces as well.
+    fn is_two_sided(&self) -> bool {
+        false
+    }
+
+    /// Whether the surface only bounds a medium, so rays pass through it unchanged.
+    fn is_interface(&self) -> bool {
+        false
+    }
+
+    /// Whether the scattering depends on the wavelength, like the refraction of dispersive glass.
+    /// Paths in spectral mode only follow their hero wavelength after scattering at it.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// Evaluates the BSDF for light arriving from `wi` and leaving towards `wo`. Materials that
+    /// can't be evaluated for arbitrary directions, like perfect specular ones, return `None` and
+    /// don't receive light through explicit light sampling. Stochastic materials, like layered
+    /// ones, may return an unbiased estimate of the BSDF.
+    fn eval(
+        &self,
+        _wo: Vec3,
+        _wi: Vec3,
+        _int: &Interaction,
+        _rng: &mut StdRng,
+    ) -> Option<BsdfEval> {
+        None
+    }
+
+    /// The medium below the surface of materials with subsurface scattering. The integrator
+    /// follows rays scattered into the surface through it with a random walk.
+    fn subsurface(&self, _int: &Interaction) -> Option<Subsurface> {
+        None
+    }
+
     fn bxdf_type(&self) -> BxdfType;
 }
 
@@ -42,10 +115,3 @@ pub fn is_near_zero(v: Vec3) -> bool {
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     v - 2.0 * v.dot(n) * n
 }
-
-pub fn refract(uv: Vec3, n: Vec3, etai_over_etat: f32) -> Vec3 {
-    let cos_theta = (-uv).dot(n).min(1.0);
-    let r_out_perp = etai_over_etat * (uv + cos_theta * n);
-    let r_out_parallel = -(1.0 - r_out_perp.length_squared()).abs().sqrt() * n;
-    r_out_perp + r_out_parallel
-}
@@ -0,0 +1,392 @@
+use std::{
+    f32::consts::{FRAC_1_PI, PI},
+    rc::Rc,
+};
+
+use glam::{vec3, Vec2, Vec3, Vec4};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{
+    color::luminance,
+    cosine_sample_hemisphere,
+    interaction::Interaction,
+    ray::Ray,
+    textures::{ConstantTexture, Texture},
+    Frame,
+};
+
+use super::{
+    microfacet::{
+        flip_z, fresnel_schlick, reflect, schlick_weight, DielectricBxdf, TrowbridgeReitz,
+    },
+    BsdfEval, BxdfType, Material, ScatterRec,
+};
+
+/// Reflectance at normal incidence of the clearcoat, matching an index of refraction of 1.5.
+const CLEARCOAT_F0: f32 = 0.04;
+
+/// A physically plausible uber material in the metallic-roughness workflow, following Burley,
+/// "Physically Based Shading at Disney" (2012) and its extension to transmission (2015).
+///
+/// The base is a blend of a diffuse dielectric, a metal and a rough glass, weighted by
+/// `metallic` and `transmission`. An optional clearcoat layer sits on top and removes the energy
+/// it reflects from the layers below. Every parameter is in [0, 1] and can be driven by a
+/// texture.
+pub struct PrincipledMaterial {
+    base_color: Rc<dyn Texture<Vec3>>,
+    metallic: Rc<dyn Texture<f32>>,
+    roughness: Rc<dyn Texture<f32>>,
+    /// Reflectance of dielectrics, where the default of 0.5 maps to an index of refraction of
+    /// 1.5. Also sets the index of refraction for transmission.
+    specular: Rc<dyn Texture<f32>>,
+    /// Tints the reflection of dielectrics towards the hue of the base color.
+    specular_tint: Rc<dyn Texture<f32>>,
+    /// Retro-reflective grazing highlight for cloth.
+    sheen: Rc<dyn Texture<f32>>,
+    sheen_tint: Rc<dyn Texture<f32>>,
+    clearcoat: Rc<dyn Texture<f32>>,
+    clearcoat_gloss: Rc<dyn Texture<f32>>,
+    transmission: Rc<dyn Texture<f32>>,
+    /// Stretches the highlight along the u direction of the surface parameterization.
+    anisotropic: Rc<dyn Texture<f32>>,
+    emission: Option<Rc<dyn Texture<Vec3>>>,
+}
+
+#[allow(dead_code)]
+impl PrincipledMaterial {
+    pub fn new(base_color: Rc<dyn Texture<Vec3>>) -> Self {
+        Self {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            sheen_tint: constant(0.5),
+            clearcoat: constant(0.0),
+            clearcoat_gloss: constant(1.0),
+            transmission: constant(0.0),
+            anisotropic: constant(0.0),
+            emission: None,
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: Rc<dyn Texture<f32>>) -> Self {
+        self.metallic = metallic;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: Rc<dyn Texture<f32>>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_specular(mut self, specular: Rc<dyn Texture<f32>>) -> Self {
+        self.specular = specular;
+        self
+    }
+
+    pub fn with_specular_tint(mut self, specular_tint: Rc<dyn Texture<f32>>) -> Self {
+        self.specular_tint = specular_tint;
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: Rc<dyn Texture<f32>>) -> Self {
+        self.sheen = sheen;
+        self
+    }
+
+    pub fn with_sheen_tint(mut self, sheen_tint: Rc<dyn Texture<f32>>) -> Self {
+        self.sheen_tint = sheen_tint;
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: Rc<dyn Texture<f32>>) -> Self {
+        self.clearcoat = clearcoat;
+        self
+    }
+
+    pub fn with_clearcoat_gloss(mut self, clearcoat_gloss: Rc<dyn Texture<f32>>) -> Self {
+        self.clearcoat_gloss = clearcoat_gloss;
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: Rc<dyn Texture<f32>>) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    pub fn with_anisotropic(mut self, anisotropic: Rc<dyn Texture<f32>>) -> Self {
+        self.anisotropic = anisotropic;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Rc<dyn Texture<Vec3>>) -> Self {
+        self.emission = Some(emission);
+        self
+    }
+
+    /// Evaluates the textures at the interaction.
+    fn bsdf(&self, int: &Interaction) -> PrincipledBsdf {
+        let tex = |t: &Rc<dyn Texture<f32>>| t.evaluate(int).clamp(0.0, 1.0);
+
+        let base_color = self.base_color.evaluate(int);
+        let metallic = tex(&self.metallic);
+        let transmission = tex(&self.transmission);
+        let roughness = tex(&self.roughness);
+
+        // the hue of the base color, for tinting reflections
+        let lum = luminance(base_color);
+        let tint = if lum > 0.0 {
+            base_color / lum
+        } else {
+            Vec3::ONE
+        };
+
+        let f0 = 0.08 * tex(&self.specular);
+        let dielectric_f0 = f0 * Vec3::ONE.lerp(tint, tex(&self.specular_tint));
+        let sqrt_f0 = f0.sqrt().min(0.99);
+        let eta = (1.0 + sqrt_f0) / (1.0 - sqrt_f0);
+
+        let distrib = TrowbridgeReitz::from_roughness(roughness, tex(&self.anisotropic));
+        let specular_f0 = dielectric_f0.lerp(base_color, metallic);
+        let sheen = tex(&self.sheen) * Vec3::ONE.lerp(tint, tex(&self.sheen_tint));
+        let uplift = |rgb| int.wavelengths.uplift(rgb);
+
+        PrincipledBsdf {
+            frame: Frame::from_xz(int.dpdu, int.ns),
+            base_color: uplift(base_color),
+            roughness,
+            diffuse_luminance: lum + luminance(sheen),
+            specular_f0_luminance: luminance(specular_f0),
+            diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+            specular_weight: 1.0 - (1.0 - metallic) * transmission,
+            glass_weight: (1.0 - metallic) * transmission,
+            specular_f0: uplift(specular_f0),
+            sheen: uplift(sheen),
+            clearcoat: 0.25 * tex(&self.clearcoat),
+            clearcoat_alpha: 0.1 + (0.001 - 0.1) * tex(&self.clearcoat_gloss),
+            distrib,
+            glass: DielectricBxdf::new(eta, distrib),
+        }
+    }
+}
+
+impl Material for PrincipledMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let bsdf = self.bsdf(int);
+
+        let wo = bsdf.frame.to_local(-ray.direction);
+        let wi = bsdf.sample_wi(wo, rng.gen(), rng.gen(), rng.gen())?;
+        let (f, pdf) = bsdf.eval(wo, wi);
+        if pdf == 0.0 || f == Vec4::ZERO {
+            return None;
+        }
+
+        let direction = bsdf.frame.to_world(wi);
+        Some(ScatterRec {
+            attenuation: f * wi.z.abs() / pdf,
+            scattered: int.spawn_ray_towards(direction),
+            pdf: Some(pdf),
+        })
+    }
+
+    fn emitted(&self, int: &Interaction, wo: Vec3) -> Vec3 {
+        match &self.emission {
+            Some(emission) if wo.dot(int.n) > 0.0 => emission.evaluate(int),
+            _ => Vec3::ZERO,
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emission.is_some()
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, _rng: &mut StdRng) -> Option<BsdfEval> {
+        let bsdf = self.bsdf(int);
+        let (f, pdf) = bsdf.eval(bsdf.frame.to_local(wo), bsdf.frame.to_local(wi));
+        Some(BsdfEval { f, pdf })
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Diffuse
+    }
+}
+
+/// The principled BSDF at an interaction, with all textures evaluated and the colors at the
+/// wavelengths of the interaction. Directions are in the local shading frame.
+struct PrincipledBsdf {
+    frame: Frame,
+    base_color: Vec4,
+    roughness: f32,
+    /// The luminance of the base color and the sheen, for picking lobes.
+    diffuse_luminance: f32,
+    /// The luminance of `specular_f0`, for picking lobes.
+    specular_f0_luminance: f32,
+    diffuse_weight: f32,
+    specular_weight: f32,
+    glass_weight: f32,
+    specular_f0: Vec4,
+    sheen: Vec4,
+    clearcoat: f32,
+    clearcoat_alpha: f32,
+    distrib: TrowbridgeReitz,
+    glass: DielectricBxdf,
+}
+
+impl PrincipledBsdf {
+    /// The probabilities of sampling the diffuse, specular, glass and clearcoat lobes, roughly
+    /// proportional to their reflectance towards `wo`.
+    fn lobe_probabilities(&self, wo: Vec3) -> [f32; 4] {
+        let cos_o = wo.z.abs();
+        let weights = [
+            self.diffuse_weight * self.diffuse_luminance,
+            self.specular_weight * schlick_f0(self.specular_f0_luminance, cos_o),
+            self.glass_weight,
+            self.clearcoat * schlick_f0(CLEARCOAT_F0, cos_o),
+        ];
+
+        let total: f32 = weights.iter().sum();
+        if total == 0.0 {
+            return [0.0; 4];
+        }
+        weights.map(|w| w / total)
+    }
+
+    /// The energy left for the base after the clearcoat reflected its share.
+    fn clearcoat_transmittance(&self, cos_theta: f32) -> f32 {
+        1.0 - self.clearcoat * schlick_f0(CLEARCOAT_F0, cos_theta.abs())
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3) -> (Vec4, f32) {
+        let probs = self.lobe_probabilities(wo);
+        let layer = self.clearcoat_transmittance(wo.z) * self.clearcoat_transmittance(wi.z);
+
+        let mut f = Vec4::ZERO;
+        let mut pdf = 0.0;
+
+        // the glass handles both sides of the surface itself
+        if self.glass_weight > 0.0 {
+            let (f_glass, pdf_glass) = self.glass.eval(wo, wi);
+            let tint = if wo.z * wi.z < 0.0 {
+                self.base_color
+            } else {
+                Vec4::ONE
+            };
+            f += layer * self.glass_weight * f_glass * tint;
+            pdf += probs[2] * pdf_glass;
+        }
+
+        // the opaque lobes are shaded on the side of wo
+        let (wo, wi) = if wo.z < 0.0 {
+            (flip_z(wo), flip_z(wi))
+        } else {
+            (wo, wi)
+        };
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return (f, pdf);
+        }
+
+        let wh = (wo + wi).normalize();
+        let cos_d = wi.dot(wh);
+
+        if self.diffuse_weight > 0.0 {
+            // Burley diffuse with retro-reflection, and the sheen at grazing angles
+            let fl = schlick_weight(wi.z);
+            let fv = schlick_weight(wo.z);
+            let rr = 2.0 * self.roughness * cos_d * cos_d;
+            let lambert = (1.0 - fl / 2.0) * (1.0 - fv / 2.0);
+            let retro = rr * (fl + fv + fl * fv * (rr - 1.0));
+            let diffuse = self.base_color * FRAC_1_PI * (lambert + retro);
+            let sheen = self.sheen * schlick_weight(cos_d);
+
+            f += layer * self.diffuse_weight * (diffuse + sheen);
+            pdf += probs[0] * wi.z * FRAC_1_PI;
+        }
+
+        if self.specular_weight > 0.0 {
+            let fresnel = fresnel_schlick(self.specular_f0, wo.dot(wh));
+            let spec = self.distrib.d(wh) * self.distrib.g(wo, wi) * fresnel / (4.0 * wo.z * wi.z);
+
+            f += layer * self.specular_weight * spec;
+            pdf += probs[1] * self.distrib.pdf(wo, wh) / (4.0 * wo.dot(wh));
+        }
+
+        if self.clearcoat > 0.0 {
+            let d = gtr1(wh.z, self.clearcoat_alpha);
+            let fresnel = schlick_f0(CLEARCOAT_F0, cos_d);
+            let g = smith_g_ggx(wo.z, 0.25) * smith_g_ggx(wi.z, 0.25);
+
+            f += Vec4::splat(self.clearcoat * d * fresnel * g);
+            pdf += probs[3] * d * wh.z / (4.0 * wo.dot(wh));
+        }
+
+        (f, pdf)
+    }
+
+    fn sample_wi(&self, wo: Vec3, u_lobe: f32, uc: f32, u: Vec2) -> Option<Vec3> {
+        let probs = self.lobe_probabilities(wo);
+
+        let mut lobe = 0;
+        let mut cdf = probs[0];
+        while u_lobe >= cdf && lobe < probs.len() - 1 {
+            lobe += 1;
+            cdf += probs[lobe];
+        }
+        if probs[lobe] == 0.0 {
+            return None;
+        }
+
+        if lobe == 2 {
+            return self.glass.sample_wi(wo, uc, u);
+        }
+
+        // the opaque lobes are sampled on the side of wo
+        let flipped = wo.z < 0.0;
+        let wo = if flipped { flip_z(wo) } else { wo };
+
+        let wi = match lobe {
+            0 => cosine_sample_hemisphere(u),
+            1 => reflect(wo, self.distrib.sample_wm(wo, u)),
+            _ => reflect(wo, sample_gtr1(u, self.clearcoat_alpha)),
+        };
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        Some(if flipped { flip_z(wi) } else { wi })
+    }
+}
+
+fn constant(value: f32) -> Rc<dyn Texture<f32>> {
+    Rc::new(ConstantTexture::new(value))
+}
+
+fn schlick_f0(f0: f32, cos_theta: f32) -> f32 {
+    f0 + (1.0 - f0) * schlick_weight(cos_theta)
+}
+
+/// The generalized Trowbridge-Reitz distribution with γ = 1 of the clearcoat, which has longer
+/// tails than GGX.
+fn gtr1(cos_theta_h: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let t = 1.0 + (a2 - 1.0) * cos_theta_h * cos_theta_h;
+    (a2 - 1.0) / (PI * a2.ln() * t)
+}
+
+/// Samples a half vector proportional to `gtr1` times its cosine.
+fn sample_gtr1(u: Vec2, alpha: f32) -> Vec3 {
+    let a2 = alpha * alpha;
+    let cos2_theta = ((1.0 - a2.powf(1.0 - u.x)) / (1.0 - a2)).clamp(0.0, 1.0);
+    let cos_theta = cos2_theta.sqrt();
+    let sin_theta = (1.0 - cos2_theta).sqrt();
+    let phi = 2.0 * PI * u.y;
+    vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// The separable Smith masking term of GGX for the clearcoat, divided by 2 cos θ.
+fn smith_g_ggx(cos_theta: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let c2 = cos_theta * cos_theta;
+    1.0 / (cos_theta + (a2 + c2 - a2 * c2).sqrt())
+}
@@ -0,0 +1,198 @@
+use std::rc::Rc;
+
+use glam::Vec3;
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{
+    interaction::Interaction,
+    ray::Ray,
+    textures::{ConstantTexture, Texture},
+    HenyeyGreenstein, Scene,
+};
+
+use super::{BsdfEval, BxdfType, DielectricMaterial, Material, ScatterRec};
+
+/// Walks that haven't left the volume after this many scattering events are terminated.
+const MAX_STEPS: usize = 256;
+/// The number of scattering events before Russian roulette starts.
+const RR_STEPS: usize = 8;
+
+/// Skin, marble, wax and other translucent materials, where light scatters below the surface of
+/// a closed volume. Light is reflected and refracted at a smooth or rough dielectric boundary,
+/// and refracted light takes a volumetric random walk until it leaves the volume again.
+///
+/// The medium inside is given by the multiple scattering albedo, which is roughly the color of
+/// the surface, and the mean free path, which is the average distance light travels between
+/// scattering events, per color channel.
+pub struct SubsurfaceMaterial {
+    albedo: Rc<dyn Texture<Vec3>>,
+    mean_free_path: Rc<dyn Texture<Vec3>>,
+    /// The asymmetry of the Henyey-Greenstein phase function.
+    g: f32,
+    ior: f32,
+    roughness: Rc<dyn Texture<f32>>,
+    boundary: DielectricMaterial,
+}
+
+#[allow(dead_code)]
+impl SubsurfaceMaterial {
+    pub fn new(albedo: Rc<dyn Texture<Vec3>>, mean_free_path: Rc<dyn Texture<Vec3>>) -> Self {
+        let ior = 1.4;
+        let roughness: Rc<dyn Texture<f32>> = Rc::new(ConstantTexture::new(0.0));
+        Self {
+            albedo,
+            mean_free_path,
+            g: 0.0,
+            ior,
+            boundary: boundary(ior, &roughness),
+            roughness,
+        }
+    }
+
+    /// Sets the asymmetry of the phase function in (-1, 1), e.g. about 0.8 for forward
+    /// scattering skin.
+    pub fn with_anisotropy(mut self, g: f32) -> Self {
+        self.g = g;
+        self
+    }
+
+    pub fn with_ior(mut self, ior: f32) -> Self {
+        self.ior = ior;
+        self.boundary = boundary(self.ior, &self.roughness);
+        self
+    }
+
+    /// Sets the perceptual roughness of the boundary in [0, 1].
+    pub fn with_roughness(mut self, roughness: Rc<dyn Texture<f32>>) -> Self {
+        self.roughness = roughness;
+        self.boundary = boundary(self.ior, &self.roughness);
+        self
+    }
+}
+
+fn boundary(ior: f32, roughness: &Rc<dyn Texture<f32>>) -> DielectricMaterial {
+    DielectricMaterial::new(Rc::new(ConstantTexture::new(Vec3::ONE)), ior)
+        .with_roughness(roughness.clone())
+}
+
+impl Material for SubsurfaceMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        self.boundary.scatter(ray, int, rng)
+    }
+
+    fn eval(&self, wo: Vec3, wi: Vec3, int: &Interaction, rng: &mut StdRng) -> Option<BsdfEval> {
+        self.boundary.eval(wo, wi, int, rng)
+    }
+
+    fn subsurface(&self, int: &Interaction) -> Option<Subsurface> {
+        let albedo = self.albedo.evaluate(int);
+        let mean_free_path = self.mean_free_path.evaluate(int);
+
+        let channels = [0, 1, 2].map(|c| remap(albedo[c], mean_free_path[c]));
+        let sigma_t = Vec3::from(channels.map(|(sigma_t, _)| sigma_t));
+        let alpha = Vec3::from(channels.map(|(_, alpha)| alpha));
+
+        Some(Subsurface {
+            sigma_t,
+            sigma_s: alpha * sigma_t,
+            phase: HenyeyGreenstein::new(self.g),
+        })
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Other
+    }
+}
+
+/// Finds the extinction coefficient and the single scattering albedo of a medium that has the
+/// multiple scattering albedo `albedo` and the mean free path `mean_free_path`, using the fit of
+/// the random walk in Cycles.
+fn remap(albedo: f32, mean_free_path: f32) -> (f32, f32) {
+    let albedo = albedo.clamp(0.0, 0.999);
+    let alpha = 1.0 - (albedo * (-5.09406 + albedo * (2.61188 - albedo * 4.31805))).exp();
+    let s = 1.9 - albedo + 3.5 * (albedo - 0.8).powi(2);
+    let sigma_t = 1.0 / (mean_free_path * s).max(1e-6);
+    (sigma_t, alpha)
+}
+
+/// The homogeneous medium below the surface of a subsurface material, as found where the path
+/// entered it.
+pub struct Subsurface {
+    sigma_t: Vec3,
+    sigma_s: Vec3,
+    phase: HenyeyGreenstein,
+}
+
+/// The result of a random walk through the medium of a subsurface material.
+pub struct RandomWalk<'a> {
+    /// The interaction at which the walk reached the boundary again, and the last segment of the
+    /// walk leading to it. `None` if the light was absorbed.
+    pub exit: Option<(Interaction<'a>, Ray)>,
+    /// The throughput of the walk.
+    pub weight: Vec3,
+    /// The number of rays traced.
+    pub rays: usize,
+}
+
+impl Subsurface {
+    /// Follows the ray, which was refracted into the volume, from one scattering event to the
+    /// next, until it reaches the boundary. The distances are sampled for one color channel at a
+    /// time, chosen by the throughput, and weighted with the densities of all channels.
+    pub fn random_walk<'a>(&self, scene: &'a Scene, ray: &Ray, rng: &mut StdRng) -> RandomWalk<'a> {
+        let mut ray = Ray::new(ray.origin, ray.direction.normalize(), ray.time);
+        let mut walk = RandomWalk {
+            exit: None,
+            weight: Vec3::ONE,
+            rays: 0,
+        };
+
+        for step in 0..MAX_STEPS {
+            let sum = walk.weight.dot(Vec3::ONE);
+            if sum <= 0.0 {
+                break;
+            }
+            let channel_pmf = walk.weight / sum;
+
+            let u: f32 = rng.gen();
+            let channel = if u < channel_pmf.x {
+                0
+            } else if u < channel_pmf.x + channel_pmf.y {
+                1
+            } else {
+                2
+            };
+            let t = -(1.0 - rng.gen::<f32>()).ln() / self.sigma_t[channel];
+
+            walk.rays += 1;
+            // a walk leaving an open surface is lost
+            let Some(int) = scene.intersect(&ray) else {
+                break;
+            };
+
+            if int.t <= t {
+                let tr = (-self.sigma_t * int.t).exp();
+                let pdf = (channel_pmf * tr).dot(Vec3::ONE);
+                walk.weight *= tr / pdf;
+                walk.exit = Some((int, ray));
+                break;
+            }
+
+            let tr = (-self.sigma_t * t).exp();
+            let pdf = (channel_pmf * self.sigma_t * tr).dot(Vec3::ONE);
+            walk.weight *= self.sigma_s * tr / pdf;
+
+            if step >= RR_STEPS {
+                let q = walk.weight.max_element().min(1.0);
+                if rng.gen::<f32>() >= q {
+                    break;
+                }
+                walk.weight /= q;
+            }
+
+            let direction = self.phase.sample(ray.direction, rng.gen());
+            ray = Ray::new(ray.at(t), direction, ray.time);
+        }
+
+        walk
+    }
+}
@@ -0,0 +1,53 @@
+use std::rc::Rc;
+
+use glam::{vec3, Vec3, Vec4};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{interaction::Interaction, ray::Ray, textures::Texture, Frame};
+
+use super::{microfacet::fresnel_dielectric, BxdfType, Material, ScatterRec};
+
+/// An infinitesimally thin dielectric slab, like a window pane modeled as a single quad. Light
+/// is reflected or passes straight through, accounting for all inter-reflections between the
+/// two sides of the slab.
+pub struct ThinDielectricMaterial {
+    /// Tints transmitted light.
+    kd: Rc<dyn Texture<Vec3>>,
+    ior: f32,
+}
+
+impl ThinDielectricMaterial {
+    pub fn new(kd: Rc<dyn Texture<Vec3>>, ior: f32) -> Self {
+        Self { kd, ior }
+    }
+}
+
+impl Material for ThinDielectricMaterial {
+    fn scatter(&self, ray: &Ray, int: &Interaction, rng: &mut StdRng) -> Option<ScatterRec> {
+        let frame = Frame::from_z(int.ns);
+        let wo = frame.to_local(-ray.direction);
+
+        // sum the geometric series of reflections inside the slab
+        let mut r = fresnel_dielectric(wo.z.abs(), self.ior);
+        if r < 1.0 {
+            let t = 1.0 - r;
+            r += t * t * r / (1.0 - r * r);
+        }
+
+        let (wi, attenuation) = if rng.gen::<f32>() < r {
+            (vec3(-wo.x, -wo.y, wo.z), Vec4::ONE)
+        } else {
+            (-wo, int.wavelengths.uplift(self.kd.evaluate(int)))
+        };
+
+        Some(ScatterRec {
+            attenuation,
+            scattered: int.spawn_ray_towards(frame.to_world(wi)),
+            pdf: None,
+        })
+    }
+
+    fn bxdf_type(&self) -> BxdfType {
+        BxdfType::Other
+    }
+}
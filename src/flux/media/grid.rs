@@ -0,0 +1,599 @@
+use std::{fmt::Debug, io::Read, path::Path};
+
+use anyhow::{bail, Context, Result};
+use glam::{ivec3, uvec3, Affine3A, IVec3, UVec3, Vec3};
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{
+    blackbody_rgb,
+    ray::Ray,
+    shapes::{QuadBox, Shape, Transform},
+    Bounds3, HenyeyGreenstein,
+};
+
+use super::{Medium, MediumSample};
+
+/// The edge length of the bricks of a sparse grid in voxels.
+const BRICK_SIZE: i32 = 8;
+/// The resolution of the grid of majorants along each axis.
+const MAJORANT_RESOLUTION: i32 = 16;
+/// The number of entries of the table of blackbody colors.
+const BLACKBODY_STEPS: usize = 256;
+
+/// A scalar field sampled at the centers of the voxels of a regular grid, which spans the unit
+/// cube. Grids that are mostly empty only store the bricks of voxels that aren't all zero.
+pub struct VoxelGrid {
+    resolution: IVec3,
+    voxels: Voxels,
+}
+
+enum Voxels {
+    /// All values, with x varying fastest.
+    Dense(Vec<f32>),
+    /// The bricks in the same order, `None` where all of their values are zero.
+    Sparse {
+        bricks: Vec<Option<Box<[f32]>>>,
+        brick_resolution: IVec3,
+    },
+}
+
+#[allow(dead_code)]
+impl VoxelGrid {
+    /// Creates a grid from its values, with x varying fastest and z slowest. The values are stored
+    /// in bricks if at least half of the bricks are empty.
+    pub fn new(resolution: UVec3, values: Vec<f32>) -> Self {
+        assert_eq!(
+            values.len(),
+            resolution.x as usize * resolution.y as usize * resolution.z as usize,
+            "voxel grid size doesn't match its resolution"
+        );
+        let resolution = resolution.as_ivec3();
+
+        let brick_resolution = (resolution + BRICK_SIZE - 1) / BRICK_SIZE;
+        let bricks: Vec<Option<Box<[f32]>>> = grid_points(brick_resolution)
+            .map(|brick| {
+                let origin = brick * BRICK_SIZE;
+                let voxels: Box<[f32]> = grid_points(IVec3::splat(BRICK_SIZE))
+                    .map(|offset| {
+                        let p = origin + offset;
+                        if p.cmplt(resolution).all() {
+                            values[linear_index(p, resolution)]
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect();
+                voxels.iter().any(|&v| v != 0.0).then_some(voxels)
+            })
+            .collect();
+
+        let filled = bricks.iter().filter(|brick| brick.is_some()).count();
+        let voxels = if 2 * filled <= bricks.len() {
+            Voxels::Sparse {
+                bricks,
+                brick_resolution,
+            }
+        } else {
+            Voxels::Dense(values)
+        };
+
+        Self { resolution, voxels }
+    }
+
+    /// Evaluates `f` at the center of every voxel, given in the unit cube.
+    pub fn from_fn(resolution: UVec3, f: impl Fn(Vec3) -> f32) -> Self {
+        let size = resolution.as_vec3();
+        let values = grid_points(resolution.as_ivec3())
+            .map(|p| f((p.as_vec3() + 0.5) / size))
+            .collect();
+        Self::new(resolution, values)
+    }
+
+    pub fn resolution(&self) -> UVec3 {
+        self.resolution.as_uvec3()
+    }
+
+    /// The value of the voxel at `p`, which is 0 outside of the grid.
+    pub fn voxel(&self, p: IVec3) -> f32 {
+        if p.cmplt(IVec3::ZERO).any() || p.cmpge(self.resolution).any() {
+            return 0.0;
+        }
+
+        match &self.voxels {
+            Voxels::Dense(values) => values[linear_index(p, self.resolution)],
+            Voxels::Sparse {
+                bricks,
+                brick_resolution,
+            } => match &bricks[linear_index(p / BRICK_SIZE, *brick_resolution)] {
+                Some(voxels) => voxels[linear_index(p % BRICK_SIZE, IVec3::splat(BRICK_SIZE))],
+                None => 0.0,
+            },
+        }
+    }
+
+    /// Interpolates the voxels trilinearly at `p` in the unit cube.
+    pub fn lookup(&self, p: Vec3) -> f32 {
+        let g = p * self.resolution.as_vec3() - 0.5;
+        let base = g.floor();
+        let f = g - base;
+        let base = base.as_ivec3();
+
+        let mut value = 0.0;
+        for corner in grid_points(IVec3::splat(2)) {
+            let weight = Vec3::select(corner.cmpeq(IVec3::ZERO), 1.0 - f, f);
+            value += weight.x * weight.y * weight.z * self.voxel(base + corner);
+        }
+        value
+    }
+
+    /// The largest value that trilinear interpolation can return inside of the box between `min`
+    /// and `max` in the unit cube.
+    pub fn max_in(&self, min: Vec3, max: Vec3) -> f32 {
+        let size = self.resolution.as_vec3();
+        let first = (min * size - 0.5).floor().as_ivec3();
+        let last = (max * size - 0.5).ceil().as_ivec3();
+
+        grid_points(last - first + 1)
+            .map(|offset| self.voxel(first + offset))
+            .fold(0.0, f32::max)
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max_in(Vec3::ZERO, Vec3::ONE)
+    }
+}
+
+/// The points of a grid with the given resolution, with x varying fastest.
+fn grid_points(resolution: IVec3) -> impl Iterator<Item = IVec3> {
+    (0..resolution.z).flat_map(move |z| {
+        (0..resolution.y).flat_map(move |y| (0..resolution.x).map(move |x| ivec3(x, y, z)))
+    })
+}
+
+fn linear_index(p: IVec3, resolution: IVec3) -> usize {
+    (p.x + resolution.x * (p.y + resolution.y * p.z)) as usize
+}
+
+/// The contents of a grid file: the density and optionally the temperature in Kelvin.
+pub struct GridFile {
+    pub density: VoxelGrid,
+    pub temperature: Option<VoxelGrid>,
+}
+
+impl GridFile {
+    /// Loads a raw voxel grid. The file starts with the magic bytes `FLUXGRID`, followed by the
+    /// resolution along x, y and z and the number of channels, 1 or 2, as little endian `u32`s.
+    /// Then come the little endian `f32` values of the density and, for 2 channels, the
+    /// temperature, each with x varying fastest.
+    pub fn load<P: AsRef<Path> + Debug>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open grid file {:?}", path))?;
+        let file_len = file
+            .metadata()
+            .with_context(|| format!("Failed to read metadata of grid file {:?}", path))?
+            .len();
+        let mut file = std::io::BufReader::new(file);
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)
+            .with_context(|| format!("Failed to read header of grid file {:?}", path))?;
+        if &magic != b"FLUXGRID" {
+            bail!("Grid file {:?} doesn't start with FLUXGRID", path);
+        }
+
+        let mut header = [0; 16];
+        file.read_exact(&mut header)
+            .with_context(|| format!("Failed to read header of grid file {:?}", path))?;
+        let [nx, ny, nz, channels] =
+            [0, 1, 2, 3].map(|i| u32::from_le_bytes(header[4 * i..4 * i + 4].try_into().unwrap()));
+        if !(1..=2).contains(&channels) {
+            bail!(
+                "Grid file {:?} has {} channels instead of 1 or 2",
+                path,
+                channels
+            );
+        }
+        // check the size against the file before allocating anything
+        let count = [ny, nz]
+            .into_iter()
+            .try_fold(nx as usize, |count, n| count.checked_mul(n as usize))
+            .filter(|&count| count > 0);
+        let Some(count) = count else {
+            bail!(
+                "Grid file {:?} has an invalid resolution of {}x{}x{}",
+                path,
+                nx,
+                ny,
+                nz
+            );
+        };
+        let data_len = (4 * channels as usize).checked_mul(count);
+        if data_len.is_none_or(|len| len as u64 > file_len) {
+            bail!(
+                "Grid file {:?} is too short for a resolution of {}x{}x{} with {} channels",
+                path,
+                nx,
+                ny,
+                nz,
+                channels
+            );
+        }
+        let resolution = uvec3(nx, ny, nz);
+
+        let mut read_channel = |name: &str| -> Result<VoxelGrid> {
+            let mut bytes = vec![0; 4 * count];
+            file.read_exact(&mut bytes)
+                .with_context(|| format!("Failed to read {} of grid file {:?}", name, path))?;
+            let values = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect();
+            Ok(VoxelGrid::new(resolution, values))
+        };
+
+        let density = read_channel("density")?;
+        let temperature = if channels == 2 {
+            Some(read_channel("temperature")?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            density,
+            temperature,
+        })
+    }
+}
+
+/// A heterogeneous medium like smoke, clouds or fire, whose density is given by a voxel grid.
+/// The absorption and scattering coefficients are scaled by the density. A temperature grid lets
+/// the medium emit light like a black body, in proportion to its absorption.
+///
+/// Distances are sampled with delta tracking and transmittance is estimated with ratio tracking,
+/// against the maximum densities of the cells of a coarse grid.
+pub struct GridMedium {
+    world_from_medium: Affine3A,
+    medium_from_world: Affine3A,
+    density: VoxelGrid,
+    majorants: Vec<f32>,
+    sigma_a: Vec3,
+    sigma_s: Vec3,
+    phase: HenyeyGreenstein,
+    temperature: Option<VoxelGrid>,
+    /// The colors of black bodies from 0 up to the highest temperature of the grid.
+    blackbody: Vec<Vec3>,
+    max_temperature: f32,
+    emission_scale: f32,
+}
+
+#[allow(dead_code)]
+impl GridMedium {
+    /// Fits the grid into `bounds`. The coefficients are given per unit distance at a density
+    /// of 1.
+    pub fn new(density: VoxelGrid, bounds: Bounds3<Vec3>, sigma_a: Vec3, sigma_s: Vec3) -> Self {
+        let cell_size = 1.0 / MAJORANT_RESOLUTION as f32;
+        let majorants = grid_points(IVec3::splat(MAJORANT_RESOLUTION))
+            .map(|cell| {
+                let min = cell.as_vec3() * cell_size;
+                density.max_in(min, min + cell_size)
+            })
+            .collect();
+
+        let world_from_medium =
+            Affine3A::from_translation(bounds.min) * Affine3A::from_scale(bounds.diagonal());
+
+        Self {
+            world_from_medium,
+            medium_from_world: world_from_medium.inverse(),
+            density,
+            majorants,
+            sigma_a,
+            sigma_s,
+            phase: HenyeyGreenstein::new(0.0),
+            temperature: None,
+            blackbody: Vec::new(),
+            max_temperature: 0.0,
+            emission_scale: 1.0,
+        }
+    }
+
+    /// Loads the density and temperature from a grid file, see [`GridFile::load`].
+    pub fn load<P: AsRef<Path> + Debug>(
+        path: P,
+        bounds: Bounds3<Vec3>,
+        sigma_a: Vec3,
+        sigma_s: Vec3,
+    ) -> Result<Self> {
+        let file = GridFile::load(path)?;
+        let medium = Self::new(file.density, bounds, sigma_a, sigma_s);
+        Ok(match file.temperature {
+            Some(temperature) => medium.with_temperature(temperature),
+            None => medium,
+        })
+    }
+
+    /// Places the bounds in the world with `transform`, e.g. to rotate the grid.
+    pub fn with_transform(mut self, transform: Affine3A) -> Self {
+        self.world_from_medium = transform * self.world_from_medium;
+        self.medium_from_world = self.world_from_medium.inverse();
+        self
+    }
+
+    /// Sets the asymmetry of the phase function in (-1, 1).
+    pub fn with_anisotropy(mut self, g: f32) -> Self {
+        self.phase = HenyeyGreenstein::new(g);
+        self
+    }
+
+    /// Sets the temperature in Kelvin, which may have a different resolution than the density.
+    pub fn with_temperature(mut self, temperature: VoxelGrid) -> Self {
+        self.max_temperature = temperature.max();
+        self.blackbody = (0..BLACKBODY_STEPS)
+            .map(|i| {
+                let t = i as f32 / (BLACKBODY_STEPS - 1) as f32;
+                blackbody_rgb(t * self.max_temperature)
+            })
+            .collect();
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Scales the emission of the black body radiation.
+    pub fn with_emission(mut self, scale: f32) -> Self {
+        self.emission_scale = scale;
+        self
+    }
+
+    /// A box around the grid, which bounds the medium when used with the interface material.
+    pub fn boundary(&self) -> Box<dyn Shape> {
+        let unit_cube = Affine3A::from_translation(Vec3::splat(0.5));
+        Box::new(Transform::new(
+            self.world_from_medium * unit_cube,
+            Box::new(QuadBox::new(1.0, 1.0, 1.0)),
+        ))
+    }
+
+    /// The emitted radiance per unit distance and absorption at `p` in the unit cube.
+    fn emission(&self, p: Vec3) -> Vec3 {
+        let Some(temperature) = &self.temperature else {
+            return Vec3::ZERO;
+        };
+        if self.max_temperature <= 0.0 {
+            return Vec3::ZERO;
+        }
+
+        let x = (temperature.lookup(p) / self.max_temperature).clamp(0.0, 1.0)
+            * (BLACKBODY_STEPS - 1) as f32;
+        let i = (x as usize).min(BLACKBODY_STEPS - 2);
+        let t = x - i as f32;
+        self.emission_scale * self.blackbody[i].lerp(self.blackbody[i + 1], t)
+    }
+
+    /// Iterates over the cells of the majorant grid along the ray up to `t_max`.
+    fn majorants(&self, ray: &Ray, t_max: f32) -> MajorantIterator<'_> {
+        let origin = self.medium_from_world.transform_point3(ray.origin);
+        let direction = self.medium_from_world.transform_vector3(ray.direction);
+        // the densities are given per unit distance in world space
+        let sigma_t = (self.sigma_a + self.sigma_s).max_element() * ray.direction.length();
+
+        MajorantIterator::new(self, origin, direction, t_max, sigma_t)
+    }
+}
+
+impl Medium for GridMedium {
+    /// Samples tentative collisions against the majorants. At each of them, the emission is
+    /// added and the path is either absorbed, scattered or continues through a null collision,
+    /// chosen by the average coefficients and weighted with those of all channels.
+    fn sample(&self, ray: &Ray, t_max: f32, rng: &mut StdRng) -> MediumSample {
+        let mut weight = Vec3::ONE;
+        let mut emission = Vec3::ZERO;
+
+        for segment in self.majorants(ray, t_max) {
+            if segment.sigma_maj <= 0.0 {
+                continue;
+            }
+
+            let mut t = segment.t_min;
+            loop {
+                t -= (1.0 - rng.gen::<f32>()).ln() / segment.sigma_maj;
+                if t >= segment.t_max {
+                    break;
+                }
+
+                let p = segment.origin + t * segment.direction;
+                let density = self.density.lookup(p);
+                let speed = ray.direction.length();
+                let sigma_a = density * self.sigma_a * speed;
+                let sigma_s = density * self.sigma_s * speed;
+                let sigma_n = (segment.sigma_maj - sigma_a - sigma_s).max(Vec3::ZERO);
+
+                emission += weight * sigma_a * self.emission(p) / segment.sigma_maj;
+
+                let p_absorb = sigma_a.dot(Vec3::ONE) / 3.0 / segment.sigma_maj;
+                let p_scatter = sigma_s.dot(Vec3::ONE) / 3.0 / segment.sigma_maj;
+                let p_null = (1.0 - p_absorb - p_scatter).max(0.0);
+
+                let u = rng.gen::<f32>() * (p_absorb + p_scatter + p_null);
+                if u < p_absorb {
+                    return MediumSample {
+                        t: None,
+                        weight: Vec3::ZERO,
+                        emission,
+                    };
+                } else if u < p_absorb + p_scatter {
+                    return MediumSample {
+                        t: Some(t),
+                        weight: weight * sigma_s / (segment.sigma_maj * p_scatter),
+                        emission,
+                    };
+                }
+                weight *= sigma_n / (segment.sigma_maj * p_null);
+            }
+        }
+
+        MediumSample {
+            t: None,
+            weight,
+            emission,
+        }
+    }
+
+    /// Estimates the transmittance with ratio tracking, terminating unlikely paths with Russian
+    /// roulette.
+    fn tr(&self, ray: &Ray, t_max: f32, rng: &mut StdRng) -> Vec3 {
+        let mut tr = Vec3::ONE;
+
+        for segment in self.majorants(ray, t_max) {
+            if segment.sigma_maj <= 0.0 {
+                continue;
+            }
+
+            let mut t = segment.t_min;
+            loop {
+                t -= (1.0 - rng.gen::<f32>()).ln() / segment.sigma_maj;
+                if t >= segment.t_max {
+                    break;
+                }
+
+                let density = self.density.lookup(segment.origin + t * segment.direction);
+                let sigma_t = density * (self.sigma_a + self.sigma_s) * ray.direction.length();
+                tr *= (1.0 - sigma_t / segment.sigma_maj).max(Vec3::ZERO);
+
+                if tr.max_element() < 0.1 {
+                    if rng.gen::<f32>() < 0.5 {
+                        return Vec3::ZERO;
+                    }
+                    tr *= 2.0;
+                }
+            }
+        }
+
+        tr
+    }
+
+    fn phase(&self) -> HenyeyGreenstein {
+        self.phase
+    }
+}
+
+/// A part of the ray that lies within one cell of the majorant grid.
+struct MajorantSegment {
+    t_min: f32,
+    t_max: f32,
+    /// The majorant of the extinction per unit of the ray parameter.
+    sigma_maj: f32,
+    /// The ray in the unit cube of the grid.
+    origin: Vec3,
+    direction: Vec3,
+}
+
+/// Steps through the cells of the majorant grid along a ray with a 3D DDA.
+struct MajorantIterator<'a> {
+    majorants: &'a [f32],
+    sigma_t: f32,
+    origin: Vec3,
+    direction: Vec3,
+    t_min: f32,
+    t_max: f32,
+    cell: IVec3,
+    /// The ray parameters at which the ray crosses into the next cell along each axis.
+    next_crossing: Vec3,
+    delta: Vec3,
+    step: IVec3,
+    done: bool,
+}
+
+impl<'a> MajorantIterator<'a> {
+    fn new(
+        medium: &'a GridMedium,
+        origin: Vec3,
+        direction: Vec3,
+        t_max: f32,
+        sigma_t: f32,
+    ) -> Self {
+        // clip the ray against the unit cube
+        let inv_direction = direction.recip();
+        let t0 = (Vec3::ZERO - origin) * inv_direction;
+        let t1 = (Vec3::ONE - origin) * inv_direction;
+        let t_enter = t0.min(t1).max_element().max(0.0);
+        let t_exit = t0.max(t1).min_element().min(t_max);
+
+        let resolution = MAJORANT_RESOLUTION as f32;
+        let p = (origin + t_enter * direction) * resolution;
+        let cell = p
+            .floor()
+            .as_ivec3()
+            .clamp(IVec3::ZERO, IVec3::splat(MAJORANT_RESOLUTION - 1));
+
+        let mut next_crossing = Vec3::INFINITY;
+        let mut delta = Vec3::INFINITY;
+        let mut step = IVec3::ZERO;
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                let boundary = (cell[axis] + 1) as f32;
+                next_crossing[axis] =
+                    t_enter + (boundary - p[axis]) / (direction[axis] * resolution);
+                delta[axis] = 1.0 / (direction[axis] * resolution);
+                step[axis] = 1;
+            } else if direction[axis] < 0.0 {
+                let boundary = cell[axis] as f32;
+                next_crossing[axis] =
+                    t_enter + (boundary - p[axis]) / (direction[axis] * resolution);
+                delta[axis] = -1.0 / (direction[axis] * resolution);
+                step[axis] = -1;
+            }
+        }
+
+        Self {
+            majorants: &medium.majorants,
+            sigma_t,
+            origin,
+            direction,
+            t_min: t_enter,
+            t_max: t_exit,
+            cell,
+            next_crossing,
+            delta,
+            step,
+            // rays that miss the cube have an empty range
+            done: t_enter >= t_exit || t_exit.is_nan(),
+        }
+    }
+}
+
+impl Iterator for MajorantIterator<'_> {
+    type Item = MajorantSegment;
+
+    fn next(&mut self) -> Option<MajorantSegment> {
+        if self.done {
+            return None;
+        }
+
+        let c = self.next_crossing;
+        let axis = if c.x <= c.y && c.x <= c.z {
+            0
+        } else if c.y <= c.z {
+            1
+        } else {
+            2
+        };
+        let t_end = self.next_crossing[axis].min(self.t_max);
+        let majorant = self.majorants[linear_index(self.cell, IVec3::splat(MAJORANT_RESOLUTION))];
+        let segment = MajorantSegment {
+            t_min: self.t_min,
+            t_max: t_end,
+            sigma_maj: majorant * self.sigma_t,
+            origin: self.origin,
+            direction: self.direction,
+        };
+
+        self.t_min = t_end;
+        self.cell[axis] += self.step[axis];
+        self.next_crossing[axis] += self.delta[axis];
+        if t_end >= self.t_max || self.cell[axis] < 0 || self.cell[axis] >= MAJORANT_RESOLUTION {
+            self.done = true;
+        }
+
+        Some(segment)
+    }
+}
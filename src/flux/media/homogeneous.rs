@@ -0,0 +1,89 @@
+use glam::Vec3;
+use rand::{rngs::StdRng, Rng};
+
+use crate::flux::{ray::Ray, HenyeyGreenstein};
+
+use super::{Medium, MediumSample};
+
+/// A medium with the same density everywhere, like fog, smoke or murky water. The absorption
+/// and scattering coefficients are given per unit distance and color channel.
+pub struct HomogeneousMedium {
+    sigma_a: Vec3,
+    sigma_s: Vec3,
+    phase: HenyeyGreenstein,
+}
+
+#[allow(dead_code)]
+impl HomogeneousMedium {
+    pub fn new(sigma_a: Vec3, sigma_s: Vec3) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            phase: HenyeyGreenstein::new(0.0),
+        }
+    }
+
+    /// Sets the asymmetry of the phase function in (-1, 1).
+    pub fn with_anisotropy(mut self, g: f32) -> Self {
+        self.phase = HenyeyGreenstein::new(g);
+        self
+    }
+
+    fn sigma_t(&self) -> Vec3 {
+        self.sigma_a + self.sigma_s
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    /// Samples the distance for one of the color channels, chosen uniformly, and weights it with
+    /// the average density of all channels.
+    fn sample(&self, ray: &Ray, t_max: f32, rng: &mut StdRng) -> MediumSample {
+        let sigma_t = self.sigma_t();
+        let speed = ray.direction.length();
+
+        let channel = rng.gen_range(0..3);
+        let dist = -(1.0 - rng.gen::<f32>()).ln() / sigma_t[channel];
+        let t = (dist / speed).min(t_max);
+        let scattered = t < t_max;
+
+        let tr = transmittance(sigma_t, t * speed);
+        let density = if scattered { sigma_t * tr } else { tr };
+        let pdf = density.dot(Vec3::ONE) / 3.0;
+        if pdf == 0.0 {
+            return MediumSample {
+                t: None,
+                weight: Vec3::ZERO,
+                emission: Vec3::ZERO,
+            };
+        }
+
+        if scattered {
+            MediumSample {
+                t: Some(t),
+                weight: self.sigma_s * tr / pdf,
+                emission: Vec3::ZERO,
+            }
+        } else {
+            MediumSample {
+                t: None,
+                weight: tr / pdf,
+                emission: Vec3::ZERO,
+            }
+        }
+    }
+
+    fn tr(&self, ray: &Ray, t_max: f32, _rng: &mut StdRng) -> Vec3 {
+        transmittance(self.sigma_t(), t_max * ray.direction.length())
+    }
+
+    fn phase(&self) -> HenyeyGreenstein {
+        self.phase
+    }
+}
+
+/// The Beer-Lambert transmittance over the distance `dist`, which may be infinite.
+fn transmittance(sigma_t: Vec3, dist: f32) -> Vec3 {
+    let tr = (-sigma_t * dist).exp();
+    // avoid 0 * inf for channels that don't attenuate at all
+    Vec3::select(sigma_t.cmpeq(Vec3::ZERO), Vec3::ONE, tr)
+}
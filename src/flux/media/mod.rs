@@ -0,0 +1,44 @@
+mod grid;
+mod homogeneous;
+
+pub use grid::*;
+pub use homogeneous::*;
+
+use std::sync::Arc;
+
+use glam::Vec3;
+use rand::rngs::StdRng;
+
+use super::{ray::Ray, HenyeyGreenstein};
+
+/// A participating medium, which absorbs and scatters light travelling through it. Rays hold on to
+/// the medium they travel through, so media are shared between the render threads.
+pub trait Medium: Send + Sync {
+    /// Samples the distance the ray travels before it scatters in the medium, up to `t_max`
+    /// where it reaches a surface.
+    fn sample(&self, ray: &Ray, t_max: f32, rng: &mut StdRng) -> MediumSample;
+
+    /// The fraction of light that passes the medium along the ray up to `t_max`. May be an
+    /// unbiased estimate.
+    fn tr(&self, ray: &Ray, t_max: f32, rng: &mut StdRng) -> Vec3;
+
+    fn phase(&self) -> HenyeyGreenstein;
+}
+
+pub struct MediumSample {
+    /// The ray parameter at which the ray scatters, `None` if it passes the medium up to `t_max`.
+    pub t: Option<f32>,
+    /// The throughput up to the sampled point, divided by the probability of sampling it.
+    pub weight: Vec3,
+    /// The radiance emitted by the medium along the ray up to the sampled point, weighted like
+    /// `weight`.
+    pub emission: Vec3,
+}
+
+/// The media on both sides of a closed surface, with the normals facing outside. Rays crossing
+/// the surface travel through the medium on the side they leave to, where `None` is vacuum.
+#[derive(Clone, Default)]
+pub struct MediumInterface {
+    pub inside: Option<Arc<dyn Medium>>,
+    pub outside: Option<Arc<dyn Medium>>,
+}
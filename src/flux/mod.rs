@@ -1,18 +1,27 @@
 mod accel;
+pub mod animation;
 mod bounds;
 pub mod cameras;
+mod color;
 mod denoise;
+mod distribution;
 mod film;
+mod frame;
 pub mod integrators;
-mod interaction;
+pub mod interaction;
 pub mod lights;
 mod materials;
+pub mod media;
+mod normal_map;
+mod phase;
 mod primitive;
 mod ray;
 mod renderer;
+mod rgb_to_spectrum;
 mod sampler;
 mod scene;
 pub mod shapes;
+mod spectrum;
 pub mod textures;
 mod updater;
 
@@ -20,11 +29,16 @@ pub use bounds::*;
 pub use cameras::*;
 pub use denoise::*;
 pub use film::Film;
+pub use frame::*;
 pub use materials::*;
+pub use normal_map::*;
+pub use phase::*;
 pub use primitive::*;
 pub use renderer::*;
+pub use rgb_to_spectrum::*;
 pub use sampler::*;
 pub use scene::*;
+pub use spectrum::*;
 pub use updater::*;
 
 use std::f32::consts::PI;
@@ -48,6 +62,27 @@ pub fn uniform_sample_sphere(u: Vec2) -> Vec3 {
     vec3(x, y, z)
 }
 
+/// Samples a direction in the cone around +Z with the given cosine of its half-angle.
+pub fn uniform_sample_cone(u: Vec2, cos_theta_max: f32) -> Vec3 {
+    let cos_theta = 1.0 - u.x * (1.0 - cos_theta_max);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.y;
+
+    vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+pub fn uniform_cone_pdf(cos_theta_max: f32) -> f32 {
+    1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+}
+
+/// Samples a direction in the hemisphere around +Z proportional to the cosine of its angle to
+/// +Z, by projecting points of the unit disk up to the hemisphere.
+pub fn cosine_sample_hemisphere(u: Vec2) -> Vec3 {
+    let d = uniform_sample_disk(u);
+    let z = (1.0 - d.length_squared()).max(0.0).sqrt();
+    vec3(d.x, d.y, z)
+}
+
 pub fn uniform_sample_disk(u: Vec2) -> Vec2 {
     let u_offset = 2.0 * u - Vec2::ONE;
 
@@ -63,3 +98,15 @@ pub fn uniform_sample_disk(u: Vec2) -> Vec2 {
         vec2(r * theta.cos(), r * theta.sin())
     }
 }
+
+/// The power heuristic with exponent 2 for multiple importance sampling, weighting a sample of
+/// the strategy with density `pdf_a` against one with density `pdf_b`.
+pub fn power_heuristic(pdf_a: f32, pdf_b: f32) -> f32 {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
@@ -0,0 +1,69 @@
+use std::rc::Rc;
+
+use glam::Vec3;
+
+use super::{interaction::Interaction, textures::Texture};
+
+/// The offset in texture coordinates for finite differences of bump maps.
+const BUMP_DELTA: f32 = 1e-3;
+
+/// Perturbs the shading normal of a surface, to add detail without adding geometry.
+pub enum NormalMap {
+    /// Normals in the tangent space of the surface, encoded as colors like in common normal map
+    /// images. The red and green channels point along increasing u and v.
+    Tangent(Rc<dyn Texture<Vec3>>),
+    /// A height field along the normal, scaled by `scale` in scene units.
+    Bump {
+        height: Rc<dyn Texture<f32>>,
+        scale: f32,
+    },
+}
+
+impl NormalMap {
+    /// Perturbs the shading normal of the interaction, seen from `wo`. Surfaces can't be seen
+    /// from below their shading normal, so the geometric normal is kept where the perturbed one
+    /// faces away from `wo`.
+    pub fn apply(&self, int: &mut Interaction, wo: Vec3) {
+        let ns = match self {
+            NormalMap::Tangent(texture) => {
+                let n = int.n;
+                let t = (int.dpdu - n * n.dot(int.dpdu)).normalize_or_zero();
+                if t == Vec3::ZERO {
+                    return;
+                }
+                let b = (int.dpdv - n * n.dot(int.dpdv) - t * t.dot(int.dpdv)).normalize_or_zero();
+                let b = if b == Vec3::ZERO { n.cross(t) } else { b };
+
+                let c = 2.0 * texture.evaluate(int) - Vec3::ONE;
+                c.x * t + c.y * b + c.z * n
+            }
+            NormalMap::Bump { height, scale } => {
+                let displace = height.evaluate(int);
+                let shifted_height = |dp: Vec3| {
+                    let shifted = Interaction {
+                        p: int.p + BUMP_DELTA * dp,
+                        ..*int
+                    };
+                    height.evaluate(&shifted)
+                };
+                let du = (shifted_height(int.dpdu) - displace) / BUMP_DELTA;
+                let dv = (shifted_height(int.dpdv) - displace) / BUMP_DELTA;
+
+                let dpdu = int.dpdu + scale * du * int.n;
+                let dpdv = int.dpdv + scale * dv * int.n;
+                dpdu.cross(dpdv)
+            }
+        };
+
+        let Some(ns) = ns.try_normalize() else {
+            return;
+        };
+        let ns = if ns.dot(int.n) < 0.0 { -ns } else { ns };
+
+        // the geometric normal faces wo, or the surface is seen from behind
+        let side = if wo.dot(int.n) < 0.0 { -1.0 } else { 1.0 };
+        if wo.dot(ns) * side > 0.0 {
+            int.ns = ns;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::f32::consts::PI;
+
+use glam::{vec3, Vec2, Vec3};
+
+use super::Frame;
+
+/// The Henyey-Greenstein phase function. The asymmetry `g` in (-1, 1) scatters light forward for
+/// positive values, backward for negative ones and uniformly for zero.
+#[derive(Clone, Copy)]
+pub struct HenyeyGreenstein {
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f32) -> Self {
+        // the density degenerates to a delta for |g| = 1
+        Self {
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+
+    /// The density of scattering light travelling along `direction` towards `scattered`.
+    pub fn p(&self, direction: Vec3, scattered: Vec3) -> f32 {
+        let g = self.g;
+        let cos_theta = direction.dot(scattered);
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.max(0.0).sqrt())
+    }
+
+    /// Samples the direction light travelling along `direction` is scattered to, exactly
+    /// proportional to the phase function, so the weight of the sample is 1.
+    pub fn sample(&self, direction: Vec3, u: Vec2) -> Vec3 {
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u.x
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u.x);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        }
+        .clamp(-1.0, 1.0);
+
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.y;
+        let local = vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        Frame::from_z(direction).to_world(local)
+    }
+}
@@ -1,17 +1,89 @@
-use std::rc::Rc;
+use std::{rc::Rc, sync::Arc};
 
 use embree4_sys::{RTCDevice, RTCGeometry};
+use glam::Vec3;
 
-use super::{materials::Material, shapes::Shape};
+use super::{
+    lights::LightLinking,
+    materials::Material,
+    media::{Medium, MediumInterface},
+    shapes::Shape,
+    textures::Texture,
+    NormalMap,
+};
 
 pub struct Primitive {
     pub shape: Box<dyn Shape>,
     pub material: Rc<dyn Material>,
+    /// Linking of the area lights created for an emissive material.
+    pub light_linking: LightLinking,
+    /// Light group of the area lights created for an emissive material.
+    pub light_group: Option<String>,
+    /// Perturbs the shading normal, independent of the material.
+    pub normal_map: Option<NormalMap>,
+    /// Cuts out the surface where the opacity is 0. Rays pass through partially opaque parts at
+    /// random, with the probability of the transparency.
+    pub opacity: Option<Rc<dyn Texture<f32>>>,
+    /// The media inside and outside of closed shapes. Rays crossing surfaces without media keep
+    /// travelling through the medium they were in.
+    pub medium_interface: Option<MediumInterface>,
 }
 
 impl Primitive {
     pub fn new(shape: Box<dyn Shape>, material: Rc<dyn Material>) -> Self {
-        Self { shape, material }
+        Self {
+            shape,
+            material,
+            light_linking: LightLinking::default(),
+            light_group: None,
+            normal_map: None,
+            opacity: None,
+            medium_interface: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_light_linking(mut self, light_linking: LightLinking) -> Self {
+        self.light_linking = light_linking;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_light_group(mut self, name: &str) -> Self {
+        self.light_group = Some(name.to_string());
+        self
+    }
+
+    /// Perturbs the shading normal with a tangent space normal map.
+    #[allow(dead_code)]
+    pub fn with_normal_map(mut self, normals: Rc<dyn Texture<Vec3>>) -> Self {
+        self.normal_map = Some(NormalMap::Tangent(normals));
+        self
+    }
+
+    /// Perturbs the shading normal with a height field, scaled by `scale` in scene units.
+    #[allow(dead_code)]
+    pub fn with_bump_map(mut self, height: Rc<dyn Texture<f32>>, scale: f32) -> Self {
+        self.normal_map = Some(NormalMap::Bump { height, scale });
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_opacity(mut self, opacity: Rc<dyn Texture<f32>>) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Fills the closed shape with the medium `inside`, surrounded by the medium `outside`. Use an
+    /// `InterfaceMaterial` for shapes that only bound a medium, without a visible surface.
+    #[allow(dead_code)]
+    pub fn with_media(
+        mut self,
+        inside: Option<Arc<dyn Medium>>,
+        outside: Option<Arc<dyn Medium>>,
+    ) -> Self {
+        self.medium_interface = Some(MediumInterface { inside, outside });
+        self
     }
 
     pub unsafe fn build_geometry(&self, id: u32, device: RTCDevice) -> RTCGeometry {
@@ -1,10 +1,18 @@
+use std::sync::Arc;
+
 use embree4_sys::RTCRay;
 use glam::Vec3;
 
+use super::{media::Medium, SampledWavelengths};
+
+#[derive(Clone)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
     pub time: f32,
+    /// The medium the ray travels through, `None` for vacuum.
+    pub medium: Option<Arc<dyn Medium>>,
+    pub wavelengths: SampledWavelengths,
 }
 
 impl Ray {
@@ -13,9 +21,21 @@ impl Ray {
             origin,
             direction: direction.normalize(),
             time,
+            medium: None,
+            wavelengths: SampledWavelengths::Rgb,
         }
     }
 
+    pub fn with_medium(mut self, medium: Option<Arc<dyn Medium>>) -> Self {
+        self.medium = medium;
+        self
+    }
+
+    pub fn with_wavelengths(mut self, wavelengths: SampledWavelengths) -> Self {
+        self.wavelengths = wavelengths;
+        self
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + t * self.direction
     }
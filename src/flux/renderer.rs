@@ -1,12 +1,12 @@
 use std::sync::{Arc, Mutex};
 
-use glam::{vec2, UVec2};
-use rand::{rngs::StdRng, SeedableRng};
+use glam::{vec2, UVec2, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 
 use super::{
     film::Film, integrators::Integrator, sampler::StratifiedSampler, updater::RenderUpdateEvent,
-    RenderUpdater, Scene,
+    RenderUpdater, RgbToSpectrumTable, SampledWavelengths, Scene,
 };
 
 pub struct Renderer {
@@ -14,6 +14,7 @@ pub struct Renderer {
     sampler: StratifiedSampler,
     num_passes: usize,
     updater: Option<Box<dyn RenderUpdater>>,
+    spectral: bool,
 }
 
 impl Renderer {
@@ -28,19 +29,33 @@ impl Renderer {
             sampler,
             num_passes,
             updater,
+            spectral: false,
         }
     }
 
-    pub fn render_film(&self, scene: &Scene) -> RenderResult {
+    /// Renders with sampled wavelengths instead of red, green and blue channels, which are
+    /// converted to sRGB for the film.
+    pub fn with_spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    pub fn render_film(&self, scene: &Scene, frame: u32) -> RenderResult {
+        // load the table for uplifting colors to spectra up front, instead of blocking all passes
+        if self.spectral {
+            RgbToSpectrumTable::get();
+        }
+
         let render_passes = (0..self.num_passes)
             .collect::<Vec<_>>()
             .into_par_iter()
-            .map(|pass| self.render_pass(scene, pass));
+            .map(|pass| self.render_pass(scene, frame, pass));
 
         let resolution = scene.camera.resolution();
 
         let shared = Arc::new(Mutex::new(SharedState {
             merged_film: Film::new(resolution),
+            merged_light_groups: vec![Film::new(resolution); scene.light_group_names().len()],
             passes_merged: 0,
             total_rays: 0,
         }));
@@ -48,6 +63,13 @@ impl Renderer {
         render_passes.for_each(|result| {
             let mut shared = shared.lock().unwrap();
             shared.merged_film.merge_tile(UVec2::ZERO, result.film);
+            for (merged, film) in shared
+                .merged_light_groups
+                .iter_mut()
+                .zip(result.light_groups)
+            {
+                merged.merge_tile(UVec2::ZERO, film);
+            }
             shared.passes_merged += 1;
             shared.total_rays += result.rays;
 
@@ -69,15 +91,17 @@ impl Renderer {
         let shared = Arc::try_unwrap(shared).unwrap().into_inner().unwrap();
         RenderResult {
             film: shared.merged_film,
+            light_groups: shared.merged_light_groups,
             rays: shared.total_rays,
         }
     }
 
-    fn render_pass(&self, scene: &Scene, pass: usize) -> RenderResult {
+    fn render_pass(&self, scene: &Scene, frame: u32, pass: usize) -> RenderResult {
         let resolution = scene.camera.resolution();
 
         let mut film = Film::new(resolution);
-        let mut rng = StdRng::seed_from_u64(pass.try_into().unwrap());
+        let mut light_groups = vec![Film::new(resolution); scene.light_group_names().len()];
+        let mut rng = StdRng::seed_from_u64(pass_seed(frame, pass));
         let mut rays = 0;
 
         for y in 0..resolution.y {
@@ -86,27 +110,61 @@ impl Renderer {
 
                 let camera_samples = self.sampler.camera_samples(p_raster, &mut rng);
                 for sample in camera_samples {
-                    let ray = scene.camera.ray(&sample);
-
-                    let result = self.integrator.li(scene, &ray, &mut rng);
-                    film.add_sample(sample.p_film, result.li, 1.0);
-                    rays += result.rays;
+                    match scene.camera.ray(&sample) {
+                        Some(camera_ray) => {
+                            let wavelengths = if self.spectral {
+                                SampledWavelengths::sample_visible(rng.gen())
+                            } else {
+                                SampledWavelengths::Rgb
+                            };
+                            let ray = camera_ray.ray.with_wavelengths(wavelengths);
+
+                            let result = self.integrator.li(scene, &ray, &mut rng);
+                            let li = wavelengths.to_rgb(result.li);
+                            film.add_sample(sample.p_film, camera_ray.weight * li, 1.0);
+                            for (group, li) in light_groups.iter_mut().zip(result.groups) {
+                                let li = wavelengths.to_rgb(li);
+                                group.add_sample(sample.p_film, camera_ray.weight * li, 1.0);
+                            }
+                            rays += result.rays;
+                        }
+                        None => {
+                            film.add_sample(sample.p_film, Vec3::ZERO, 1.0);
+                            for group in &mut light_groups {
+                                group.add_sample(sample.p_film, Vec3::ZERO, 1.0);
+                            }
+                        }
+                    }
                 }
             }
         }
 
-        RenderResult { film, rays }
+        RenderResult {
+            film,
+            light_groups,
+            rays,
+        }
     }
 }
 
+/// Combines frame and pass into a unique seed, so that consecutive frames of an animation don't
+/// share the same noise pattern. Frame 0 maps to the plain pass index.
+fn pass_seed(frame: u32, pass: usize) -> u64 {
+    ((frame as u64) << 32) | pass as u64
+}
+
 #[derive(Debug)]
 struct SharedState {
     merged_film: Film,
+    merged_light_groups: Vec<Film>,
     passes_merged: usize,
     total_rays: usize,
 }
 
 pub struct RenderResult {
     pub film: Film,
+    /// A film for every light group of the scene, in the order of `Scene::light_group_names`.
+    /// Their sum is the beauty film.
+    pub light_groups: Vec<Film>,
     pub rays: usize,
 }
@@ -0,0 +1,131 @@
+use std::sync::OnceLock;
+
+use glam::Vec3;
+
+use super::color::VISIBLE_WAVELENGTHS;
+
+/// A smooth spectrum in [0, 1], given by a sigmoid of a quadratic polynomial in the wavelength
+/// normalized to the visible range.
+#[derive(Clone, Copy, Debug)]
+pub struct RgbSigmoidPolynomial {
+    c: Vec3,
+}
+
+impl RgbSigmoidPolynomial {
+    pub fn evaluate(&self, lambda: f32) -> f32 {
+        let (start, end) = VISIBLE_WAVELENGTHS;
+        let t = (lambda - start) / (end - start);
+        sigmoid((self.c.x * t + self.c.y) * t + self.c.z)
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    if x.is_infinite() {
+        return if x > 0.0 { 1.0 } else { 0.0 };
+    }
+    0.5 + x / (2.0 * (1.0 + x * x).sqrt())
+}
+
+/// Finds smooth spectra that reproduce linear sRGB colors in [0, 1], after "A Low-Dimensional
+/// Function Space for Efficient Spectral Upsampling" by Jakob and Hanika. The polynomials are
+/// fitted with Gauss-Newton iterations over a grid of colors, indexed by the largest channel and
+/// the ratios of the other two to it, and interpolated in between. The fitting runs in the build
+/// script, see `build/rgb_to_spectrum.rs`.
+pub struct RgbToSpectrumTable {
+    /// The number of nodes along each axis of the grid.
+    resolution: usize,
+    /// The values of the largest channel at the nodes of the grid, denser towards 0 and 1.
+    z_nodes: Vec<f32>,
+    /// The coefficients for each largest channel, then z, y and x.
+    coefficients: Vec<Vec3>,
+}
+
+impl RgbToSpectrumTable {
+    /// The table for sRGB, which is loaded on first use.
+    pub fn get() -> &'static Self {
+        static TABLE: OnceLock<RgbToSpectrumTable> = OnceLock::new();
+        TABLE.get_or_init(|| {
+            Self::from_bytes(include_bytes!(concat!(
+                env!("OUT_DIR"),
+                "/rgb_to_spectrum.bin"
+            )))
+        })
+    }
+
+    /// Reads the table written by the build script.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let (resolution, values) = bytes.split_at(4);
+        let resolution = u32::from_le_bytes(resolution.try_into().unwrap()) as usize;
+        let values: Vec<f32> = values
+            .chunks_exact(4)
+            .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+            .collect();
+        let (z_nodes, coefficients) = values.split_at(resolution);
+
+        Self {
+            resolution,
+            z_nodes: z_nodes.to_vec(),
+            coefficients: coefficients.chunks_exact(3).map(Vec3::from_slice).collect(),
+        }
+    }
+
+    pub fn lookup(&self, rgb: Vec3) -> RgbSigmoidPolynomial {
+        let rgb = rgb.clamp(Vec3::ZERO, Vec3::ONE);
+
+        // grays are constant and can be solved exactly, including black and white
+        if rgb.x == rgb.y && rgb.y == rgb.z {
+            let v = rgb.x;
+            return RgbSigmoidPolynomial {
+                c: Vec3::new(0.0, 0.0, (v - 0.5) / (v * (1.0 - v)).sqrt()),
+            };
+        }
+
+        let max_channel = if rgb.x >= rgb.y && rgb.x >= rgb.z {
+            0
+        } else if rgb.y >= rgb.z {
+            1
+        } else {
+            2
+        };
+        let n = self.resolution;
+        let z = rgb[max_channel];
+        let x = rgb[(max_channel + 1) % 3] / z * (n - 1) as f32;
+        let y = rgb[(max_channel + 2) % 3] / z * (n - 1) as f32;
+
+        let xi = (x as usize).min(n - 2);
+        let yi = (y as usize).min(n - 2);
+        let zi = self
+            .z_nodes
+            .partition_point(|&node| node <= z)
+            .clamp(1, n - 1)
+            - 1;
+        let dx = x - xi as f32;
+        let dy = y - yi as f32;
+        let dz = (z - self.z_nodes[zi]) / (self.z_nodes[zi + 1] - self.z_nodes[zi]);
+
+        let c = |i: usize, j: usize, k: usize| {
+            self.coefficients[self.index(max_channel, zi + k, yi + j, xi + i)]
+        };
+        let lerp = |a: Vec3, b: Vec3, t: f32| a.lerp(b, t);
+        let c = lerp(
+            lerp(
+                lerp(c(0, 0, 0), c(1, 0, 0), dx),
+                lerp(c(0, 1, 0), c(1, 1, 0), dx),
+                dy,
+            ),
+            lerp(
+                lerp(c(0, 0, 1), c(1, 0, 1), dx),
+                lerp(c(0, 1, 1), c(1, 1, 1), dx),
+                dy,
+            ),
+            dz,
+        );
+
+        RgbSigmoidPolynomial { c }
+    }
+
+    fn index(&self, max_channel: usize, zi: usize, yi: usize, xi: usize) -> usize {
+        let n = self.resolution;
+        ((max_channel * n + zi) * n + yi) * n + xi
+    }
+}
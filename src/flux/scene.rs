@@ -1,20 +1,49 @@
-use std::ptr::null_mut;
+use std::{collections::HashMap, sync::Arc};
 
-use embree4_sys::{rtcIntersect1, RTCRay, RTCRayHit, RTC_INVALID_GEOMETRY_ID};
-use glam::vec3;
+use embree4_sys::{RTCRay, RTCRayHit, RTC_INVALID_GEOMETRY_ID};
+use glam::{vec3, Vec3};
+use log::{debug, trace};
+use rand::rngs::StdRng;
 
 use super::{
-    accel::EmbreeAccel, cameras::Camera, interaction::Interaction, lights::Light,
-    primitive::Primitive, ray::Ray,
+    accel::EmbreeAccel,
+    cameras::Camera,
+    interaction::Interaction,
+    lights::{build_light_sampler, AreaLight, Light, LightLinking, LightSampler, LightSamplerType},
+    media::Medium,
+    primitive::Primitive,
+    ray::Ray,
+    shapes::FrameUpdate,
 };
 
 pub struct Scene {
     pub primitives: Vec<Primitive>,
     pub accel: EmbreeAccel,
     pub camera: Box<dyn Camera>,
+    /// The medium the camera is in, like fog filling the whole scene.
+    pub camera_medium: Option<Arc<dyn Medium>>,
+    /// Whether the camera or any primitive has a medium, so shadow rays can't simply test for
+    /// occlusion.
+    has_media: bool,
+    /// The lights passed to the scene, followed by the area lights of emissive primitives.
     pub lights: Vec<Box<dyn Light>>,
+    pub light_sampler: Box<dyn LightSampler>,
+    light_sampler_type: LightSamplerType,
+    /// The linking of every light.
+    light_linking: Vec<LightLinking>,
+    scene_light_count: usize,
+    /// Maps the primitive and face index of emissive surfaces to their area light.
+    area_lights: HashMap<(usize, usize), usize>,
+    /// The names of the light groups, empty if the scene doesn't use any. Otherwise, the first
+    /// group collects all lights without a group.
+    light_group_names: Vec<String>,
+    /// The light group of every light.
+    light_groups: Vec<usize>,
 }
 
+/// The name of the light group for lights that weren't assigned to one.
+pub const DEFAULT_LIGHT_GROUP: &str = "default";
+
 // TODO: This is currently required for the progressive renderer to share the scene between
 // threads. However, we may be able to clone it. Take care of the shared resources between
 // the Embree API, since it counts references and can't know we copied/cloned the reference.
@@ -27,11 +56,144 @@ impl Scene {
         lights: Vec<Box<dyn Light>>,
     ) -> Self {
         let accel = unsafe { EmbreeAccel::build(&primitives) };
-        Self {
+        let light_sampler_type = LightSamplerType::default();
+        let has_media = primitives
+            .iter()
+            .any(|prim| prim.medium_interface.is_some());
+        let mut scene = Self {
             primitives,
             accel,
             camera,
+            camera_medium: None,
+            has_media,
+            light_sampler: build_light_sampler(light_sampler_type, &lights),
+            light_sampler_type,
+            light_linking: vec![LightLinking::default(); lights.len()],
+            scene_light_count: lights.len(),
+            area_lights: HashMap::new(),
+            light_group_names: vec![],
+            light_groups: vec![0; lights.len()],
             lights,
+        };
+        scene.build_area_lights();
+        scene
+    }
+
+    /// Places the camera in the medium, which fills the scene outside of closed shapes with
+    /// media of their own.
+    #[allow(dead_code)]
+    pub fn set_camera_medium(&mut self, medium: Arc<dyn Medium>) {
+        self.camera_medium = Some(medium);
+        self.has_media = true;
+    }
+
+    pub fn set_light_sampler(&mut self, sampler_type: LightSamplerType) {
+        self.light_sampler_type = sampler_type;
+        self.light_sampler = build_light_sampler(sampler_type, &self.lights);
+    }
+
+    /// Sets the linking of the light `light_idx`, as passed to the scene. Area lights use the
+    /// linking of their primitive.
+    #[allow(dead_code)]
+    pub fn set_light_linking(&mut self, light_idx: usize, linking: LightLinking) {
+        assert!(light_idx < self.scene_light_count);
+        self.light_linking[light_idx] = linking;
+    }
+
+    pub fn light_linking(&self, light_idx: usize) -> &LightLinking {
+        &self.light_linking[light_idx]
+    }
+
+    /// Assigns the light `light_idx`, as passed to the scene, to the named light group. Area
+    /// lights use the group of their primitive.
+    pub fn set_light_group(&mut self, light_idx: usize, name: &str) {
+        assert!(light_idx < self.scene_light_count);
+        self.light_groups[light_idx] = light_group_index(&mut self.light_group_names, name);
+    }
+
+    /// The names of the light groups, in the order of the radiance split by the integrator.
+    pub fn light_group_names(&self) -> &[String] {
+        &self.light_group_names
+    }
+
+    /// The light group of the light `light_idx`.
+    pub fn light_group(&self, light_idx: usize) -> usize {
+        self.light_groups[light_idx]
+    }
+
+    /// The index of the area light for the surface at the interaction, if it is emissive.
+    pub fn area_light(&self, int: &Interaction) -> Option<usize> {
+        self.area_lights.get(&(int.prim_idx, int.face_idx)).copied()
+    }
+
+    /// Creates an area light for every face of the emissive primitives and rebuilds the light
+    /// sampler.
+    fn build_area_lights(&mut self) {
+        self.lights.truncate(self.scene_light_count);
+        self.light_linking.truncate(self.scene_light_count);
+        self.light_groups.truncate(self.scene_light_count);
+        self.area_lights.clear();
+
+        for (prim_idx, primitive) in self.primitives.iter().enumerate() {
+            if !primitive.material.is_emissive() {
+                continue;
+            }
+
+            let group = match &primitive.light_group {
+                Some(name) => light_group_index(&mut self.light_group_names, name),
+                None => 0,
+            };
+
+            for (face_idx, shape) in primitive.shape.area_shapes().into_iter().enumerate() {
+                self.area_lights
+                    .insert((prim_idx, face_idx), self.lights.len());
+                self.lights.push(Box::new(AreaLight::new(
+                    prim_idx, face_idx, shape, primitive,
+                )));
+                self.light_linking.push(primitive.light_linking.clone());
+                self.light_groups.push(group);
+            }
+        }
+
+        debug!("{} area lights", self.area_lights.len());
+        self.light_sampler = build_light_sampler(self.light_sampler_type, &self.lights);
+    }
+
+    /// Evaluates the camera and all primitives for the given frame. If only transforms changed,
+    /// the acceleration structure is refit instead of rebuilt.
+    pub fn set_frame(&mut self, frame: u32) {
+        let frame = frame as f32;
+
+        self.camera.set_frame(frame);
+
+        let updates: Vec<FrameUpdate> = self
+            .primitives
+            .iter_mut()
+            .map(|prim| prim.shape.set_frame(frame))
+            .collect();
+
+        match updates.iter().max() {
+            Some(FrameUpdate::Geometry) => {
+                trace!("geometry changed, rebuilding accel");
+                unsafe { self.accel.rebuild(&self.primitives) };
+            }
+            Some(FrameUpdate::Transform) => {
+                let ids: Vec<usize> = updates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, update)| **update == FrameUpdate::Transform)
+                    .map(|(id, _)| id)
+                    .collect();
+                unsafe { self.accel.refit(&self.primitives, &ids) };
+            }
+            _ => {}
+        }
+
+        if updates
+            .iter()
+            .any(|update| *update != FrameUpdate::Unchanged)
+        {
+            self.build_area_lights();
         }
     }
 
@@ -41,7 +203,7 @@ impl Scene {
             hit: Default::default(),
         };
 
-        unsafe { rtcIntersect1(self.accel.scene, &mut ray_hit, null_mut()) };
+        unsafe { self.accel.intersect(&self.primitives, ray, &mut ray_hit) };
 
         if ray_hit.hit.geomID == RTC_INVALID_GEOMETRY_ID {
             None
@@ -54,16 +216,101 @@ impl Scene {
             let prim_idx = ray_hit.hit.geomID as usize;
             let primitive = &self.primitives[prim_idx];
 
+            let (dpdu, dpdv) = n.any_orthonormal_pair();
             let mut int = Interaction {
                 t,
                 p,
                 n,
+                ns: n,
+                dpdu,
+                dpdv,
                 time: ray.time,
+                wavelengths: ray.wavelengths,
                 primitive,
+                prim_idx,
+                face_idx: ray_hit.hit.primID as usize,
             };
             primitive.shape.adjust_interaction(&mut int);
 
+            int.ns = int.n;
+            if let Some(normal_map) = &primitive.normal_map {
+                normal_map.apply(&mut int, -ray.direction);
+            }
+
             Some(int)
         }
     }
+
+    /// Tests whether anything blocks the ray before it travels the distance `t_max`.
+    pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        let mut rtc_ray = RTCRay {
+            tfar: t_max,
+            ..RTCRay::from(ray)
+        };
+
+        unsafe { self.accel.occluded(&self.primitives, ray, &mut rtc_ray) };
+
+        // Embree sets tfar to -inf for occluded rays
+        rtc_ray.tfar == f32::NEG_INFINITY
+    }
+
+    /// The fraction of light passing along the ray up to the distance `t_max`, through the media
+    /// it travels and the boundaries of media it crosses. Zero if any other surface blocks the
+    /// ray. Also returns the number of rays traced.
+    pub fn transmittance(&self, ray: Ray, t_max: f32, rng: &mut StdRng) -> (Vec3, usize) {
+        if !self.has_media {
+            let tr = if self.occluded(&ray, t_max) {
+                Vec3::ZERO
+            } else {
+                Vec3::ONE
+            };
+            return (tr, 1);
+        }
+
+        let mut ray = ray;
+        let mut t_max = t_max;
+        let mut tr = Vec3::ONE;
+        let mut rays = 0;
+        loop {
+            rays += 1;
+            let hit = self.intersect(&ray).filter(|int| int.t < t_max);
+            if let Some(medium) = &ray.medium {
+                let t_end = hit.as_ref().map_or(t_max, |int| int.t);
+                tr *= medium.tr(&ray, t_end, rng);
+            }
+
+            let Some(int) = hit else {
+                return (tr, rays);
+            };
+            if !int.primitive.material.is_interface() || tr == Vec3::ZERO {
+                return (Vec3::ZERO, rays);
+            }
+
+            let medium = int.medium_towards(ray.direction, ray.medium.as_ref());
+            ray = int.spawn_ray_towards(ray.direction).with_medium(medium);
+            t_max -= int.t;
+        }
+    }
+}
+
+/// Returns the index of the named light group, adding it if necessary. The default group is
+/// added along with the first named one. Names end up in file names, so they can't be empty or
+/// contain path separators.
+fn light_group_index(names: &mut Vec<String>, name: &str) -> usize {
+    assert!(
+        !name.is_empty() && !name.contains(['/', '\\']),
+        "invalid light group name {:?}",
+        name
+    );
+    if names.is_empty() {
+        names.push(DEFAULT_LIGHT_GROUP.to_string());
+    }
+
+    match names.iter().position(|n| n == name) {
+        Some(idx) => idx,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    }
 }
@@ -1,12 +1,17 @@
 use std::ptr::null_mut;
 
 use embree4_sys::{
-    rtcNewGeometry, rtcSetGeometryBoundsFunction, rtcSetGeometryIntersectFunction,
+    rtcInvokeIntersectFilterFromGeometry, rtcInvokeOccludedFilterFromGeometry, rtcNewGeometry,
+    rtcSetGeometryBoundsFunction, rtcSetGeometryIntersectFunction, rtcSetGeometryOccludedFunction,
     rtcSetGeometryUserPrimitiveCount, RTCBounds, RTCBoundsFunctionArguments, RTCDevice,
-    RTCGeometry, RTCGeometryType, RTCIntersectFunctionNArguments, RTCRayHit,
+    RTCFilterFunctionNArguments, RTCGeometry, RTCGeometryType, RTCHit, RTCHitN,
+    RTCIntersectFunctionNArguments, RTCOccludedFunctionNArguments, RTCRay, RTCRayHit, RTCRayN,
+    RTCRayQueryContext,
 };
 use glam::{vec2, Vec2, Vec3};
 
+use crate::flux::interaction::Interaction;
+
 use super::Shape;
 
 pub struct Floor;
@@ -24,13 +29,19 @@ impl Shape for Floor {
         rtcSetGeometryUserPrimitiveCount(geometry, 1);
         rtcSetGeometryBoundsFunction(geometry, Some(bounds_fn), null_mut());
         rtcSetGeometryIntersectFunction(geometry, Some(intersect_fn));
+        rtcSetGeometryOccludedFunction(geometry, Some(occluded_fn));
 
         geometry
     }
 
-    fn uv(&self, p: Vec3) -> Vec2 {
+    fn uv(&self, p: Vec3, _face_idx: usize) -> Vec2 {
         vec2(p.x, p.z)
     }
+
+    fn adjust_interaction(&self, int: &mut Interaction) {
+        int.dpdu = Vec3::X;
+        int.dpdv = Vec3::Z;
+    }
 }
 
 unsafe extern "C" fn bounds_fn(args: *const RTCBoundsFunctionArguments) {
@@ -48,31 +59,87 @@ unsafe extern "C" fn bounds_fn(args: *const RTCBoundsFunctionArguments) {
 }
 
 unsafe extern "C" fn intersect_fn(args: *const RTCIntersectFunctionNArguments) {
-    let args = *args;
+    let args = &*args;
     assert_eq!(1, args.N);
 
-    let valid = *args.valid;
-    if valid == 0 {
+    if *args.valid == 0 {
         return;
     }
 
-    let ray_hit_ptr = args.rayhit as *mut RTCRayHit;
-    let ray_hit = &mut *ray_hit_ptr;
-
-    let t = -ray_hit.ray.org_y / ray_hit.ray.dir_y;
-    if t < ray_hit.ray.tnear || t > ray_hit.ray.tfar {
+    let ray_hit = &mut *(args.rayhit as *mut RTCRayHit);
+    let Some(t) = hit_distance(&ray_hit.ray) else {
         return;
-    }
+    };
+    let mut hit = floor_hit(&ray_hit.ray, args.primID, args.geomID, &*args.context);
 
-    ray_hit.hit.Ng_x = 0.0;
-    ray_hit.hit.Ng_z = 0.0;
-    if ray_hit.ray.org_y > 0.0 {
-        ray_hit.hit.Ng_y = 1.0;
+    // the filter sees the candidate distance, the previous one is restored if it rejects the hit
+    let tfar = ray_hit.ray.tfar;
+    ray_hit.ray.tfar = t;
+    let mut valid = -1;
+    let filter_args = RTCFilterFunctionNArguments {
+        valid: &mut valid,
+        geometryUserPtr: args.geometryUserPtr,
+        context: args.context,
+        ray: &mut ray_hit.ray as *mut RTCRay as *mut RTCRayN,
+        hit: &mut hit as *mut RTCHit as *mut RTCHitN,
+        N: 1,
+    };
+    rtcInvokeIntersectFilterFromGeometry(args, &filter_args);
+
+    if valid == 0 {
+        ray_hit.ray.tfar = tfar;
     } else {
-        ray_hit.hit.Ng_y = -1.0;
+        ray_hit.hit = hit;
     }
+}
 
-    ray_hit.ray.tfar = t;
-    ray_hit.hit.primID = args.primID;
-    ray_hit.hit.geomID = args.geomID;
+unsafe extern "C" fn occluded_fn(args: *const RTCOccludedFunctionNArguments) {
+    let args = &*args;
+    assert_eq!(1, args.N);
+
+    if *args.valid == 0 {
+        return;
+    }
+
+    let ray = &mut *(args.ray as *mut RTCRay);
+    let Some(t) = hit_distance(ray) else {
+        return;
+    };
+    let mut hit = floor_hit(ray, args.primID, args.geomID, &*args.context);
+
+    let tfar = ray.tfar;
+    ray.tfar = t;
+    let mut valid = -1;
+    let filter_args = RTCFilterFunctionNArguments {
+        valid: &mut valid,
+        geometryUserPtr: args.geometryUserPtr,
+        context: args.context,
+        ray: ray as *mut RTCRay as *mut RTCRayN,
+        hit: &mut hit as *mut RTCHit as *mut RTCHitN,
+        N: 1,
+    };
+    rtcInvokeOccludedFilterFromGeometry(args, &filter_args);
+
+    // Embree marks occluded rays with a negative infinite distance
+    ray.tfar = if valid == 0 { tfar } else { f32::NEG_INFINITY };
+}
+
+/// The distance along the ray to the plane y = 0, if it's within the valid range of the ray.
+fn hit_distance(ray: &RTCRay) -> Option<f32> {
+    let t = -ray.org_y / ray.dir_y;
+    (t >= ray.tnear && t <= ray.tfar).then_some(t)
+}
+
+/// The hit on the floor, with its normal facing the side the ray comes from.
+fn floor_hit(ray: &RTCRay, prim_id: u32, geom_id: u32, context: &RTCRayQueryContext) -> RTCHit {
+    RTCHit {
+        Ng_x: 0.0,
+        Ng_y: if ray.org_y > 0.0 { 1.0 } else { -1.0 },
+        Ng_z: 0.0,
+        u: 0.0,
+        v: 0.0,
+        primID: prim_id,
+        geomID: geom_id,
+        instID: context.instID,
+    }
 }
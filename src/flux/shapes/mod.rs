@@ -17,12 +17,39 @@ pub use trimesh::*;
 use embree4_sys::{RTCDevice, RTCGeometry};
 use glam::{Vec2, Vec3};
 
-use super::interaction::Interaction;
+use super::{interaction::Interaction, lights::AreaShape};
 
 pub trait Shape {
     unsafe fn build_geometry(&self, id: u32, device: RTCDevice) -> RTCGeometry;
 
-    fn uv(&self, p: Vec3) -> Vec2;
+    /// The texture coordinates of the point `p` on the face `face_idx`.
+    fn uv(&self, p: Vec3, face_idx: usize) -> Vec2;
 
     fn adjust_interaction(&self, _int: &mut Interaction) {}
+
+    /// The world space geometry for sampling the shape as an area light, with one entry per face
+    /// index reported by Embree. Empty for shapes that can't be sampled.
+    fn area_shapes(&self) -> Vec<AreaShape> {
+        vec![]
+    }
+
+    /// Evaluates the animated parameters of this shape for the given frame and reports what kind
+    /// of change the acceleration structure has to account for.
+    fn set_frame(&mut self, _frame: f32) -> FrameUpdate {
+        FrameUpdate::Unchanged
+    }
+
+    /// Writes the state of the current frame into the geometry previously created by
+    /// `build_geometry`. Only called for shapes that reported `FrameUpdate::Transform`.
+    unsafe fn update_geometry(&self, _geometry: RTCGeometry) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FrameUpdate {
+    /// Nothing changed, the geometry can be reused as is.
+    Unchanged,
+    /// Only the transform changed, so the geometry can be updated in place.
+    Transform,
+    /// The geometry itself changed and has to be rebuilt.
+    Geometry,
 }
@@ -4,6 +4,8 @@ use embree4_sys::{
 };
 use glam::{vec2, Vec2, Vec3};
 
+use crate::flux::interaction::Interaction;
+
 use super::Shape;
 
 pub struct Quad {
@@ -49,7 +51,7 @@ impl Shape for Quad {
         geometry
     }
 
-    fn uv(&self, p: Vec3) -> Vec2 {
+    fn uv(&self, p: Vec3, _face_idx: usize) -> Vec2 {
         let u_vec = self.vertices[1] - self.vertices[0];
         let v_vec = self.vertices[3] - self.vertices[0];
 
@@ -59,4 +61,9 @@ impl Shape for Quad {
 
         vec2(u, v)
     }
+
+    fn adjust_interaction(&self, int: &mut Interaction) {
+        int.dpdu = self.vertices[1] - self.vertices[0];
+        int.dpdv = self.vertices[3] - self.vertices[0];
+    }
 }
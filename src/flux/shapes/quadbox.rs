@@ -1,6 +1,8 @@
 use embree4_sys::{RTCDevice, RTCGeometry};
 use glam::{vec3, Vec3};
 
+use crate::flux::{interaction::Interaction, lights::AreaShape};
+
 use super::{Shape, TriangleMesh};
 
 pub struct QuadBox {
@@ -42,7 +44,15 @@ impl Shape for QuadBox {
         self.shape.build_geometry(id, device)
     }
 
-    fn uv(&self, p: Vec3) -> glam::Vec2 {
-        self.shape.uv(p)
+    fn uv(&self, p: Vec3, face_idx: usize) -> glam::Vec2 {
+        self.shape.uv(p, face_idx)
+    }
+
+    fn adjust_interaction(&self, int: &mut Interaction) {
+        self.shape.adjust_interaction(int);
+    }
+
+    fn area_shapes(&self) -> Vec<AreaShape> {
+        self.shape.area_shapes()
     }
 }
@@ -4,7 +4,9 @@ use embree4_sys::{
     rtcNewGeometry, rtcSetNewGeometryBuffer, RTCBufferType, RTCDevice, RTCFormat, RTCGeometry,
     RTCGeometryType,
 };
-use glam::{vec2, Vec2, Vec3};
+use glam::{vec2, vec3, Vec2, Vec3};
+
+use crate::flux::{interaction::Interaction, lights::AreaShape};
 
 use super::Shape;
 
@@ -38,7 +40,7 @@ impl Shape for Sphere {
         geometry
     }
 
-    fn uv(&self, p: Vec3) -> Vec2 {
+    fn uv(&self, p: Vec3, _face_idx: usize) -> Vec2 {
         let oc = p - self.center;
 
         let theta = (-oc.y / self.radius).acos();
@@ -46,4 +48,24 @@ impl Shape for Sphere {
 
         vec2(phi / (2.0 * PI), theta / PI)
     }
+
+    fn adjust_interaction(&self, int: &mut Interaction) {
+        let oc = int.p - self.center;
+
+        // the tangents are undefined at the poles
+        let rho = (oc.x * oc.x + oc.z * oc.z).sqrt();
+        if rho < 1e-6 * self.radius {
+            return;
+        }
+
+        int.dpdu = 2.0 * PI * vec3(oc.z, 0.0, -oc.x);
+        int.dpdv = PI * vec3(-oc.y * oc.x / rho, rho, -oc.y * oc.z / rho);
+    }
+
+    fn area_shapes(&self) -> Vec<AreaShape> {
+        vec![AreaShape::Sphere {
+            center: self.center,
+            radius: self.radius,
+        }]
+    }
 }
@@ -87,7 +87,7 @@ impl Shape for SubdivisionMesh {
         geometry
     }
 
-    fn uv(&self, _p: Vec3) -> Vec2 {
+    fn uv(&self, _p: Vec3, _face_idx: usize) -> Vec2 {
         // TODO: implement UV coordinates for triangle meshes
         Vec2::ZERO
     }
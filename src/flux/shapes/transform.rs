@@ -1,22 +1,46 @@
 use embree4_sys::{
     rtcAttachGeometryByID, rtcCommitGeometry, rtcCommitScene, rtcNewGeometry, rtcNewScene,
-    rtcReleaseGeometry, rtcSetGeometryInstancedScene, rtcSetGeometryTransform, RTCDevice,
-    RTCGeometry, RTCGeometryType,
+    rtcReleaseGeometry, rtcReleaseScene, rtcSetGeometryInstancedScene, rtcSetGeometryTransform,
+    rtcSetSceneFlags, RTCDevice, RTCGeometry, RTCGeometryType, RTCSceneFlags,
 };
 use glam::{Affine3A, Vec2, Vec3};
 
-use crate::flux::interaction::Interaction;
+use crate::flux::{animation::AnimatedTransform, interaction::Interaction, lights::AreaShape};
 
-use super::Shape;
+use super::{FrameUpdate, Shape};
 
 pub struct Transform {
     transform: Affine3A,
+    animation: Option<AnimatedTransform>,
     shape: Box<dyn Shape>,
 }
 
 impl Transform {
     pub fn new(transform: Affine3A, shape: Box<dyn Shape>) -> Self {
-        Self { transform, shape }
+        Self {
+            transform,
+            animation: None,
+            shape,
+        }
+    }
+
+    pub fn animated(animation: AnimatedTransform, shape: Box<dyn Shape>) -> Self {
+        Self {
+            transform: animation.evaluate(0.0),
+            animation: Some(animation),
+            shape,
+        }
+    }
+
+    unsafe fn set_transform(&self, instance: RTCGeometry) {
+        let xfm = self.transform.to_cols_array();
+        let xfm_ptr = xfm.as_ptr();
+        rtcSetGeometryTransform(
+            instance,
+            0,
+            embree4_sys::RTCFormat::FLOAT3X4_COLUMN_MAJOR,
+            xfm_ptr as _,
+        );
     }
 }
 
@@ -26,33 +50,66 @@ impl Shape for Transform {
         rtcCommitGeometry(shape_geom);
 
         let sub_scene = rtcNewScene(device);
+        // the opacity filter has to reach into instances
+        rtcSetSceneFlags(sub_scene, RTCSceneFlags::FILTER_FUNCTION_IN_ARGUMENTS);
         rtcAttachGeometryByID(sub_scene, shape_geom, id);
         rtcReleaseGeometry(shape_geom);
         rtcCommitScene(sub_scene);
 
         let instance = rtcNewGeometry(device, RTCGeometryType::INSTANCE);
         rtcSetGeometryInstancedScene(instance, sub_scene);
-
-        let xfm = self.transform.to_cols_array();
-        let xfm_ptr = xfm.as_ptr();
-        rtcSetGeometryTransform(
-            instance,
-            0,
-            embree4_sys::RTCFormat::FLOAT3X4_COLUMN_MAJOR,
-            xfm_ptr as _,
-        );
+        // the instance holds its own reference to the sub-scene
+        rtcReleaseScene(sub_scene);
+        self.set_transform(instance);
 
         instance
     }
 
-    fn uv(&self, _p: Vec3) -> Vec2 {
-        // TODO: implement UV coordinates for Transform geometry
-        Vec2::ZERO
+    fn uv(&self, p: Vec3, face_idx: usize) -> Vec2 {
+        let p_local = self.transform.inverse().transform_point3(p);
+        self.shape.uv(p_local, face_idx)
     }
 
     fn adjust_interaction(&self, int: &mut Interaction) {
+        // the inner shape works in its local space
+        let p = int.p;
+        int.p = self.transform.inverse().transform_point3(p);
         self.shape.adjust_interaction(int);
+        int.p = p;
+
         // we need to normalize the normal, because the transform could have scaled it
         int.n = self.transform.transform_vector3(int.n).normalize();
+        int.dpdu = self.transform.transform_vector3(int.dpdu);
+        int.dpdv = self.transform.transform_vector3(int.dpdv);
+    }
+
+    fn area_shapes(&self) -> Vec<AreaShape> {
+        self.shape
+            .area_shapes()
+            .iter()
+            .map(|shape| shape.transformed(&self.transform))
+            .collect()
+    }
+
+    fn set_frame(&mut self, frame: f32) -> FrameUpdate {
+        // The instanced sub-scene is baked into the instance geometry, so any change of the inner
+        // shape requires a full rebuild.
+        let inner_update = self.shape.set_frame(frame);
+        if inner_update != FrameUpdate::Unchanged {
+            return FrameUpdate::Geometry;
+        }
+
+        match &self.animation {
+            Some(animation) if animation.is_animated() => {
+                self.transform = animation.evaluate(frame);
+                FrameUpdate::Transform
+            }
+            _ => FrameUpdate::Unchanged,
+        }
+    }
+
+    unsafe fn update_geometry(&self, instance: RTCGeometry) {
+        self.set_transform(instance);
+        rtcCommitGeometry(instance);
     }
 }
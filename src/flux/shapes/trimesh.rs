@@ -2,18 +2,47 @@ use embree4_sys::{
     rtcNewGeometry, rtcSetNewGeometryBuffer, RTCBufferType, RTCDevice, RTCFormat, RTCGeometry,
     RTCGeometryType,
 };
-use glam::{Vec2, Vec3};
+use glam::{vec2, Vec2, Vec3};
+
+use crate::flux::{interaction::Interaction, lights::AreaShape};
 
 use super::Shape;
 
 pub struct TriangleMesh {
     vertices: Vec<Vec3>,
     indices: Vec<usize>,
+    /// Texture coordinates per vertex.
+    uvs: Option<Vec<Vec2>>,
 }
 
+#[allow(dead_code)]
 impl TriangleMesh {
     pub fn new(vertices: Vec<Vec3>, indices: Vec<usize>) -> Self {
-        Self { vertices, indices }
+        Self {
+            vertices,
+            indices,
+            uvs: None,
+        }
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<Vec2>) -> Self {
+        assert_eq!(uvs.len(), self.vertices.len());
+        self.uvs = Some(uvs);
+        self
+    }
+
+    fn triangle_indices(&self, face_idx: usize) -> Option<[usize; 3]> {
+        let indices = self.indices.get(3 * face_idx..3 * face_idx + 3)?;
+        Some([indices[0], indices[1], indices[2]])
+    }
+
+    /// The texture coordinates of the vertices of a triangle. Without texture coordinates, every
+    /// triangle covers the same half of the unit square.
+    fn triangle_uvs(&self, idx: [usize; 3]) -> [Vec2; 3] {
+        match &self.uvs {
+            Some(uvs) => idx.map(|i| uvs[i]),
+            None => [vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)],
+        }
     }
 }
 
@@ -54,8 +83,54 @@ impl Shape for TriangleMesh {
         geometry
     }
 
-    fn uv(&self, _p: Vec3) -> Vec2 {
-        // TODO: implement UV coordinates for triangle meshes
-        Vec2::ZERO
+    fn uv(&self, p: Vec3, face_idx: usize) -> Vec2 {
+        let Some(idx) = self.triangle_indices(face_idx) else {
+            return Vec2::ZERO;
+        };
+        let [p0, p1, p2] = idx.map(|i| self.vertices[i]);
+
+        // barycentric coordinates of the point within the triangle
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+        let ep = p - p0;
+        let d11 = e1.dot(e1);
+        let d12 = e1.dot(e2);
+        let d22 = e2.dot(e2);
+        let denom = d11 * d22 - d12 * d12;
+        if denom == 0.0 {
+            return Vec2::ZERO;
+        }
+        let b1 = (d22 * ep.dot(e1) - d12 * ep.dot(e2)) / denom;
+        let b2 = (d11 * ep.dot(e2) - d12 * ep.dot(e1)) / denom;
+        let b0 = 1.0 - b1 - b2;
+
+        let [uv0, uv1, uv2] = self.triangle_uvs(idx);
+        b0 * uv0 + b1 * uv1 + b2 * uv2
+    }
+
+    fn adjust_interaction(&self, int: &mut Interaction) {
+        let Some(idx) = self.triangle_indices(int.face_idx) else {
+            return;
+        };
+        let [p0, p1, p2] = idx.map(|i| self.vertices[i]);
+        let [uv0, uv1, uv2] = self.triangle_uvs(idx);
+
+        // solve p - p2 = (u - u2) dpdu + (v - v2) dpdv for the edges of the triangle
+        let (duv02, duv12) = (uv0 - uv2, uv1 - uv2);
+        let (dp02, dp12) = (p0 - p2, p1 - p2);
+        let determinant = duv02.x * duv12.y - duv02.y * duv12.x;
+        if determinant.abs() < 1e-9 {
+            return;
+        }
+
+        int.dpdu = (duv12.y * dp02 - duv02.y * dp12) / determinant;
+        int.dpdv = (duv02.x * dp12 - duv12.x * dp02) / determinant;
+    }
+
+    fn area_shapes(&self) -> Vec<AreaShape> {
+        (0..self.indices.len() / 3)
+            .filter_map(|face_idx| self.triangle_indices(face_idx))
+            .map(|idx| AreaShape::Triangle(idx.map(|i| self.vertices[i])))
+            .collect()
     }
 }
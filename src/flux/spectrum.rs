@@ -0,0 +1,207 @@
+use std::sync::OnceLock;
+
+use glam::{Vec3, Vec4};
+
+use super::{
+    color::{cie_xyz, xyz_to_srgb, VISIBLE_WAVELENGTHS},
+    RgbToSpectrumTable,
+};
+
+/// The wavelengths in nm at which spectra are sampled for the red, green and blue channels.
+pub const RGB_WAVELENGTHS: [f32; 3] = [650.0, 550.0, 450.0];
+
+/// The radiance emitted by a black body at `temperature` in Kelvin for the wavelength `lambda`
+/// in nm, normalized to a maximum of 1 over all wavelengths.
+pub fn blackbody(lambda: f32, temperature: f32) -> f32 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+
+    // Planck's law with the wavelength in meters
+    let planck = |lambda: f64| {
+        const C: f64 = 299_792_458.0;
+        const H: f64 = 6.626_070_15e-34;
+        const KB: f64 = 1.380_649e-23;
+        let t = temperature as f64;
+        2.0 * H * C * C / (lambda.powi(5) * ((H * C / (lambda * KB * t)).exp() - 1.0))
+    };
+
+    // Wien's displacement law gives the wavelength of the maximum
+    let lambda_max = 2.897_771_955e-3 / temperature as f64;
+    (planck(lambda as f64 * 1e-9) / planck(lambda_max)) as f32
+}
+
+/// The linear sRGB color of the normalized black body at `temperature` in Kelvin, scaled so that
+/// a spectrum of constant 1 has a luminance of 1.
+pub fn blackbody_rgb(temperature: f32) -> Vec3 {
+    let (start, end) = VISIBLE_WAVELENGTHS;
+    let steps = (end - start) as usize;
+
+    let (xyz, y_integral) =
+        (0..=steps)
+            .map(|i| start + i as f32)
+            .fold((Vec3::ZERO, 0.0), |(xyz, y), lambda| {
+                let cmf = cie_xyz(lambda);
+                (xyz + blackbody(lambda, temperature) * cmf, y + cmf.y)
+            });
+
+    xyz_to_srgb(xyz / y_integral).max(Vec3::ZERO)
+}
+
+/// A spectral distribution, linearly interpolated between samples at increasing wavelengths in
+/// nm and constant beyond the first and last one.
+#[derive(Clone, Debug)]
+pub struct PiecewiseLinearSpectrum {
+    lambdas: Vec<f32>,
+    values: Vec<f32>,
+}
+
+impl PiecewiseLinearSpectrum {
+    pub fn new(lambdas: Vec<f32>, values: Vec<f32>) -> Self {
+        assert!(!lambdas.is_empty() && lambdas.len() == values.len());
+        assert!(lambdas.windows(2).all(|w| w[0] < w[1]));
+        Self { lambdas, values }
+    }
+
+    pub fn evaluate(&self, lambda: f32) -> f32 {
+        let i = self.lambdas.partition_point(|&l| l <= lambda);
+        if i == 0 {
+            return self.values[0];
+        }
+        if i == self.lambdas.len() {
+            return self.values[i - 1];
+        }
+
+        let t = (lambda - self.lambdas[i - 1]) / (self.lambdas[i] - self.lambdas[i - 1]);
+        self.values[i - 1] + t * (self.values[i] - self.values[i - 1])
+    }
+}
+
+/// The number of wavelengths a path carries in spectral mode.
+pub const SPECTRUM_SAMPLES: usize = 4;
+
+/// The wavelengths that the radiance of a path is computed for. Spectral quantities are `Vec4`s,
+/// which hold the red, green and blue channels and a zero in RGB mode, and the values at the
+/// sampled wavelengths in spectral mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SampledWavelengths {
+    #[default]
+    Rgb,
+    /// Wavelengths in nm with their sampling densities, where a density of 0 marks a
+    /// wavelength that was terminated.
+    Spectral { lambda: Vec4, pdf: Vec4 },
+}
+
+#[allow(dead_code)]
+impl SampledWavelengths {
+    /// Samples a hero wavelength over the visible range, with a density that roughly follows
+    /// the sensitivity of the eye, and the others at equal offsets of `u`, wrapping around.
+    pub fn sample_visible(u: f32) -> Self {
+        let mut lambda = Vec4::ZERO;
+        let mut pdf = Vec4::ZERO;
+        for i in 0..SPECTRUM_SAMPLES {
+            let u = (u + i as f32 / SPECTRUM_SAMPLES as f32).fract();
+            lambda[i] = 538.0 - 138.888_89 * (0.856_910_6 - 1.827_502 * u).atanh();
+            pdf[i] = 0.003_939_804 / (0.0072 * (lambda[i] - 538.0)).cosh().powi(2);
+        }
+        Self::Spectral { lambda, pdf }
+    }
+
+    pub fn is_spectral(&self) -> bool {
+        matches!(self, Self::Spectral { .. })
+    }
+
+    /// The first wavelength, `None` in RGB mode.
+    pub fn hero(&self) -> Option<f32> {
+        match self {
+            Self::Rgb => None,
+            Self::Spectral { lambda, .. } => Some(lambda.x),
+        }
+    }
+
+    /// Keeps only the hero wavelength, for paths that split up by wavelength, e.g. when they
+    /// are refracted by dispersive glass. Returns `None` in RGB mode and if the others were
+    /// terminated before.
+    ///
+    /// Radiance computed at the returned wavelengths is weighted by `hero_weight`, so that it
+    /// can still be converted with the original wavelengths, like the rest of the path.
+    pub fn terminate_secondary(self) -> Option<Self> {
+        match self {
+            Self::Rgb => None,
+            Self::Spectral { lambda, pdf } => {
+                if pdf.y == 0.0 && pdf.z == 0.0 && pdf.w == 0.0 {
+                    return None;
+                }
+                Some(Self::Spectral {
+                    lambda,
+                    pdf: Vec4::new(pdf.x / SPECTRUM_SAMPLES as f32, 0.0, 0.0, 0.0),
+                })
+            }
+        }
+    }
+
+    /// The weight of radiance computed after `terminate_secondary`, which keeps the hero
+    /// wavelength for all of them and drops the others.
+    pub fn hero_weight() -> Vec4 {
+        Vec4::new(SPECTRUM_SAMPLES as f32, 0.0, 0.0, 0.0)
+    }
+
+    /// Uplifts a linear sRGB color, like a reflectance, emission or throughput, to a smooth
+    /// spectrum at these wavelengths. Colors brighter than 1 are scaled down for the lookup
+    /// and back up again.
+    pub fn uplift(&self, rgb: Vec3) -> Vec4 {
+        match self {
+            Self::Rgb => rgb.extend(0.0),
+            Self::Spectral { lambda, .. } => {
+                let rgb = rgb.max(Vec3::ZERO);
+                if rgb == Vec3::ZERO {
+                    return Vec4::ZERO;
+                }
+                let scale = rgb.max_element().max(1.0);
+                let spectrum = RgbToSpectrumTable::get().lookup(rgb / scale);
+                scale * Vec4::from(lambda.to_array().map(|l| spectrum.evaluate(l)))
+            }
+        }
+    }
+
+    /// Converts the spectral quantity `s` at these wavelengths to linear sRGB. In spectral mode,
+    /// the Monte Carlo estimate of CIE XYZ is white balanced so that a constant spectrum of 1
+    /// is white.
+    pub fn to_rgb(self, s: Vec4) -> Vec3 {
+        match self {
+            Self::Rgb => s.truncate(),
+            Self::Spectral { lambda, pdf } => {
+                let cie = Cie::get();
+                let xyz = (0..SPECTRUM_SAMPLES)
+                    .filter(|&i| pdf[i] > 0.0)
+                    .map(|i| s[i] * cie_xyz(lambda[i]) / pdf[i])
+                    .sum::<Vec3>()
+                    / (SPECTRUM_SAMPLES as f32 * cie.y_integral);
+                xyz_to_srgb(xyz) / cie.white_rgb
+            }
+        }
+    }
+}
+
+/// Integrals of the color matching functions over the visible range.
+struct Cie {
+    y_integral: f32,
+    /// The linear sRGB color of the constant spectrum of 1.
+    white_rgb: Vec3,
+}
+
+impl Cie {
+    fn get() -> &'static Self {
+        static CIE: OnceLock<Cie> = OnceLock::new();
+        CIE.get_or_init(|| {
+            let (start, end) = VISIBLE_WAVELENGTHS;
+            let xyz = (0..=(end - start) as usize)
+                .map(|i| cie_xyz(start + i as f32))
+                .sum::<Vec3>();
+            Cie {
+                y_integral: xyz.y,
+                white_rgb: xyz_to_srgb(xyz / xyz.y),
+            }
+        })
+    }
+}
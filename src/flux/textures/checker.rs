@@ -18,7 +18,7 @@ impl<T: Copy> CheckerTexture<T> {
 
 impl<T: Copy> Texture<T> for CheckerTexture<T> {
     fn evaluate(&self, int: &Interaction) -> T {
-        let uv = int.primitive.shape.uv(int.p);
+        let uv = int.primitive.shape.uv(int.p, int.face_idx);
 
         let u_int = (uv.x / self.scale).floor() as i32;
         let v_int = (uv.y / self.scale).floor() as i32;
@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use glam::{vec2, vec3, Vec3};
 use image::{DynamicImage, GenericImageView, Rgba};
 
-use crate::flux::interaction::Interaction;
+use crate::flux::{color::luminance, interaction::Interaction};
 
 use super::Texture;
 
@@ -9,6 +11,8 @@ pub struct ImageTexture {
     width: u32,
     height: u32,
     cache: Vec<Vec3>,
+    /// The alpha channel, 1 everywhere for images without one.
+    alpha: Vec<f32>,
 }
 
 impl ImageTexture {
@@ -16,35 +20,63 @@ impl ImageTexture {
         // build a directly accessible color cache
         let (width, height) = img.dimensions();
         let mut cache = vec![Vec3::ZERO; (width * height) as usize];
+        let mut alpha = vec![1.0; (width * height) as usize];
         for (x, y, pixel) in img.pixels() {
             let index = (y * width + x) as usize;
-            let color = match pixel {
-                Rgba([r, g, b, _]) => {
-                    vec3((r as f32) / 255.0, (g as f32) / 255.0, (b as f32) / 255.0)
-                }
-            };
-            cache[index] = color;
+            let Rgba([r, g, b, a]) = pixel;
+            cache[index] = vec3((r as f32) / 255.0, (g as f32) / 255.0, (b as f32) / 255.0);
+            alpha[index] = (a as f32) / 255.0;
         }
         Self {
             width,
             height,
             cache,
+            alpha,
         }
     }
-}
 
-impl Texture<Vec3> for ImageTexture {
-    fn evaluate(&self, int: &Interaction) -> Vec3 {
+    fn index(&self, int: &Interaction) -> usize {
         let uv = {
-            let uv = int.primitive.shape.uv(int.p);
+            let uv = int.primitive.shape.uv(int.p, int.face_idx);
             // flip y coordinate to image coordinate space
             vec2(uv.x, 1.0 - uv.y)
         };
 
-        let x = (uv.x * (self.width - 1) as f32) as u32;
-        let y = (uv.y * (self.height - 1) as f32) as u32;
-        let index = (y * self.width + x) as usize;
+        // clamp to the edge, e.g. for the offset lookups of bump mapping, negative values already
+        // saturate to 0
+        let x = ((uv.x * (self.width - 1) as f32) as u32).min(self.width - 1);
+        let y = ((uv.y * (self.height - 1) as f32) as u32).min(self.height - 1);
+        (y * self.width + x) as usize
+    }
+}
+
+impl Texture<Vec3> for ImageTexture {
+    fn evaluate(&self, int: &Interaction) -> Vec3 {
+        self.cache[self.index(int)]
+    }
+}
+
+/// The luminance of the image, e.g. to mask materials.
+impl Texture<f32> for ImageTexture {
+    fn evaluate(&self, int: &Interaction) -> f32 {
+        luminance(Texture::<Vec3>::evaluate(self, int))
+    }
+}
+
+/// The alpha channel of an image, e.g. as the opacity of leaves or fences.
+pub struct AlphaTexture {
+    image: Rc<ImageTexture>,
+}
+
+#[allow(dead_code)]
+impl AlphaTexture {
+    pub fn new(image: Rc<ImageTexture>) -> Self {
+        Self { image }
+    }
+}
 
-        self.cache[index]
+impl Texture<f32> for AlphaTexture {
+    fn evaluate(&self, int: &Interaction) -> f32 {
+        self.image.alpha[self.image.index(int)]
     }
 }
@@ -4,11 +4,13 @@ use crate::flux::interaction::Interaction;
 
 use super::Texture;
 
+#[allow(dead_code)]
 pub struct MultiplyTexture<T> {
     scale: f32,
     tex: Rc<dyn Texture<T>>,
 }
 
+#[allow(dead_code)]
 impl<T: Copy> MultiplyTexture<T> {
     pub fn new(scale: f32, tex: Rc<dyn Texture<T>>) -> Self {
         Self { scale, tex }
@@ -25,16 +25,21 @@ impl NoiseTexture {
     }
 }
 
-impl Texture<Vec3> for NoiseTexture {
-    fn evaluate(&self, int: &Interaction) -> Vec3 {
-        let uv = int.primitive.shape.uv(int.p);
+impl Texture<f32> for NoiseTexture {
+    fn evaluate(&self, int: &Interaction) -> f32 {
+        let uv = int.primitive.shape.uv(int.p, int.face_idx);
         let (width, height) = self.noise.size();
         let x = (uv.x * width as f32) as usize;
         let y = (uv.y * height as f32) as usize;
 
         let v = self.noise.get_value(x, y) as f32;
         // Map [-1, 1] to [0, 1]
-        let v = (v + 1.0) / 2.0;
-        Vec3::splat(v)
+        (v + 1.0) / 2.0
+    }
+}
+
+impl Texture<Vec3> for NoiseTexture {
+    fn evaluate(&self, int: &Interaction) -> Vec3 {
+        Vec3::splat(Texture::<f32>::evaluate(self, int))
     }
 }
@@ -14,7 +14,7 @@ impl UvTexture {
 
 impl Texture<Vec3> for UvTexture {
     fn evaluate(&self, int: &Interaction) -> Vec3 {
-        let uv = int.primitive.shape.uv(int.p);
+        let uv = int.primitive.shape.uv(int.p, int.face_idx);
         vec3(uv.x, 0.0, uv.y)
     }
 }
@@ -4,7 +4,7 @@ mod example_scenes;
 mod flux;
 
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
     time::{Duration, Instant},
 };
@@ -12,15 +12,23 @@ use std::{
 use crate::{
     example_scenes::{load_example_scene, ExampleScene},
     flux::{
+        animation::FrameRange,
+        cameras::{
+            load_lens_file, Aperture, ApertureMask, ApertureShape, Camera, CameraType,
+            FisheyeCamera, FisheyeMapping, OrthographicCamera, PerspectiveCamera, RealisticCamera,
+            SphericalCamera,
+        },
         integrators::Integrator,
         integrators::{AlbedoIntegrator, NormalIntegrator, PathTracingIntegrator},
-        Denoiser, RenderResult, Renderer, Scene, StratifiedSampler,
+        lights::LightSamplerType,
+        Denoiser, Film, RenderResult, Renderer, Scene, StratifiedSampler,
     },
 };
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use flux::DefaultRenderUpdater;
+use glam::Vec3;
 use log::{debug, info};
 use measure_time::{debug_time, trace_time};
 use num_format::{Locale, ToFormattedString};
@@ -32,23 +40,30 @@ fn main() -> Result<()> {
     let args = Args::parse();
     info!("{:?}", args);
 
-    let scene = load_scene(&args)?;
-    let renderer = setup_renderer(&args);
+    let mut scene = load_scene(&args)?;
+    if let Some(camera) = &args.camera {
+        scene.camera = setup_camera(camera, scene.camera.as_ref(), &args)?;
+    }
+    if let Some(aperture) = setup_aperture(&args)? {
+        scene.camera.set_aperture(aperture);
+    }
+    let light_sampler = LightSamplerType::from_str(&args.light_sampler)
+        .map_err(|parse_err| FluxError::LightSampler(args.light_sampler.clone(), parse_err))?;
+    scene.set_light_sampler(light_sampler);
 
-    let result = {
-        info!("rendering...");
+    let renderer = setup_renderer(&args);
 
-        let t_render_film = Instant::now();
-        let result = renderer.render_film(&scene);
-        let elapsed = t_render_film.elapsed();
+    match args.frames {
+        Some(frames) => render_animation(&mut scene, &renderer, frames, &args)?,
+        None => render_still(&scene, &renderer, &args)?,
+    }
 
-        print_stats(RenderStats {
-            total_rays: result.rays,
-            elapsed,
-        });
+    info!("done");
+    Ok(())
+}
 
-        result
-    };
+fn render_still(scene: &Scene, renderer: &Renderer, args: &Args) -> Result<()> {
+    let result = render(scene, renderer, 0);
 
     let output_dir = Path::new(&args.out_dir);
 
@@ -57,22 +72,101 @@ fn main() -> Result<()> {
 
     let beauty_raw_path = output_dir.join("output-raw.png");
     std::fs::copy(&beauty_path, beauty_raw_path)?;
+    result
+        .film
+        .to_rgb_f32_image()
+        .save(output_dir.join("output-raw.exr"))?;
+    save_light_groups(scene, &result, |name| {
+        output_dir.join(format!("output-group-{}.exr", name))
+    })?;
+
+    if let Some(reference) = &args.reference {
+        let rmse = compute_rmse(&result.film, reference)?;
+        info!("RMSE to reference: {:.6}", rmse);
+    }
+
+    let denoised = denoise(scene, &result.film, 0, args, |name| {
+        output_dir.join(format!("output-{}.png", name))
+    })?;
+    denoised.to_srgb_image().save(&beauty_path)?;
+
+    Ok(())
+}
 
-    let denoised = {
-        info!("denoising...");
+fn render_animation(
+    scene: &mut Scene,
+    renderer: &Renderer,
+    frames: FrameRange,
+    args: &Args,
+) -> Result<()> {
+    let output_dir = Path::new(&args.out_dir);
 
-        let denoiser = setup_denoiser(&scene, &args)?;
+    for frame in frames.iter() {
+        info!("frame {} / {}", frame, frames.end);
 
-        trace_time!("denoise filter");
-        unsafe { denoiser.denoise(&result.film) }
-    };
+        scene.set_frame(frame);
+        let result = render(scene, renderer, frame);
 
-    denoised.to_srgb_image().save(&beauty_path)?;
+        let raw_path = output_dir.join(format!("output-raw.{:04}.exr", frame));
+        result.film.to_rgb_f32_image().save(&raw_path)?;
+        save_light_groups(scene, &result, |name| {
+            output_dir.join(format!("output-group-{}.{:04}.exr", name, frame))
+        })?;
+
+        let denoised = denoise(scene, &result.film, frame, args, |name| {
+            output_dir.join(format!("output-{}.{:04}.png", name, frame))
+        })?;
+        let beauty_path = output_dir.join(format!("output.{:04}.exr", frame));
+        denoised.to_rgb_f32_image().save(&beauty_path)?;
+    }
 
-    info!("done");
     Ok(())
 }
 
+/// Saves the linear radiance of every light group. Like the raw beauty, they aren't denoised, so
+/// they sum to it.
+fn save_light_groups(
+    scene: &Scene,
+    result: &RenderResult,
+    path: impl Fn(&str) -> PathBuf,
+) -> Result<()> {
+    for (name, film) in scene.light_group_names().iter().zip(&result.light_groups) {
+        film.to_rgb_f32_image().save(path(name))?;
+    }
+
+    Ok(())
+}
+
+fn render(scene: &Scene, renderer: &Renderer, frame: u32) -> RenderResult {
+    info!("rendering...");
+
+    let t_render_film = Instant::now();
+    let result = renderer.render_film(scene, frame);
+    let elapsed = t_render_film.elapsed();
+
+    print_stats(RenderStats {
+        total_rays: result.rays,
+        elapsed,
+    });
+
+    result
+}
+
+fn denoise(
+    scene: &Scene,
+    film: &Film,
+    frame: u32,
+    args: &Args,
+    path: impl Fn(&str) -> PathBuf,
+) -> Result<Film> {
+    info!("denoising...");
+
+    let denoiser = setup_denoiser(scene, frame, args, path)?;
+
+    trace_time!("denoise filter");
+    Ok(unsafe { denoiser.denoise(film) })
+}
+
 fn print_stats(stats: RenderStats) {
     info!("render finished in {:.3?}", stats.elapsed);
 
@@ -110,28 +204,33 @@ fn setup_renderer(args: &Args) -> Renderer {
         ))
     };
 
-    Renderer::new(integrator, sampler, num_passes, Some(updater))
+    Renderer::new(integrator, sampler, num_passes, Some(updater)).with_spectral(args.spectral)
 }
 
-fn setup_denoiser(scene: &Scene, args: &Args) -> Result<Denoiser> {
+/// Renders the auxiliary channels for the denoiser and saves them, both raw and denoised, to the
+/// paths given for their names.
+fn setup_denoiser(
+    scene: &Scene,
+    frame: u32,
+    args: &Args,
+    path: impl Fn(&str) -> PathBuf,
+) -> Result<Denoiser> {
     debug!("initializing denoiser");
 
-    let output_dir = Path::new(&args.out_dir);
-
-    let albedo_path = output_dir.join("output-albedo.png");
+    let albedo_path = path("albedo");
     let albedo = {
         trace_time!("rendering albedo channel");
 
-        let result = render_aux_channel(scene, Box::new(AlbedoIntegrator::new()), args);
+        let result = render_aux_channel(scene, Box::new(AlbedoIntegrator::new()), frame, args);
         result.film.to_srgb_image().save(&albedo_path)?;
         result.film
     };
 
-    let normal_path = output_dir.join("output-normal.png");
+    let normal_path = path("normal");
     let normal = {
         trace_time!("rendering normal channel");
 
-        let result = render_aux_channel(scene, Box::new(NormalIntegrator::new()), args);
+        let result = render_aux_channel(scene, Box::new(NormalIntegrator::new()), frame, args);
         result.film.to_srgb_image().save(&normal_path)?;
 
         // OIDN expects normals to be in range [-1, 1], but the integrator generates colors in
@@ -144,7 +243,7 @@ fn setup_denoiser(scene: &Scene, args: &Args) -> Result<Denoiser> {
         Denoiser::new(scene.camera.resolution(), &albedo, &normal)
     };
 
-    let albedo_raw_path = output_dir.join("output-albedo-raw.png");
+    let albedo_raw_path = path("albedo-raw");
     std::fs::copy(&albedo_path, albedo_raw_path)?;
     denoiser
         .albedo_denoised
@@ -152,7 +251,7 @@ fn setup_denoiser(scene: &Scene, args: &Args) -> Result<Denoiser> {
         .save(&albedo_path)?;
 
     // again, we need to map the normals back to our domain of [0, 1]
-    let normal_raw_path = output_dir.join("output-normal-raw.png");
+    let normal_raw_path = path("normal-raw");
     std::fs::copy(&normal_path, normal_raw_path)?;
     denoiser
         .normal_denoised
@@ -172,19 +271,167 @@ fn load_scene(args: &Args) -> Result<Scene> {
     Ok(load_example_scene(example_scene))
 }
 
-fn render_aux_channel(scene: &Scene, integrator: Box<dyn Integrator>, args: &Args) -> RenderResult {
+/// Replaces the camera of the loaded scene, keeping its resolution, view and animation.
+fn setup_camera(camera: &str, scene_camera: &dyn Camera, args: &Args) -> Result<Box<dyn Camera>> {
+    let camera_type = CameraType::from_str(camera)
+        .map_err(|parse_err| FluxError::Camera(camera.to_string(), parse_err))?;
+
+    let resolution = scene_camera.resolution();
+    let view_matrix = scene_camera.view_matrix();
+    let position = view_matrix.transform_point3(Vec3::ZERO);
+    let look_at = position + view_matrix.transform_vector3(Vec3::Z);
+
+    // thin lens depth of field, which needs a focus distance once the lens has a size
+    let thin_lens = || -> Result<(f32, f32)> {
+        if args.lens_radius <= 0.0 {
+            return Ok((0.0, 1.0));
+        }
+        let focus_dist = args
+            .focus_dist
+            .ok_or_else(|| FluxError::MissingCameraArg(camera.to_string(), "focus-dist"))?;
+        Ok((args.lens_radius, focus_dist))
+    };
+
+    let mut camera: Box<dyn Camera> = match camera_type {
+        CameraType::Perspective => {
+            let (lens_radius, focus_dist) = thin_lens()?;
+            Box::new(PerspectiveCamera::new(
+                resolution,
+                position,
+                look_at,
+                args.fov,
+                lens_radius,
+                focus_dist,
+            ))
+        }
+        CameraType::Orthographic => {
+            let size = args
+                .ortho_size
+                .ok_or_else(|| FluxError::MissingCameraArg(camera.to_string(), "ortho-size"))?;
+            let (lens_radius, focus_dist) = thin_lens()?;
+            Box::new(OrthographicCamera::new(
+                resolution,
+                position,
+                look_at,
+                size,
+                lens_radius,
+                focus_dist,
+            ))
+        }
+        CameraType::Spherical => Box::new(SphericalCamera::new(resolution, position, look_at)),
+        CameraType::Ods => Box::new(SphericalCamera::stereo(
+            resolution, position, look_at, args.ipd,
+        )),
+        CameraType::FisheyeEquidistant => Box::new(FisheyeCamera::new(
+            resolution,
+            position,
+            look_at,
+            args.fisheye_fov,
+            FisheyeMapping::Equidistant,
+        )),
+        CameraType::FisheyeEquisolid => Box::new(FisheyeCamera::new(
+            resolution,
+            position,
+            look_at,
+            args.fisheye_fov,
+            FisheyeMapping::Equisolid,
+        )),
+        CameraType::Realistic => {
+            let focus_dist = args
+                .focus_dist
+                .ok_or_else(|| FluxError::MissingCameraArg(camera.to_string(), "focus-dist"))?;
+            let elements = load_lens_file(&args.lens_file)?;
+            Box::new(
+                RealisticCamera::new(
+                    resolution,
+                    position,
+                    look_at,
+                    elements,
+                    args.aperture,
+                    focus_dist,
+                    args.film_diagonal,
+                )
+                .with_context(|| format!("Failed to set up the lens {:?}", args.lens_file))?,
+            )
+        }
+    };
+
+    if let Some(keyframes) = scene_camera.keyframes() {
+        camera.set_keyframes(keyframes.clone());
+    }
+
+    Ok(camera)
+}
+
+fn setup_aperture(args: &Args) -> Result<Option<Aperture>> {
+    let shape = if let Some(path) = &args.aperture_mask {
+        ApertureShape::Mask(ApertureMask::load(path)?)
+    } else if let Some(blades) = args.aperture_blades {
+        ApertureShape::Polygon {
+            blades,
+            rotation: args.aperture_rotation,
+        }
+    } else if args.anamorphic_squeeze != 1.0 {
+        ApertureShape::Circle
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        Aperture::new(shape).with_squeeze(args.anamorphic_squeeze),
+    ))
+}
+
+/// The root mean squared error of the film to a reference rendering of the same resolution.
+fn compute_rmse(film: &Film, reference_path: &str) -> Result<f32> {
+    let reference = image::open(reference_path)
+        .with_context(|| format!("Failed to load reference image {:?}", reference_path))?
+        .into_rgb32f();
+    let image = film.to_rgb_f32_image();
+
+    if reference.dimensions() != image.dimensions() {
+        bail!(
+            "Reference image {:?} has a resolution of {:?}, expected {:?}",
+            reference_path,
+            reference.dimensions(),
+            image.dimensions()
+        );
+    }
+
+    let squared_error: f64 = image
+        .as_raw()
+        .iter()
+        .zip(reference.as_raw())
+        .map(|(a, b)| ((a - b) as f64).powi(2))
+        .sum();
+
+    Ok((squared_error / image.as_raw().len() as f64).sqrt() as f32)
+}
+
+fn render_aux_channel(
+    scene: &Scene,
+    integrator: Box<dyn Integrator>,
+    frame: u32,
+    args: &Args,
+) -> RenderResult {
     let sampler = StratifiedSampler::new(args.aux_spp);
 
     let passes = args.aux_sweeps * num_cpus::get();
     let renderer = Renderer::new(integrator, sampler, passes, None);
 
-    renderer.render_film(scene)
+    renderer.render_film(scene, frame)
 }
 
 #[derive(Debug, thiserror::Error)]
 enum FluxError {
     #[error("Failed to parse scene '{0}': {1}")]
     Scene(String, ParseError),
+    #[error("Failed to parse camera '{0}': {1}")]
+    Camera(String, ParseError),
+    #[error("Failed to parse light sampler '{0}': {1}")]
+    LightSampler(String, ParseError),
+    #[error("Camera '{0}' requires --{1} to be set")]
+    MissingCameraArg(String, &'static str),
 }
 
 struct RenderStats {
@@ -234,4 +481,79 @@ pub struct Args {
     /// Update interval for intermediate render results
     #[arg(long = "update-interval", short = 'u', default_value = "1")]
     update_interval: u64,
+
+    /// Inclusive range of frames to render as an animation, e.g. 1..120
+    #[arg(long = "frames")]
+    frames: Option<FrameRange>,
+
+    /// Replaces the scene camera, keeping its view (perspective, orthographic, spherical, ods,
+    /// fisheye-equidistant, fisheye-equisolid, realistic)
+    #[arg(long = "camera")]
+    camera: Option<String>,
+
+    /// Horizontal field of view in degrees for the perspective camera override
+    #[arg(long = "fov", default_value = "45")]
+    fov: f32,
+
+    /// Width of the view window in world units for the orthographic camera
+    #[arg(long = "ortho-size")]
+    ortho_size: Option<f32>,
+
+    /// Field of view in degrees of the image circle for fisheye cameras
+    #[arg(long = "fisheye-fov", default_value = "180")]
+    fisheye_fov: f32,
+
+    /// Interpupillary distance in world units for the stereo (ODS) camera
+    #[arg(long = "ipd", default_value = "0.064")]
+    ipd: f32,
+
+    /// Lens prescription table for the realistic camera
+    #[arg(long = "lens-file", default_value = "./assets/lenses/dgauss.50mm.dat")]
+    lens_file: String,
+
+    /// Aperture stop diameter in mm for the realistic camera
+    #[arg(long = "aperture", default_value = "10")]
+    aperture: f32,
+
+    /// Lens radius in world units for depth of field with the perspective and orthographic
+    /// cameras
+    #[arg(long = "lens-radius", default_value = "0")]
+    lens_radius: f32,
+
+    /// Distance in world units (meters) the camera is focused on. Required by the realistic
+    /// camera and by the others when --lens-radius is set
+    #[arg(long = "focus-dist")]
+    focus_dist: Option<f32>,
+
+    /// Film diagonal in mm for the realistic camera
+    #[arg(long = "film-diagonal", default_value = "35")]
+    film_diagonal: f32,
+
+    /// Number of aperture blades, giving polygonal bokeh for cameras with depth of field
+    #[arg(long = "aperture-blades")]
+    aperture_blades: Option<u32>,
+
+    /// Rotation of the aperture blades in degrees
+    #[arg(long = "aperture-rotation", default_value = "0")]
+    aperture_rotation: f32,
+
+    /// Grayscale image of the aperture shape, white being open. Takes precedence over blades
+    #[arg(long = "aperture-mask")]
+    aperture_mask: Option<String>,
+
+    /// Horizontal squeeze of anamorphic lenses, values above 1 give tall oval bokeh
+    #[arg(long = "anamorphic-squeeze", default_value = "1")]
+    anamorphic_squeeze: f32,
+
+    /// Strategy for choosing a light to sample at each path vertex (uniform, power, tree)
+    #[arg(long = "light-sampler", default_value = "tree")]
+    light_sampler: String,
+
+    /// Render with sampled wavelengths instead of RGB, converted to sRGB through CIE XYZ
+    #[arg(long = "spectral")]
+    spectral: bool,
+
+    /// Raw EXR rendering to report the RMSE of the beauty image against, for noise comparisons
+    #[arg(long = "reference")]
+    reference: Option<String>,
 }